@@ -1,130 +1,213 @@
+mod selector;
+
 use proc_macro::TokenStream;
 use quote::quote;
+use syn::ext::IdentExt;
 use syn::{
     parse_macro_input, Attribute, Data, DeriveInput, Fields, GenericArgument, PathArguments, Type,
 };
 
 /// Collect decorators from field types at compile time
-/// This walks the type tree and generates calls to collect decorators from nested types
+/// This walks the type tree and generates calls to collect decorators from nested types.
+/// Enum variants are walked too, whether their fields are named or positional.
+/// Types named by `#[spytial(include = "...")]` on a field, or in `includes`
+/// for the container, are probed as well.
 fn collect_field_type_decorators(
-    data: &Data,
-    self_type_name: &str,
+    input: &DeriveInput,
+    includes: &[Type],
 ) -> Vec<proc_macro2::TokenStream> {
     let mut field_decorators = Vec::new();
-    let mut seen_types = std::collections::HashSet::new();
-
-    // Add the self type to seen_types to prevent self-referential includes
-    seen_types.insert(self_type_name.to_string());
+    let mut walk = TypeWalk::new(input);
 
-    if let Data::Struct(data_struct) = data {
-        match &data_struct.fields {
-            Fields::Named(fields) => {
-                for field in &fields.named {
-                    field_decorators.extend(analyze_field_type(&field.ty, &mut seen_types));
-                }
-            }
-            Fields::Unnamed(fields) => {
-                for field in &fields.unnamed {
-                    field_decorators.extend(analyze_field_type(&field.ty, &mut seen_types));
-                }
-            }
-            Fields::Unit => {}
+    let fields: Vec<&Fields> = match &input.data {
+        Data::Struct(data_struct) => vec![&data_struct.fields],
+        Data::Enum(data_enum) => data_enum.variants.iter().map(|v| &v.fields).collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    for field in fields.into_iter().flatten() {
+        field_decorators.extend(walk.field_type(&field.ty));
+        // Malformed includes are reported by `field_decorator_calls`.
+        for ty in spytial_includes(&field.attrs).unwrap_or_default() {
+            field_decorators.extend(walk.include(&ty));
         }
     }
+    for ty in includes {
+        field_decorators.extend(walk.include(ty));
+    }
 
     field_decorators
 }
 
-/// Analyze a field type and generate decorator-collection calls for nested types.
-///
-/// Containers (`Vec`, `Option`, `Box`, `Rc`, `Arc`, `RefCell`, `Cell`,
-/// `VecDeque`, `LinkedList`) are unwrapped to reach the inner type.
-/// Primitives and standard collections are skipped (they can never carry
-/// decorators).  Everything else gets a probe call via [`DecoProbe`] — if the
-/// type implements `HasSpytialDecorators` the real decorators are returned;
-/// otherwise the probe safely returns an empty set.
-fn analyze_field_type(
-    ty: &Type,
-    seen_types: &mut std::collections::HashSet<String>,
-) -> Vec<proc_macro2::TokenStream> {
-    match ty {
-        Type::Path(type_path) => {
-            if let Some(segment) = type_path.path.segments.last() {
+/// The state of a walk over the field types of one derive input.
+struct TypeWalk<'a> {
+    /// The deriving type's own name, never probed (it would recurse).
+    self_name: String,
+    /// Its type parameters, resolved per instantiation.
+    params: Vec<&'a syn::Ident>,
+    /// Those of `params` bounded by `HasSpytialDecorators`, which can be
+    /// probed directly.
+    bounded: Vec<String>,
+    /// Types probed so far, as written, so each is probed once.
+    seen: std::collections::HashSet<String>,
+}
+
+impl<'a> TypeWalk<'a> {
+    fn new(input: &'a DeriveInput) -> Self {
+        let params: Vec<&syn::Ident> = input
+            .generics
+            .type_params()
+            .map(|param| &param.ident)
+            .collect();
+        let names_trait = |bound: &syn::TypeParamBound| match bound {
+            syn::TypeParamBound::Trait(bound) => bound
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "HasSpytialDecorators"),
+            _ => false,
+        };
+        let mut bounded: Vec<String> = input
+            .generics
+            .type_params()
+            .filter(|param| param.bounds.iter().any(names_trait))
+            .map(|param| param.ident.to_string())
+            .collect();
+        if let Some(where_clause) = &input.generics.where_clause {
+            for predicate in &where_clause.predicates {
+                if let syn::WherePredicate::Type(predicate) = predicate {
+                    if predicate.bounds.iter().any(names_trait) {
+                        let ty = &predicate.bounded_ty;
+                        bounded.push(quote!(#ty).to_string());
+                    }
+                }
+            }
+        }
+        TypeWalk {
+            self_name: input.ident.to_string(),
+            params,
+            bounded,
+            seen: std::collections::HashSet::new(),
+        }
+    }
+
+    /// Analyze a field type and generate decorator-collection calls for nested types.
+    ///
+    /// Containers (`Vec`, `Option`, `Box`, `Rc`, `Arc`, `Weak`, `RefCell`, `Cell`,
+    /// `Mutex`, `RwLock`, `VecDeque`, `LinkedList`, `BinaryHeap`, `HashMap`,
+    /// `BTreeMap`, `HashSet`, `BTreeSet`, `Result`, `Cow`, caraspace's
+    /// `SharedRef`) are unwrapped to reach every type argument, as are tuples,
+    /// arrays, slices and references.
+    /// Primitives can never carry decorators and are skipped.  The deriving
+    /// type's own parameters are looked up per instantiation (see
+    /// [`TypeWalk::param`]).  Everything else gets a probe call via
+    /// [`DecoProbe`], with its path and type arguments as written — if the
+    /// type implements `HasSpytialDecorators` the real decorators are
+    /// returned; otherwise the probe safely returns an empty set.  The type
+    /// arguments of a probed type are walked too, so `Wrapper<Person>`
+    /// collects `Person`'s decorators even when `Wrapper` cannot.
+    fn field_type(&mut self, ty: &Type) -> Vec<proc_macro2::TokenStream> {
+        match ty {
+            Type::Path(type_path) => {
+                let Some(segment) = type_path.path.segments.last() else {
+                    return Vec::new();
+                };
                 let name = segment.ident.to_string();
+                let mut out = Vec::new();
                 match name.as_str() {
-                    // Containers: unwrap to reach the inner type
-                    "Vec" | "Option" | "Box" | "Rc" | "Arc" | "RefCell" | "Cell" | "VecDeque"
-                    | "LinkedList" => {
-                        if let PathArguments::AngleBracketed(args) = &segment.arguments {
-                            if let Some(GenericArgument::Type(inner)) = args.args.first() {
-                                return analyze_inner_type(inner, seen_types);
-                            }
-                        }
-                    }
-                    // Primitives and std collections: can never have decorators
+                    // Containers: walk every type argument
+                    "Vec" | "Option" | "Box" | "Rc" | "Arc" | "RefCell" | "Cell" | "Mutex"
+                    | "RwLock" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashMap"
+                    | "BTreeMap" | "HashSet" | "BTreeSet" | "Result" | "Cow" | "SharedRef"
+                    | "Weak" => {}
+                    // Primitives and markers: can never have decorators
                     "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32"
                     | "u64" | "u128" | "usize" | "f32" | "f64" | "bool" | "char" | "String"
-                    | "str" | "Result" | "HashMap" | "HashSet" | "BTreeMap" | "BTreeSet" => {}
+                    | "str" | "PhantomData" => return out,
+                    // The deriving type itself
+                    "Self" => return out,
+                    _ if name == self.self_name => return out,
+                    // One of its type parameters
+                    _ if type_path.qself.is_none()
+                        && type_path.path.get_ident().is_some()
+                        && self.params.iter().any(|p| **p == name) =>
+                    {
+                        return self.param(&segment.ident);
+                    }
                     // Everything else: safe to probe
                     _ => {
-                        if !seen_types.contains(&name) {
-                            seen_types.insert(name.clone());
-                            return vec![generate_probe_call(&name)];
+                        if self.seen.insert(quote!(#ty).to_string()) {
+                            out.push(generate_probe_call(ty));
                         }
                     }
                 }
-            }
-        }
-        _ => {}
-    }
-    Vec::new()
-}
-
-/// Recursively unwrap container generics (`Option<Box<T>>` → `T`), then
-/// probe the inner type.
-fn analyze_inner_type(
-    ty: &Type,
-    seen_types: &mut std::collections::HashSet<String>,
-) -> Vec<proc_macro2::TokenStream> {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            let name = segment.ident.to_string();
-            match name.as_str() {
-                "Vec" | "Option" | "Box" | "Rc" | "Arc" | "RefCell" | "Cell" | "VecDeque"
-                | "LinkedList" => {
-                    if let PathArguments::AngleBracketed(args) = &segment.arguments {
-                        if let Some(GenericArgument::Type(inner)) = args.args.first() {
-                            return analyze_inner_type(inner, seen_types);
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    for arg in &args.args {
+                        if let GenericArgument::Type(inner) = arg {
+                            out.extend(self.field_type(inner));
                         }
                     }
                 }
-                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
-                | "u128" | "usize" | "f32" | "f64" | "bool" | "char" | "String" | "str"
-                | "Result" | "HashMap" | "HashSet" | "BTreeMap" | "BTreeSet" => {}
-                _ => {
-                    if !seen_types.contains(&name) {
-                        seen_types.insert(name.clone());
-                        return vec![generate_probe_call(&name)];
-                    }
-                }
+                out
             }
+            Type::Tuple(tuple) => tuple
+                .elems
+                .iter()
+                .flat_map(|elem| self.field_type(elem))
+                .collect(),
+            Type::Array(array) => self.field_type(&array.elem),
+            Type::Slice(slice) => self.field_type(&slice.elem),
+            Type::Reference(reference) => self.field_type(&reference.elem),
+            Type::Paren(paren) => self.field_type(&paren.elem),
+            Type::Group(group) => self.field_type(&group.elem),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Collect the decorators of the type parameter `param` for whatever it
+    /// is instantiated with. A parameter bounded by `HasSpytialDecorators`
+    /// is probed like any type. An unbounded one cannot be asked for its
+    /// decorators, so they are looked up in the registry by type name, which
+    /// knows every non-generic derived type from the start.
+    fn param(&mut self, param: &syn::Ident) -> Vec<proc_macro2::TokenStream> {
+        let name = param.to_string();
+        if !self.seen.insert(name.clone()) {
+            return Vec::new();
+        }
+        if self.bounded.contains(&name) {
+            return vec![generate_probe_call(param)];
+        }
+        vec![quote! {
+            .extend_with(
+                caraspace::spytial_annotations::get_type_decorators(
+                    ::std::any::type_name::<#param>(),
+                )
+                .unwrap_or_default(),
+            )
+        }]
+    }
+
+    /// Probe a type named by `#[spytial(include = "...")]` as written, path
+    /// and type arguments included, unless it has been probed already.
+    fn include(&mut self, ty: &Type) -> Vec<proc_macro2::TokenStream> {
+        if self.seen.insert(quote!(#ty).to_string()) {
+            vec![generate_probe_call(ty)]
+        } else {
+            Vec::new()
         }
     }
-    Vec::new()
 }
 
-/// Generate a probe call that safely collects decorators from `type_name`.
+/// Generate a probe call that safely collects decorators from `ty`.
 ///
 /// Uses the inherent-method-priority trick: if the type implements
 /// `HasSpytialDecorators`, the inherent `DecoProbe::get` is chosen and
 /// returns real decorators.  Otherwise the blanket `DefaultDecorators::get`
 /// is chosen and returns an empty set.  No heuristic needed.
-fn generate_probe_call(type_name: &str) -> proc_macro2::TokenStream {
-    let type_ident = syn::Ident::new(type_name, proc_macro2::Span::call_site());
+fn generate_probe_call(ty: &impl quote::ToTokens) -> proc_macro2::TokenStream {
     quote! {
         .extend_with({
             use caraspace::spytial_annotations::DefaultDecorators as _;
-            caraspace::spytial_annotations::DecoProbe::<#type_ident>(::std::marker::PhantomData).get()
+            caraspace::spytial_annotations::DecoProbe::<#ty>(::std::marker::PhantomData).get()
         })
     }
 }
@@ -153,6 +236,25 @@ fn generate_probe_call(type_name: &str) -> proc_macro2::TokenStream {
 /// - `#[inferred_edge(name = "edge", selector = "sel")]` - Adds inferred edge directive
 /// - `#[tag(to_tag = "sel", name = "attr", value = "n-ary selector")]` - Adds tag directive
 ///
+/// # Field Attributes
+/// - `#[spytial(attribute)]` - Adds attribute directive for this field
+/// - `#[spytial(hide)]` - Adds hide field directive for this field
+/// - `#[spytial(edge(color = "red", style = "dashed", ...))]` - Adds edge style directive for this field; takes the `edge_style` keys with `color` for `value`
+/// - `#[spytial(include = "path::Type")]` - Also collects decorators from a type the field's type hides (an alias or custom container); allowed on the container too
+///
+/// On the type itself, `#[spytial(type_name = "ast::Node")]` exports its
+/// atoms under that name, and `#[spytial(qualified)]` under its module path;
+/// either way the type's own selectors are renamed to match.
+///
+/// `attribute` and `hide` accept an optional `(selector = "...")`. The field
+/// is named as serde serializes it, after `rename` and `rename_all`.
+///
+/// Selectors are parsed at expansion time, and a selector that does not
+/// parse is a compile error pointing at its string. For a struct whose
+/// fields are all primitives, standard containers of them, or the struct
+/// itself, type and field names that are one typo away from a real one
+/// are rejected as well.
+///
 /// # Example
 /// ```rust
 /// use serde::Serialize;
@@ -183,7 +285,8 @@ fn generate_probe_call(type_name: &str) -> proc_macro2::TokenStream {
         hide_field,
         hide_atom,
         inferred_edge,
-        tag
+        tag,
+        spytial
     )
 )]
 pub fn derive_spytial_decorators(input: TokenStream) -> TokenStream {
@@ -195,12 +298,22 @@ pub fn derive_spytial_decorators(input: TokenStream) -> TokenStream {
 
     // Parse spatial annotation attributes for this type
     let mut decorator_calls = Vec::new();
+    let vocabulary = Vocabulary::of(&input);
+    let mut string_errors: Option<syn::Error> = None;
 
     for attr in &input.attrs {
         let parsed = match parse_spatial_attribute(attr) {
             Ok(parsed) => parsed,
             Err(err) => return err.to_compile_error().into(),
         };
+        if parsed.is_some() {
+            if let Err(err) = check_decorator_strings(attr, vocabulary.as_ref()) {
+                match &mut string_errors {
+                    Some(all) => all.combine(err),
+                    None => string_errors = Some(err),
+                }
+            }
+        }
         match parsed {
             Some(SpatialAttribute::Attribute { field }) => {
                 decorator_calls.push(quote! {
@@ -359,37 +472,144 @@ pub fn derive_spytial_decorators(input: TokenStream) -> TokenStream {
         }
     }
 
-    // Second, analyze field types and collect their decorators at compile time
-    // Only do this for structs - enums don't have fields to analyze
-    let field_type_decorators = match &input.data {
-        Data::Struct(_) => collect_field_type_decorators(&input.data, &name.to_string()),
-        Data::Enum(_) | Data::Union(_) => Vec::new(), // Enums and unions just return empty decorators
-    };
+    // Types named by a container-level `#[spytial(include = "...")]`, and
+    // the atom type name chosen by `type_name = "..."` or `qualified`
+    let mut includes = Vec::new();
+    let mut exported_name: Option<proc_macro2::TokenStream> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("spytial")) {
+        let parsed = attr.parse_nested_meta(|meta| {
+            let naming = meta.path.is_ident("type_name") || meta.path.is_ident("qualified");
+            if naming && exported_name.is_some() {
+                return Err(meta.error("the type name is already set"));
+            }
+            if meta.path.is_ident("include") {
+                includes.push(parse_include(&meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("type_name") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                let valid = lit.value().split("::").all(|segment| {
+                    segment.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                        && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+                });
+                if !valid {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "`type_name` must be a name selectors can use, like \"ast::Node\"",
+                    ));
+                }
+                exported_name = Some(quote! { #lit });
+            } else if meta.path.is_ident("qualified") {
+                let ident = name.unraw().to_string();
+                exported_name = Some(quote! { concat!(module_path!(), "::", #ident) });
+            } else {
+                return Err(unknown_spytial_key(
+                    &meta.path,
+                    "",
+                    &["include", "type_name", "qualified"],
+                ));
+            }
+            Ok(())
+        });
+        if let Err(err) = parsed {
+            match &mut string_errors {
+                Some(all) => all.combine(err),
+                None => string_errors = Some(err),
+            }
+        }
+    }
+
+    match field_decorator_calls(&input, vocabulary.as_ref()) {
+        Ok(calls) => decorator_calls.extend(calls),
+        Err(err) => match &mut string_errors {
+            Some(all) => all.combine(err),
+            None => string_errors = Some(err),
+        },
+    }
+
+    if let Some(err) = string_errors {
+        return err.to_compile_error().into();
+    }
+
+    // Second, analyze field types (of the struct, or of every enum variant)
+    // and collect their decorators at compile time
+    let field_type_decorators = collect_field_type_decorators(&input, &includes);
+
+    // A type exported under another name renames itself in its own
+    // selectors, before those of its field types are merged in, and
+    // registers the name for the serializer.
+    let serde_name =
+        serde_string(&input.attrs, "rename").unwrap_or_else(|| name.unraw().to_string());
+    let mut register_name = quote! {};
+    if let Some(exported) = &exported_name {
+        decorator_calls.push(quote! {
+            .rename_type(#serde_name, #exported)
+        });
+        register_name = quote! {
+            caraspace::spytial_annotations::register_type_name(
+                ::std::any::type_name::<Self>(),
+                #serde_name,
+                #exported
+            );
+        };
+    }
 
     // Combine own decorators with field type decorators
     decorator_calls.extend(field_type_decorators);
 
+    // Register this type automatically when decorators() is called, keyed
+    // by its full type name. A static inside a generic impl is shared by
+    // every instantiation, so generic types register on each call instead
+    // of once.
+    let per_instantiation =
+        generics.type_params().next().is_some() || generics.const_params().next().is_some();
+    let body = if per_instantiation {
+        quote! {
+            let decorators = caraspace::spytial_annotations::SpytialDecoratorsBuilder::new()
+                #(#decorator_calls)*
+                .build();
+            #register_name
+            caraspace::spytial_annotations::register_type_decorators(
+                ::std::any::type_name::<Self>(),
+                decorators.clone()
+            );
+            decorators
+        }
+    } else {
+        quote! {
+            static REGISTRATION: ::std::sync::Once = ::std::sync::Once::new();
+            REGISTRATION.call_once(|| {
+                let decorators = caraspace::spytial_annotations::SpytialDecoratorsBuilder::new()
+                    #(#decorator_calls)*
+                    .build();
+                #register_name
+                caraspace::spytial_annotations::register_type_decorators(
+                    ::std::any::type_name::<Self>(),
+                    decorators.clone()
+                );
+            });
+
+            caraspace::spytial_annotations::SpytialDecoratorsBuilder::new()
+                #(#decorator_calls)*
+                .build()
+        }
+    };
+
+    // A type without generics can be listed at link time, so generic types
+    // holding it find its decorators by name before it is ever used.
+    let register = if generics.params.is_empty() {
+        quote! { caraspace::__register_decorators!(#name); }
+    } else {
+        quote! {}
+    };
+
     // Generate the HasSpytialDecorators implementation
     let expanded = quote! {
         impl #impl_generics caraspace::spytial_annotations::HasSpytialDecorators for #name #ty_generics #where_clause {
             fn decorators() -> caraspace::spytial_annotations::SpytialDecorators {
-                // Register this type automatically when decorators() is called
-                static REGISTRATION: ::std::sync::Once = ::std::sync::Once::new();
-                REGISTRATION.call_once(|| {
-                    let decorators = caraspace::spytial_annotations::SpytialDecoratorsBuilder::new()
-                        #(#decorator_calls)*
-                        .build();
-                    caraspace::spytial_annotations::register_type_decorators(
-                        stringify!(#name),
-                        decorators.clone()
-                    );
-                });
-
-                caraspace::spytial_annotations::SpytialDecoratorsBuilder::new()
-                    #(#decorator_calls)*
-                    .build()
+                #body
             }
         }
+
+        #register
     };
 
     TokenStream::from(expanded)
@@ -558,6 +778,559 @@ fn validate_known_keys(
     })
 }
 
+/// Every type and relation name a selector on a struct can mean, when all of
+/// them are visible at expansion time.
+struct Vocabulary {
+    owner: String,
+    /// Atom types: the struct itself and the types its fields serialize as.
+    types: Vec<String>,
+    /// Relation names: the struct's fields, plus `idx` for sequences.
+    relations: Vec<String>,
+}
+
+impl Vocabulary {
+    /// The vocabulary of `input`, or `None` when it cannot be known here:
+    /// generic or non-struct types, serde attributes that rename things, or
+    /// fields whose types are defined elsewhere (their own fields and types
+    /// would be fair game in a selector).
+    fn of(input: &DeriveInput) -> Option<Self> {
+        let Data::Struct(data) = &input.data else {
+            return None;
+        };
+        let Fields::Named(fields) = &data.fields else {
+            return None;
+        };
+        if !input.generics.params.is_empty() || has_serde_attr(&input.attrs) {
+            return None;
+        }
+        let owner = input.ident.to_string();
+        let mut vocabulary = Vocabulary {
+            types: vec![owner.clone()],
+            relations: Vec::new(),
+            owner,
+        };
+        for field in &fields.named {
+            if has_serde_attr(&field.attrs) {
+                return None;
+            }
+            vocabulary.relations.push(field.ident.as_ref()?.to_string());
+            vocabulary.add_type(&field.ty)?;
+        }
+        Some(vocabulary)
+    }
+
+    /// Record what `ty` serializes as; `None` if it is not a type whose
+    /// whole shape is known.
+    fn add_type(&mut self, ty: &Type) -> Option<()> {
+        if let Type::Tuple(tuple) = ty {
+            if tuple.elems.is_empty() {
+                add(&mut self.types, "unit");
+                return Some(());
+            }
+        }
+        let Type::Path(path) = ty else {
+            return None;
+        };
+        let segment = path.path.segments.last()?;
+        let name = segment.ident.to_string();
+        let primitive = match name.as_str() {
+            "String" | "str" => "string",
+            "isize" => "i64",
+            "usize" => "u64",
+            "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "f32" | "f64"
+            | "bool" | "char" => name.as_str(),
+            "Self" => return Some(()),
+            _ if name == self.owner => return Some(()),
+            "Option" | "Vec" | "VecDeque" | "LinkedList" | "Box" | "Rc" | "Arc" | "RefCell"
+            | "Cell" => {
+                if name == "Option" {
+                    add(&mut self.types, "None");
+                    add(&mut self.types, "Some");
+                } else if matches!(name.as_str(), "Vec" | "VecDeque" | "LinkedList") {
+                    add(&mut self.types, "sequence");
+                    add(&mut self.relations, "idx");
+                }
+                let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                    return None;
+                };
+                let Some(GenericArgument::Type(inner)) = args.args.first() else {
+                    return None;
+                };
+                return self.add_type(inner);
+            }
+            _ => return None,
+        };
+        add(&mut self.types, primitive);
+        Some(())
+    }
+
+    /// Error for `name`, used as a `what` in the decorator at `lit`, when it
+    /// is missing from `known` but a close match is there — almost surely a
+    /// typo. Names unlike anything known are let through: a hand-written
+    /// `Serialize` impl can emit anything.
+    fn typo(
+        &self,
+        lit: &syn::LitStr,
+        what: &str,
+        name: &str,
+        known: &[&String],
+    ) -> Option<syn::Error> {
+        let limit = (name.chars().count() / 3).max(1);
+        let close = known
+            .iter()
+            .map(|k| (edit_distance(k, name), k))
+            .filter(|(d, _)| *d <= limit)
+            .min()?
+            .1;
+        let owner = &self.owner;
+        let not = if what == "field" {
+            format!("a field of `{owner}`")
+        } else {
+            format!("`{owner}`, one of its fields, or a type they contain")
+        };
+        Some(syn::Error::new(
+            lit.span(),
+            format!("{what} `{name}` is not {not}; did you mean `{close}`?"),
+        ))
+    }
+}
+
+fn add(names: &mut Vec<String>, name: &str) {
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_string());
+    }
+}
+
+fn has_serde_attr(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|a| a.path().is_ident("serde"))
+}
+
+/// Edit distance counting a swap of adjacent characters as one edit, for
+/// "did you mean" suggestions.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The string literals given for `keys` in `attr`, with the key they were
+/// given for. Malformed arguments are skipped; the extractors and
+/// [`validate_known_keys`] report those.
+fn string_args(attr: &Attribute, keys: &[&str]) -> Vec<(String, syn::LitStr)> {
+    let mut out = Vec::new();
+    if attr.meta.require_list().is_err() {
+        return out;
+    }
+    let _ = attr.parse_nested_meta(|meta| {
+        let key = meta
+            .path
+            .get_ident()
+            .map(|i| i.to_string())
+            .unwrap_or_default();
+        if let Ok(value) = meta.value() {
+            if let Ok(syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            })) = value.parse::<syn::Expr>()
+            {
+                if keys.contains(&key.as_str()) {
+                    out.push((key, lit));
+                }
+            }
+        }
+        Ok(())
+    });
+    out
+}
+
+/// Parse the selectors in a decorator attribute and, where `vocabulary`
+/// is known, check the type and field names they mention for typos. Errors
+/// point at the offending string literal.
+fn check_decorator_strings(
+    attr: &Attribute,
+    vocabulary: Option<&Vocabulary>,
+) -> Result<(), syn::Error> {
+    let mut errors: Option<syn::Error> = None;
+    let mut push = |err: syn::Error| match &mut errors {
+        Some(all) => all.combine(err),
+        None => errors = Some(err),
+    };
+    let attr_name = attr
+        .path()
+        .get_ident()
+        .map(|i| i.to_string())
+        .unwrap_or_default();
+
+    for (key, lit) in string_args(attr, &["selector", "to_tag", "value", "field"]) {
+        // `value` is a selector only on `tag`; elsewhere it is a colour.
+        if key == "value" && attr_name != "tag" {
+            continue;
+        }
+        let text = lit.value();
+        if key == "field" {
+            if let Some(v) = vocabulary {
+                let known: Vec<&String> = v.relations.iter().collect();
+                if !known.contains(&&text) {
+                    if let Some(err) = v.typo(&lit, "field", &text, &known) {
+                        push(err);
+                    }
+                }
+            }
+            continue;
+        }
+        if let Some(err) = check_selector(&lit, &attr_name, vocabulary) {
+            push(err);
+        }
+    }
+    errors.map_or(Ok(()), Err)
+}
+
+/// Parse the selector in `lit`, given to `#[attr_name(...)]`, and check the
+/// names it mentions against `vocabulary` when that is known.
+fn check_selector(
+    lit: &syn::LitStr,
+    attr_name: &str,
+    vocabulary: Option<&Vocabulary>,
+) -> Option<syn::Error> {
+    let text = lit.value();
+    let names = match selector::referenced_names(&text) {
+        Ok(names) => names,
+        Err(err) => {
+            let column = text[..err.at].chars().count() + 1;
+            return Some(syn::Error::new(
+                lit.span(),
+                format!(
+                    "invalid selector for #[{attr_name}(...)]: {} at column {column}",
+                    err.message
+                ),
+            ));
+        }
+    };
+    let v = vocabulary?;
+    let known: Vec<&String> = v.types.iter().chain(&v.relations).collect();
+    let mut errors: Option<syn::Error> = None;
+    for name in names.iter().filter(|n| !known.contains(n)) {
+        if let Some(err) = v.typo(lit, "selector name", name, &known) {
+            match &mut errors {
+                Some(all) => all.combine(err),
+                None => errors = Some(err),
+            }
+        }
+    }
+    errors
+}
+
+/// Decorator calls for the `#[spytial(...)]` attributes on the fields of
+/// `input`. Each applies to the relation the field serializes as, so it
+/// follows the field through renames.
+fn field_decorator_calls(
+    input: &DeriveInput,
+    vocabulary: Option<&Vocabulary>,
+) -> Result<Vec<proc_macro2::TokenStream>, syn::Error> {
+    let mut calls = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut push = |err: syn::Error| match &mut errors {
+        Some(all) => all.combine(err),
+        None => errors = Some(err),
+    };
+    let rename_all = serde_string(&input.attrs, "rename_all");
+
+    let fields: Vec<&syn::Field> = match &input.data {
+        Data::Struct(data) => data.fields.iter().collect(),
+        Data::Enum(data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        Data::Union(data) => data.fields.named.iter().collect(),
+    };
+    for field in fields {
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("spytial")) {
+            if !matches!(input.data, Data::Struct(_)) {
+                let parsed = attr.parse_nested_meta(|meta| {
+                    if !meta.path.is_ident("include") {
+                        return Err(syn::Error::new_spanned(
+                            &meta.path,
+                            "only `include` is supported in #[spytial(...)] on enum variant fields",
+                        ));
+                    }
+                    parse_include(&meta.value()?.parse()?).map(drop)
+                });
+                if let Err(err) = parsed {
+                    push(err);
+                }
+                continue;
+            }
+            let Some(ident) = &field.ident else {
+                push(syn::Error::new_spanned(
+                    attr,
+                    "#[spytial(...)] needs a named field; tuple fields all export as `idx`",
+                ));
+                continue;
+            };
+            let relation = serde_string(&field.attrs, "rename")
+                .unwrap_or_else(|| rename_field(&ident.unraw().to_string(), rename_all.as_deref()));
+            if let Err(err) = parse_field_decorator(attr, &relation, vocabulary, &mut calls) {
+                push(err);
+            }
+        }
+    }
+    errors.map_or(Ok(calls), Err)
+}
+
+/// Parse one `#[spytial(...)]` field attribute into builder calls on the
+/// relation `field`.
+fn parse_field_decorator(
+    attr: &Attribute,
+    field: &str,
+    vocabulary: Option<&Vocabulary>,
+    calls: &mut Vec<proc_macro2::TokenStream>,
+) -> Result<(), syn::Error> {
+    attr.parse_nested_meta(|meta| {
+        let key = meta
+            .path
+            .get_ident()
+            .map(|i| i.to_string())
+            .unwrap_or_default();
+        match key.as_str() {
+            "attribute" | "hide" => {
+                let mut selector = None;
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|inner| {
+                        if !inner.path.is_ident("selector") {
+                            return Err(unknown_spytial_key(&inner.path, &key, &["selector"]));
+                        }
+                        let lit: syn::LitStr = inner.value()?.parse()?;
+                        if let Some(err) = check_selector(&lit, "spytial", vocabulary) {
+                            return Err(err);
+                        }
+                        selector = Some(lit.value());
+                        Ok(())
+                    })?;
+                }
+                let selector_arg = match selector {
+                    Some(s) => quote! { Some(#s) },
+                    None => quote! { None },
+                };
+                calls.push(if key == "attribute" {
+                    quote! { .attribute(#field, #selector_arg) }
+                } else {
+                    quote! { .hide_field(#field, #selector_arg) }
+                });
+                Ok(())
+            }
+            "include" => {
+                // Probed along with the field types; only checked here.
+                parse_include(&meta.value()?.parse()?)?;
+                Ok(())
+            }
+            "edge" => {
+                let mut color = "blue".to_string();
+                let (mut selector, mut filter, mut style) = (None, None, None);
+                let (mut weight, mut show_label, mut hidden) = (None, None, None);
+                meta.parse_nested_meta(|inner| {
+                    let name = inner
+                        .path
+                        .get_ident()
+                        .map(|i| i.to_string())
+                        .unwrap_or_default();
+                    match name.as_str() {
+                        "color" => color = inner.value()?.parse::<syn::LitStr>()?.value(),
+                        "selector" => {
+                            let lit: syn::LitStr = inner.value()?.parse()?;
+                            if let Some(err) = check_selector(&lit, "spytial", vocabulary) {
+                                return Err(err);
+                            }
+                            selector = Some(lit.value());
+                        }
+                        "filter" => filter = Some(inner.value()?.parse::<syn::LitStr>()?.value()),
+                        "style" => style = Some(inner.value()?.parse::<syn::LitStr>()?.value()),
+                        "weight" => {
+                            weight = Some(match inner.value()?.parse::<syn::Lit>()? {
+                                syn::Lit::Float(n) => n.base10_parse::<f64>()?,
+                                syn::Lit::Int(n) => n.base10_parse::<f64>()?,
+                                other => {
+                                    return Err(syn::Error::new_spanned(
+                                        other,
+                                        "expected a number for `weight`",
+                                    ))
+                                }
+                            })
+                        }
+                        "show_label" => {
+                            show_label = Some(inner.value()?.parse::<syn::LitBool>()?.value)
+                        }
+                        "hidden" => hidden = Some(inner.value()?.parse::<syn::LitBool>()?.value),
+                        _ => {
+                            return Err(unknown_spytial_key(
+                                &inner.path,
+                                "edge",
+                                &[
+                                    "color",
+                                    "style",
+                                    "selector",
+                                    "filter",
+                                    "weight",
+                                    "show_label",
+                                    "hidden",
+                                ],
+                            ))
+                        }
+                    }
+                    Ok(())
+                })?;
+                let opt_str = |v: Option<String>| match v {
+                    Some(s) => quote! { Some(#s) },
+                    None => quote! { None },
+                };
+                let opt = |v: Option<proc_macro2::TokenStream>| match v {
+                    Some(t) => quote! { Some(#t) },
+                    None => quote! { None },
+                };
+                let (selector, filter, style) = (opt_str(selector), opt_str(filter), opt_str(style));
+                let weight = opt(weight.map(|n: f64| quote! { #n }));
+                let show_label = opt(show_label.map(|b: bool| quote! { #b }));
+                let hidden = opt(hidden.map(|b: bool| quote! { #b }));
+                calls.push(quote! {
+                    .edge_style(#field, #color, #selector, #filter, #style, #weight, #show_label, #hidden)
+                });
+                Ok(())
+            }
+            _ => Err(unknown_spytial_key(
+                &meta.path,
+                "",
+                &["attribute", "hide", "edge", "include"],
+            )),
+        }
+    })
+}
+
+/// Error for a key of `#[spytial(...)]`, or of its `nested(...)` list, that
+/// is not in `known`.
+fn unknown_spytial_key(path: &syn::Path, nested: &str, known: &[&str]) -> syn::Error {
+    let key = path
+        .get_ident()
+        .map(|i| i.to_string())
+        .unwrap_or_else(|| quote!(#path).to_string());
+    let attr = if nested.is_empty() {
+        "spytial(...)".to_string()
+    } else {
+        format!("spytial({nested}(...))")
+    };
+    syn::Error::new_spanned(
+        path,
+        format!(
+            "unknown parameter `{key}` for #[{attr}]; expected one of: {}",
+            known.join(", ")
+        ),
+    )
+}
+
+/// Consume a nested meta item without interpreting it, whether it is a
+/// bare `key`, `key = value` or `key(...)`.
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> Result<(), syn::Error> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|inner| skip_meta(&inner))?;
+    }
+    Ok(())
+}
+
+/// The types named by `include = "..."` in the `#[spytial(...)]` attributes
+/// among `attrs`; other keys are skipped.
+fn spytial_includes(attrs: &[Attribute]) -> Result<Vec<Type>, syn::Error> {
+    let mut includes = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("spytial")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("include") {
+                includes.push(parse_include(&meta.value()?.parse()?)?);
+                Ok(())
+            } else {
+                skip_meta(&meta)
+            }
+        })?;
+    }
+    Ok(includes)
+}
+
+/// The type named by the string of an `include = "..."`.
+fn parse_include(lit: &syn::LitStr) -> Result<Type, syn::Error> {
+    lit.parse::<Type>().map_err(|err| {
+        syn::Error::new(
+            lit.span(),
+            format!("`include` must name a type, like \"crate::model::Employee\": {err}"),
+        )
+    })
+}
+
+/// The string serde was given for `key` in `#[serde(...)]` among `attrs`,
+/// either as `key = "..."` or as `key(serialize = "...")`.
+fn serde_string(attrs: &[Attribute], key: &str) -> Option<String> {
+    let mut found = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident(key) {
+                return skip_meta(&meta);
+            }
+            if meta.input.peek(syn::Token![=]) {
+                found = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else {
+                meta.parse_nested_meta(|inner| {
+                    let lit: syn::LitStr = inner.value()?.parse()?;
+                    if inner.path.is_ident("serialize") {
+                        found = Some(lit.value());
+                    }
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        });
+    }
+    found
+}
+
+/// The name serde gives the field `name` under `#[serde(rename_all = rule)]`.
+fn rename_field(name: &str, rule: Option<&str>) -> String {
+    let pascal = || -> String {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect()
+    };
+    match rule {
+        Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => name.to_ascii_uppercase(),
+        Some("PascalCase") => pascal(),
+        Some("camelCase") => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        Some("kebab-case") => name.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => name.to_ascii_uppercase().replace('_', "-"),
+        _ => name.to_string(),
+    }
+}
+
 fn parse_attribute_args(attr: &Attribute) -> Result<Option<SpatialAttribute>, syn::Error> {
     validate_known_keys(attr, "attribute", &["field"])?;
     // Simple parsing - look for field = "value"
@@ -914,10 +1687,19 @@ fn extract_string_from_tokens(tokens: &str, key: &str) -> Option<String> {
 
     for pattern in &patterns {
         if let Some(start) = tokens.find(pattern) {
-            let start = start + pattern.len();
-            if let Some(end) = tokens[start..].find('"') {
-                return Some(tokens[start..start + end].to_string());
-            }
+            // Take the whole literal, escapes included, and let syn unescape
+            // it, so a selector such as `@:x = "a b"` survives intact.
+            let open = start + pattern.len() - 1;
+            let mut escaped = false;
+            let end = tokens[open + 1..].find(|c| {
+                let closes = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                closes
+            })?;
+            let literal = &tokens[open..open + 1 + end + 1];
+            return syn::parse_str::<syn::LitStr>(literal)
+                .map(|lit| lit.value())
+                .ok();
         }
     }
     None
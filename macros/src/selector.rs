@@ -0,0 +1,399 @@
+//! Compile-time checks for selector strings in decorator attributes.
+//!
+//! This crate cannot depend on `caraspace`, so the grammar of
+//! `caraspace::selector` is mirrored here — closely enough to reject the
+//! selectors that would fail to parse at runtime, with the same messages.
+//! Keep the two in step: `tests/derive_checks.rs` in the main crate runs a
+//! shared corpus through both. Parsing also collects the names a selector
+//! refers to, so the derive can check them against the annotated struct.
+
+/// A parse error and the byte offset it points at.
+#[derive(Debug)]
+pub(crate) struct SyntaxError {
+    pub message: String,
+    pub at: usize,
+}
+
+impl SyntaxError {
+    fn at(message: impl Into<String>, at: usize) -> Self {
+        SyntaxError {
+            message: message.into(),
+            at,
+        }
+    }
+}
+
+/// Parse `src` and return the type and relation names it mentions.
+///
+/// Comprehension variables, `univ`/`none`/`iden` and module-qualified names
+/// are left out, as are bare words compared with a label (`@:(x.color) =
+/// Red`), which stand for themselves when no such name exists.
+pub(crate) fn referenced_names(src: &str) -> Result<Vec<String>, SyntaxError> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: src.len(),
+    };
+    let expr = parser.expr()?;
+    if let Some((tok, at)) = parser.tokens.get(parser.pos) {
+        return Err(SyntaxError::at(format!("unexpected `{tok}`"), *at));
+    }
+    let mut names = Vec::new();
+    collect(&expr, &mut Vec::new(), &mut names);
+    Ok(names)
+}
+
+fn collect(expr: &Expr, bound: &mut Vec<String>, out: &mut Vec<String>) {
+    match expr {
+        Expr::Name(name) => {
+            if !bound.contains(name)
+                && !["univ", "none", "iden"].contains(&name.as_str())
+                && !name.contains("::")
+                && !out.contains(name)
+            {
+                out.push(name.clone());
+            }
+        }
+        Expr::Str => {}
+        Expr::Label(inner) => collect(inner, bound, out),
+        Expr::Ops(operands) => operands.iter().for_each(|e| collect(e, bound, out)),
+        Expr::Comprehension {
+            vars,
+            domains,
+            body,
+        } => {
+            for domain in domains {
+                collect(domain, bound, out);
+            }
+            let depth = bound.len();
+            bound.extend(vars.iter().cloned());
+            collect_formula(body, bound, out);
+            bound.truncate(depth);
+        }
+    }
+}
+
+fn collect_formula(formula: &Formula, bound: &mut Vec<String>, out: &mut Vec<String>) {
+    match formula {
+        Formula::Compare(lhs, rhs, by_label) => {
+            let labelled = *by_label && (is_label(lhs) || is_label(rhs));
+            for side in [lhs, rhs] {
+                if !(labelled && matches!(side, Expr::Name(_))) {
+                    collect(side, bound, out);
+                }
+            }
+        }
+        Formula::Test(e) => collect(e, bound, out),
+        Formula::Connective(parts) => parts.iter().for_each(|f| collect_formula(f, bound, out)),
+    }
+}
+
+fn is_label(expr: &Expr) -> bool {
+    matches!(expr, Expr::Label(_) | Expr::Str)
+}
+
+// ── Tokens ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Sym(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(s) => f.write_str(s),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Sym(s) => f.write_str(s),
+        }
+    }
+}
+
+/// Longest symbols first so `->` wins over `-` and `!=` over `!`.
+const SYMBOLS: &[&str] = &[
+    "->", "!=", "&&", "||", "@", "{", "}", "(", ")", ",", ":", "|", "+", "&", "-", ".", "~", "^",
+    "*", "=", "!",
+];
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, SyntaxError> {
+    let mut out = Vec::new();
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        let at = src.len() - rest.len();
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| SyntaxError::at("unterminated string", at))?;
+            out.push((Token::Str(rest[1..1 + end].to_string()), at));
+            rest = &rest[end + 2..];
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut len = 0;
+            loop {
+                len += rest[len..]
+                    .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                    .unwrap_or(rest.len() - len);
+                let after = &rest[len..];
+                if after.starts_with("::")
+                    && after[2..].starts_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+                {
+                    len += 2;
+                } else {
+                    break;
+                }
+            }
+            out.push((Token::Ident(rest[..len].to_string()), at));
+            rest = &rest[len..];
+        } else if let Some(sym) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            out.push((Token::Sym(sym), at));
+            rest = &rest[sym.len()..];
+        } else {
+            return Err(SyntaxError::at(format!("unexpected character `{c}`"), at));
+        }
+    }
+    Ok(out)
+}
+
+// ── Syntax ──────────────────────────────────────────────────────────────
+
+/// Only what name collection needs: operators are not distinguished.
+enum Expr {
+    Name(String),
+    Str,
+    Label(Box<Expr>),
+    Ops(Vec<Expr>),
+    Comprehension {
+        vars: Vec<String>,
+        domains: Vec<Expr>,
+        body: Box<Formula>,
+    },
+}
+
+enum Formula {
+    /// `in` (false) or `=` / `!=` (true).
+    Compare(Expr, Expr, bool),
+    /// `some e` / `no e`.
+    Test(Expr),
+    /// `and`, `or`, `not`; `true` and `false` have no parts.
+    Connective(Vec<Formula>),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(tok, _)| tok)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, at)| *at)
+    }
+
+    fn peek_sym(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(Token::Sym(s)) if *s == sym)
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        let hit = self.peek_sym(sym);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let hit = matches!(self.peek(), Some(Token::Ident(s)) if s == word);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    /// Consume `true` or `false` standing alone as a formula.
+    fn constant(&mut self) -> bool {
+        if !matches!(self.peek(), Some(Token::Ident(w)) if w == "true" || w == "false") {
+            return false;
+        }
+        let ends = match self.tokens.get(self.pos + 1).map(|(tok, _)| tok) {
+            None => true,
+            Some(Token::Sym(s)) => matches!(*s, ")" | "}" | "&&" | "||"),
+            Some(Token::Ident(w)) => w == "and" || w == "or",
+            Some(Token::Str(_)) => false,
+        };
+        if ends {
+            self.pos += 1;
+        }
+        ends
+    }
+
+    fn unexpected(&self, expected: &str) -> SyntaxError {
+        let found = match self.peek() {
+            Some(tok) => format!("`{tok}`"),
+            None => "end of selector".to_string(),
+        };
+        SyntaxError::at(format!("expected {expected}, found {found}"), self.offset())
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> Result<(), SyntaxError> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{sym}`")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, SyntaxError> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    /// One precedence level of left-associative binary operators.
+    fn level(
+        &mut self,
+        ops: &[&str],
+        next: fn(&mut Self) -> Result<Expr, SyntaxError>,
+    ) -> Result<Expr, SyntaxError> {
+        let mut operands = vec![next(self)?];
+        while ops.iter().any(|op| self.eat_sym(op)) {
+            operands.push(next(self)?);
+        }
+        Ok(if operands.len() == 1 {
+            operands.pop().expect("one operand")
+        } else {
+            Expr::Ops(operands)
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr, SyntaxError> {
+        self.level(&["+", "-"], Self::intersection)
+    }
+
+    fn intersection(&mut self) -> Result<Expr, SyntaxError> {
+        self.level(&["&"], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, SyntaxError> {
+        self.level(&["->"], Self::join)
+    }
+
+    fn join(&mut self) -> Result<Expr, SyntaxError> {
+        self.level(&["."], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, SyntaxError> {
+        if ["~", "^", "*"].iter().any(|op| self.eat_sym(op)) {
+            return Ok(Expr::Ops(vec![self.unary()?]));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, SyntaxError> {
+        if self.eat_sym("(") {
+            let inner = self.expr()?;
+            self.expect_sym(")")?;
+            return Ok(inner);
+        }
+        if self.eat_sym("@") {
+            self.expect_sym(":")?;
+            return Ok(Expr::Label(Box::new(self.unary()?)));
+        }
+        if self.eat_sym("{") {
+            let (mut vars, mut domains) = (Vec::new(), Vec::new());
+            loop {
+                vars.push(self.ident()?);
+                while self.eat_sym(",") {
+                    vars.push(self.ident()?);
+                }
+                self.expect_sym(":")?;
+                domains.push(self.expr()?);
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+            self.expect_sym("|")?;
+            let body = self.formula()?;
+            self.expect_sym("}")?;
+            return Ok(Expr::Comprehension {
+                vars,
+                domains,
+                body: Box::new(body),
+            });
+        }
+        if let Some(Token::Str(_)) = self.peek() {
+            self.pos += 1;
+            return Ok(Expr::Str);
+        }
+        Ok(Expr::Name(self.ident()?))
+    }
+
+    fn formula(&mut self) -> Result<Formula, SyntaxError> {
+        let mut parts = vec![self.conjunction()?];
+        while self.eat_word("or") || self.eat_sym("||") {
+            parts.push(self.conjunction()?);
+        }
+        Ok(connective(parts))
+    }
+
+    fn conjunction(&mut self) -> Result<Formula, SyntaxError> {
+        let mut parts = vec![self.negation()?];
+        while self.eat_word("and") || self.eat_sym("&&") {
+            parts.push(self.negation()?);
+        }
+        Ok(connective(parts))
+    }
+
+    fn negation(&mut self) -> Result<Formula, SyntaxError> {
+        if self.eat_word("not") || self.eat_sym("!") {
+            return Ok(Formula::Connective(vec![self.negation()?]));
+        }
+        if self.eat_word("some") || self.eat_word("no") {
+            return Ok(Formula::Test(self.expr()?));
+        }
+        if self.constant() {
+            return Ok(Formula::Connective(Vec::new()));
+        }
+        // `(` opens either a nested formula or an expression; try the
+        // formula first and rewind if it doesn't close cleanly.
+        if self.peek_sym("(") {
+            let start = self.pos;
+            self.pos += 1;
+            if let Ok(inner) = self.formula() {
+                if self.eat_sym(")") {
+                    return Ok(inner);
+                }
+            }
+            self.pos = start;
+        }
+        let lhs = self.expr()?;
+        let negated = self.eat_word("not") || self.eat_sym("!");
+        if self.eat_word("in") {
+            Ok(Formula::Compare(lhs, self.expr()?, false))
+        } else if !negated && (self.eat_sym("=") || self.eat_sym("!=")) {
+            Ok(Formula::Compare(lhs, self.expr()?, true))
+        } else {
+            Err(self.unexpected("`in`, `=` or `!=`"))
+        }
+    }
+}
+
+fn connective(mut parts: Vec<Formula>) -> Formula {
+    if parts.len() == 1 {
+        parts.pop().expect("one part")
+    } else {
+        Formula::Connective(parts)
+    }
+}
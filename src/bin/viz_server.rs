@@ -1,9 +1,41 @@
 use std::env;
 use std::fs;
-use std::io::{Read, Write};
-use std::net::TcpListener;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener};
 use std::path::Path;
 use std::process;
+use std::time::Duration;
+
+/// Largest diagram accepted by `POST /diagram`; pages are a few MB.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// Largest request line plus headers accepted; browsers send a few KB.
+const MAX_HEAD: u64 = 16 * 1024;
+
+/// How long a client may take to send its request before it is dropped.
+/// Connections are served one at a time, so a stalled one must not wait
+/// forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Served for the diagram file until a diagram of another kind is posted.
+const HTML: &str = "text/html; charset=utf-8";
+
+/// Header `POST /diagram` must carry. A browser will not send a custom
+/// header cross-origin without a CORS preflight, which this server never
+/// grants, so a web page cannot post a diagram.
+const SINK_HEADER: &str = "x-caraspace-sink";
+
+/// The content type a posted diagram is served with, for the media types
+/// `HttpSink` sends; anything else is refused.
+fn served_type(posted: &str) -> Option<&'static str> {
+    let media = posted.split(';').next().unwrap_or_default().trim();
+    match media.to_ascii_lowercase().as_str() {
+        "text/html" => Some(HTML),
+        "image/svg+xml" => Some("image/svg+xml"),
+        "text/plain" => Some("text/plain; charset=utf-8"),
+        _ => None,
+    }
+}
 
 fn main() {
     let file_path = env::args()
@@ -16,13 +48,15 @@ fn main() {
 
     if !Path::new(&file_path).exists() {
         eprintln!(
-            "Visualization file does not exist: {}. Run a diagram example first.",
+            "Visualization file does not exist yet: {}. It is served once a diagram writes it or is posted to /diagram.",
             file_path
         );
-        process::exit(1);
     }
 
-    let bind_addr = format!("0.0.0.0:{port}");
+    // Loopback only unless asked: anyone who can reach the port can replace
+    // the page. The Docker image sets SPYTIAL_BIND=0.0.0.0.
+    let host = env::var("SPYTIAL_BIND").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let bind_addr = format!("{host}:{port}");
     let listener = TcpListener::bind(&bind_addr).unwrap_or_else(|error| {
         eprintln!("Failed to bind {}: {}", bind_addr, error);
         process::exit(1);
@@ -30,37 +64,143 @@ fn main() {
 
     println!("Visualization server ready at http://localhost:{port}/rust_viz_data.html");
     println!("Health endpoint at http://localhost:{port}/health");
+    println!("POST diagrams to http://localhost:{port}/diagram");
 
+    let mut content_type = HTML.to_string();
     for stream in listener.incoming() {
         match stream {
-            Ok(mut stream) => handle_connection(&mut stream, &file_path),
+            Ok(mut stream) => handle_connection(&mut stream, &file_path, &mut content_type),
             Err(error) => eprintln!("Connection error: {}", error),
         }
     }
 }
 
-fn handle_connection(stream: &mut std::net::TcpStream, file_path: &str) {
-    let mut buffer = [0_u8; 4096];
-    let bytes_read = match stream.read(&mut buffer) {
+/// Answer one request. `content_type` is that of the diagram last posted,
+/// which `GET /rust_viz_data.html` serves it with.
+fn handle_connection(stream: &mut std::net::TcpStream, file_path: &str, content_type: &mut String) {
+    if let Err(error) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+        eprintln!("Failed to set read timeout: {}", error);
+        return;
+    }
+    let mut reader = BufReader::new(&mut *stream);
+    let mut head = (&mut reader).take(MAX_HEAD);
+    let mut request_line = String::new();
+    match head.read_line(&mut request_line) {
         Ok(0) => return,
-        Ok(read) => read,
+        Ok(_) => {}
         Err(error) => {
             eprintln!("Failed to read request: {}", error);
             return;
         }
-    };
+    }
 
-    let request = String::from_utf8_lossy(&buffer[..bytes_read]);
-    let mut parts = request
-        .lines()
-        .next()
-        .unwrap_or_default()
-        .split_whitespace();
+    let mut content_length = 0;
+    let mut posted_type = None;
+    let mut origin = None;
+    let mut host = None;
+    let mut from_sink = false;
+    loop {
+        let mut header = String::new();
+        match head.read_line(&mut header) {
+            Ok(0) => break,
+            Ok(_) if header.trim_end().is_empty() => break,
+            Ok(_) => {
+                if let Some((name, value)) = header.split_once(':') {
+                    let name = name.trim();
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    } else if name.eq_ignore_ascii_case("content-type") {
+                        posted_type = Some(value.trim().to_string());
+                    } else if name.eq_ignore_ascii_case("origin") {
+                        origin = Some(value.trim().to_string());
+                    } else if name.eq_ignore_ascii_case("host") {
+                        host = Some(value.trim().to_string());
+                    } else if name.eq_ignore_ascii_case(SINK_HEADER) {
+                        from_sink = true;
+                    }
+                }
+            }
+            Err(error) => {
+                eprintln!("Failed to read request: {}", error);
+                return;
+            }
+        }
+    }
+
+    if head.limit() == 0 {
+        write_response(
+            stream,
+            "431 Request Header Fields Too Large",
+            "text/plain; charset=utf-8",
+            b"Request Header Fields Too Large",
+        );
+        // Closing with the rest of the request unread would reset the
+        // connection before the client sees the answer; read a little more.
+        let _ = stream.shutdown(Shutdown::Write);
+        let _ = io::copy(&mut (&mut *stream).take(MAX_HEAD * 4), &mut io::sink());
+        return;
+    }
 
+    let mut parts = request_line.split_whitespace();
     let method = parts.next().unwrap_or_default();
     let raw_path = parts.next().unwrap_or("/");
     let path = raw_path.split('?').next().unwrap_or(raw_path);
 
+    if method == "POST" && path == "/diagram" {
+        // A page in the user's browser must not replace the diagram: it
+        // would then be served, script and all, from this origin.
+        let own_origin = host.map(|host| format!("http://{host}"));
+        let foreign = origin.is_some_and(|origin| Some(origin) != own_origin);
+        if !from_sink || foreign {
+            write_response(
+                stream,
+                "403 Forbidden",
+                "text/plain; charset=utf-8",
+                b"Forbidden",
+            );
+            return;
+        }
+        let Some(served) = posted_type.as_deref().and_then(served_type) else {
+            write_response(
+                stream,
+                "415 Unsupported Media Type",
+                "text/plain; charset=utf-8",
+                b"Unsupported Media Type",
+            );
+            return;
+        };
+        if content_length > MAX_BODY {
+            write_response(
+                stream,
+                "413 Payload Too Large",
+                "text/plain; charset=utf-8",
+                b"Payload Too Large",
+            );
+            return;
+        }
+        let mut body = vec![0_u8; content_length];
+        if let Err(error) = reader.read_exact(&mut body) {
+            eprintln!("Failed to read posted diagram: {}", error);
+            return;
+        }
+        match fs::write(file_path, &body) {
+            Ok(()) => {
+                *content_type = served.to_string();
+                write_response(stream, "204 No Content", "text/plain; charset=utf-8", b"")
+            }
+            Err(error) => {
+                let body = format!("Failed to write visualization file: {}", error);
+                write_response(
+                    stream,
+                    "500 Internal Server Error",
+                    "text/plain; charset=utf-8",
+                    body.as_bytes(),
+                );
+            }
+        }
+        return;
+    }
+
     if method != "GET" {
         write_response(
             stream,
@@ -83,7 +223,13 @@ fn handle_connection(stream: &mut std::net::TcpStream, file_path: &str) {
             write_response(stream, "204 No Content", "text/plain; charset=utf-8", b"")
         }
         "/rust_viz_data.html" => match fs::read(file_path) {
-            Ok(contents) => write_response(stream, "200 OK", "text/html; charset=utf-8", &contents),
+            Ok(contents) => write_response(stream, "200 OK", content_type, &contents),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => write_response(
+                stream,
+                "404 Not Found",
+                "text/plain; charset=utf-8",
+                b"No diagram yet",
+            ),
             Err(error) => {
                 let body = format!("Failed to read visualization file: {}", error);
                 write_response(
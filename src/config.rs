@@ -0,0 +1,254 @@
+//! Project-wide diagram defaults from `caraspace.toml`.
+//!
+//! So that everyone on a team gets the same diagrams without exporting
+//! variables in each shell, caraspace looks for a `caraspace.toml` in the
+//! crate being run (`CARGO_MANIFEST_DIR`, or the current directory outside
+//! Cargo) and each directory above it, stopping at the first one found.
+//! Failing that, it reads the `[package.metadata.caraspace]` table of that
+//! crate's `Cargo.toml`. The file is read once per process.
+//!
+//! ```toml
+//! output_dir = "target/diagrams"   # relative to the file
+//! file_name = "{n}.{ext}"
+//! format = "html"                  # html, svg, terminal, ascii or serve
+//! open = false
+//! theme = "dark"                   # light or dark
+//! max_depth = 6
+//! browser = "firefox --new-tab {}"
+//! spec = """
+//! directives:
+//!   - flag: hideDisconnected
+//! """
+//! spec_file = "diagrams.yaml"      # also merged, relative to the file
+//!
+//! [redact]
+//! fields = ["password", "token"]
+//! types = ["ApiKey"]
+//! ```
+//!
+//! The settings become the base of [`DiagramOptions::from_env`]: the
+//! `SPYTIAL_*` variables still override them, and
+//! [`set_default_options`](crate::set_default_options) or
+//! [`diagram_with`](crate::diagram_with) override both.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::options::{DiagramOptions, OutputFormat, Theme};
+
+/// Name of the project configuration file.
+pub const FILE_NAME: &str = "caraspace.toml";
+
+/// The contents of a `caraspace.toml` (or `[package.metadata.caraspace]`).
+///
+/// Every key is optional; unset ones leave the [`DiagramOptions`] they are
+/// [applied](ProjectConfig::apply) to alone. Relative paths are resolved
+/// against the directory of the file they were read from.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// See [`DiagramOptions::output_dir`].
+    pub output_dir: Option<PathBuf>,
+    /// See [`DiagramOptions::file_name`].
+    pub file_name: Option<String>,
+    /// See [`DiagramOptions::format`].
+    pub format: Option<OutputFormat>,
+    /// See [`DiagramOptions::open`].
+    pub open: Option<bool>,
+    /// See [`DiagramOptions::title`].
+    pub title: Option<String>,
+    /// See [`DiagramOptions::theme`].
+    pub theme: Option<Theme>,
+    /// See [`DiagramOptions::max_depth`].
+    pub max_depth: Option<usize>,
+    /// See [`DiagramOptions::browser`].
+    pub browser: Option<String>,
+    /// Decorator YAML merged into every spec; see
+    /// [`DiagramOptions::extra_spec`].
+    pub spec: Option<String>,
+    /// A YAML file merged into every spec, after [`ProjectConfig::spec`].
+    /// It is read the first time the configuration is applied.
+    pub spec_file: Option<PathBuf>,
+    /// Values kept out of every diagram.
+    pub redact: Redact,
+    /// The contents of `spec_file`, once read.
+    #[serde(skip)]
+    spec_file_text: OnceLock<Option<String>>,
+}
+
+/// The `[redact]` table of a [`ProjectConfig`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Redact {
+    /// See [`DiagramOptions::redact_field`].
+    pub fields: Vec<String>,
+    /// See [`DiagramOptions::redact_type`].
+    pub types: Vec<String>,
+}
+
+impl ProjectConfig {
+    /// Parse `caraspace.toml` text, resolving relative paths against `base`.
+    pub fn from_toml_str(text: &str, base: &Path) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text).map_err(|e| ConfigError(e.to_string()))?;
+        Ok(config.resolved(base))
+    }
+
+    /// Read a `caraspace.toml`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = read(path)?;
+        Self::from_toml_str(&text, parent(path))
+            .map_err(|e| ConfigError(format!("{}: {}", path.display(), e.0)))
+    }
+
+    /// Read the `[package.metadata.caraspace]` table of a `Cargo.toml`, or
+    /// `None` if it has none.
+    pub fn from_manifest(path: impl AsRef<Path>) -> Result<Option<Self>, ConfigError> {
+        let path = path.as_ref();
+        let invalid = |e: toml::de::Error| ConfigError(format!("{}: {e}", path.display()));
+        let manifest: toml::Table = toml::from_str(&read(path)?).map_err(invalid)?;
+        let Some(table) = manifest
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("caraspace"))
+        else {
+            return Ok(None);
+        };
+        let config: Self = table.clone().try_into().map_err(invalid)?;
+        Ok(Some(config.resolved(parent(path))))
+    }
+
+    /// Look for a [`FILE_NAME`] in `dir` and each directory above it, then
+    /// for `[package.metadata.caraspace]` in `dir/Cargo.toml`.
+    pub fn find(dir: impl AsRef<Path>) -> Result<Option<Self>, ConfigError> {
+        let dir = dir.as_ref();
+        for candidate in dir.ancestors().map(|d| d.join(FILE_NAME)) {
+            if candidate.is_file() {
+                return Self::load(candidate).map(Some);
+            }
+        }
+        let manifest = dir.join("Cargo.toml");
+        if manifest.is_file() {
+            return Self::from_manifest(manifest);
+        }
+        Ok(None)
+    }
+
+    /// `options` with every setting this configuration has.
+    pub fn apply(&self, mut options: DiagramOptions) -> DiagramOptions {
+        if let Some(dir) = &self.output_dir {
+            options = options.output_dir(dir);
+        }
+        if let Some(pattern) = &self.file_name {
+            options = options.file_name(pattern);
+        }
+        if let Some(format) = self.format {
+            options = options.format(format);
+        }
+        if let Some(open) = self.open {
+            options = options.open(open);
+        }
+        if let Some(title) = &self.title {
+            options = options.title(title);
+        }
+        if let Some(theme) = self.theme {
+            options = options.theme(theme);
+        }
+        if let Some(depth) = self.max_depth {
+            options = options.max_depth(depth);
+        }
+        if let Some(command) = &self.browser {
+            options = options.browser(command);
+        }
+        if let Some(spec) = &self.spec {
+            options = options.extra_spec(spec);
+        }
+        if let Some(spec) = self.spec_file_text() {
+            options = options.extra_spec(spec);
+        }
+        for field in &self.redact.fields {
+            options = options.redact_field(field);
+        }
+        for type_name in &self.redact.types {
+            options = options.redact_type(type_name);
+        }
+        options
+    }
+
+    /// The contents of [`ProjectConfig::spec_file`], read on first use. A
+    /// file that cannot be read is reported on stderr once and skipped.
+    fn spec_file_text(&self) -> Option<&str> {
+        self.spec_file_text
+            .get_or_init(|| {
+                let path = self.spec_file.as_ref()?;
+                fs::read_to_string(path)
+                    .map_err(|err| {
+                        eprintln!(
+                            "caraspace: could not read spec_file {}, skipping it: {err}",
+                            path.display()
+                        )
+                    })
+                    .ok()
+            })
+            .as_deref()
+    }
+
+    fn resolved(mut self, base: &Path) -> Self {
+        self.output_dir = self.output_dir.map(|dir| base.join(dir));
+        self.spec_file = self.spec_file.map(|file| base.join(file));
+        self
+    }
+}
+
+/// The configuration of the running project, found with
+/// [`ProjectConfig::find`] from `CARGO_MANIFEST_DIR` (or the current
+/// directory) on first use, along with its `spec_file`. A file that fails to
+/// load is reported on stderr once and ignored.
+pub fn project() -> Option<&'static ProjectConfig> {
+    static PROJECT: OnceLock<Option<ProjectConfig>> = OnceLock::new();
+    PROJECT
+        .get_or_init(|| {
+            let dir = env::var_os("CARGO_MANIFEST_DIR")
+                .map(PathBuf::from)
+                .or_else(|| env::current_dir().ok())?;
+            let config = ProjectConfig::find(dir).unwrap_or_else(|err| {
+                eprintln!("caraspace: ignoring project configuration: {err}");
+                None
+            })?;
+            config.spec_file_text();
+            Some(config)
+        })
+        .as_ref()
+}
+
+fn read(path: &Path) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|e| ConfigError(format!("{}: {e}", path.display())))
+}
+
+fn parent(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+/// Why a configuration file could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError(String);
+
+impl ConfigError {
+    /// Human-readable description, naming the file when there is one.
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,530 @@
+//! # Debug-Driven Export
+//!
+//! A second frontend for the relational [`jsondata`](crate::jsondata) shape,
+//! for values that implement [`Debug`] but not [`serde::Serialize`] (typically
+//! third-party types). It parses the `{:#?}` text back into structure and
+//! emits the same atoms and relations [`crate::export`] would, as far as the
+//! text allows.
+//!
+//! ## Recognized shapes
+//!
+//! | Debug text | Atoms / relations |
+//! |------------|-------------------|
+//! | `Foo { x: .. }` | `Foo` atom, `x(Foo, value)` |
+//! | `Foo(a)` | `newtype_struct` atom, `value(Foo, a)` |
+//! | `Foo(a, b)` | `tuple_struct` atom, `idx(Foo, "0", a)` |
+//! | `Foo` | `unit_struct` singleton |
+//! | `Some(x)` / `None` | same unwrapping and singleton as serde export |
+//! | `[a, b]`, `{a, b}` | `sequence` atom, `idx` |
+//! | `(a, b)` | `tuple` atom, `idx` |
+//! | `{k: v}` | `map` atom, `map_entry` |
+//! | `"s"`, `'c'`, `true` | `string`, `char`, `bool` |
+//! | `42`, `-1.5`, `NaN` | `int`, `float` |
+//!
+//! ## Limits
+//!
+//! Debug output is lossy compared to serde's semantic entry points: integer
+//! and float widths are unknown (`int`/`float` instead of `i32`/`f64`), enum
+//! variants are indistinguishable from structs of the same shape, and a
+//! hand-written `Debug` impl can print anything. Text that matches none of the
+//! shapes above becomes an opaque `debug` atom labelled with the raw text, so
+//! export never fails outright through [`export_debug_instance`].
+
+use crate::export::JsonDataSerializer;
+use crate::jsondata::JsonDataInstance;
+use std::fmt::{self, Debug, Display};
+
+/// Export any `Debug` value by parsing its `{:#?}` output.
+///
+/// If the text cannot be parsed at all, the instance holds a single `debug`
+/// atom labelled with the full text. Use [`try_export_debug_instance`] to see
+/// the parse error instead.
+pub fn export_debug_instance<T: Debug + ?Sized>(value: &T) -> JsonDataInstance {
+    let text = format!("{value:#?}");
+    parse_debug_instance(&text).unwrap_or_else(|_| {
+        let mut out = JsonDataSerializer::new();
+        out.emit_atom("debug", &text);
+        out.into_instance()
+    })
+}
+
+/// Fallible variant of [`export_debug_instance`].
+pub fn try_export_debug_instance<T: Debug + ?Sized>(
+    value: &T,
+) -> Result<JsonDataInstance, DebugParseError> {
+    parse_debug_instance(&format!("{value:#?}"))
+}
+
+/// Parse `Debug` text (pretty `{:#?}` or compact `{:?}`) into a data instance.
+pub fn parse_debug_instance(text: &str) -> Result<JsonDataInstance, DebugParseError> {
+    let mut parser = Parser {
+        src: text,
+        pos: 0,
+        out: JsonDataSerializer::new(),
+    };
+    parser.value()?;
+    parser.skip_ws();
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected trailing text"));
+    }
+    Ok(parser.out.into_instance())
+}
+
+/// Error returned by [`try_export_debug_instance`] and [`parse_debug_instance`]
+/// when the text does not follow `Debug`'s usual shapes.
+#[derive(Debug, Clone)]
+pub struct DebugParseError(String);
+
+impl DebugParseError {
+    /// Borrow the underlying message (includes the byte offset).
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for DebugParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "debug parse error: {}", self.0)
+    }
+}
+
+impl std::error::Error for DebugParseError {}
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+    out: JsonDataSerializer,
+}
+
+impl<'s> Parser<'s> {
+    fn error(&self, what: &str) -> DebugParseError {
+        DebugParseError(format!("{what} at byte {}", self.pos))
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), DebugParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    /// Parse one value and return its atom id.
+    fn value(&mut self) -> Result<String, DebugParseError> {
+        self.skip_ws();
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some('"') => {
+                let s = self.string_literal()?;
+                Ok(self.out.emit_atom("string", &s))
+            }
+            Some('\'') => match self.char_literal() {
+                Some(c) => Ok(self.out.emit_atom("char", &c.to_string())),
+                None => self.bare(),
+            },
+            Some('[') => {
+                self.bump();
+                self.sequence(']')
+            }
+            Some('(') => {
+                self.bump();
+                self.tuple()
+            }
+            Some('{') => {
+                self.bump();
+                if self.brace_is_map() {
+                    self.map()
+                } else {
+                    self.sequence('}')
+                }
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => self.named(),
+            Some(_) => self.bare(),
+        }
+    }
+
+    /// `Name`, `Name { .. }`, `Name(..)`, or a bare word like `true`/`inf`.
+    fn named(&mut self) -> Result<String, DebugParseError> {
+        let start = self.pos;
+        let name = self.path();
+        let after_name = self.pos;
+        self.skip_ws();
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                self.strukt(&name)
+            }
+            Some('(') => {
+                self.bump();
+                self.tuple_struct(&name)
+            }
+            None | Some(',' | ')' | ']' | '}' | ':') => {
+                self.pos = after_name;
+                Ok(self.word(&name))
+            }
+            Some(_) => {
+                self.pos = start;
+                self.bare()
+            }
+        }
+    }
+
+    /// Identifier path such as `Foo`, `std::time::Instant`, `PhantomData<u32>`.
+    fn path(&mut self) -> String {
+        let start = self.pos;
+        loop {
+            while self
+                .peek()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '#')
+            {
+                self.bump();
+            }
+            if self.rest().starts_with("::") {
+                self.pos += 2;
+            } else {
+                break;
+            }
+        }
+        if self.peek() == Some('<') {
+            let mut depth = 0usize;
+            while let Some(c) = self.bump() {
+                match c {
+                    '<' => depth += 1,
+                    '>' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.src[start..self.pos].to_string()
+    }
+
+    fn word(&mut self, word: &str) -> String {
+        match word {
+            "true" | "false" => self.out.get_or_create_singleton("bool", word),
+            "None" => self.out.get_or_create_singleton("None", "None"),
+            "inf" | "NaN" => self.out.emit_atom("float", word),
+            _ => self.out.get_or_create_singleton("unit_struct", word),
+        }
+    }
+
+    fn strukt(&mut self, name: &str) -> Result<String, DebugParseError> {
+        let struct_id = self.out.emit_atom(name, name);
+        loop {
+            if self.eat('}') {
+                return Ok(struct_id);
+            }
+            self.skip_ws();
+            if self.rest().starts_with("..") {
+                // `finish_non_exhaustive`
+                self.pos += 2;
+                continue;
+            }
+            let field = self.path();
+            if field.is_empty() {
+                return Err(self.error("expected field name"));
+            }
+            self.expect(':')?;
+            let field_id = self.value()?;
+            self.out.push_relation(
+                &field,
+                vec![struct_id.clone(), field_id],
+                vec![name, "atom"],
+            );
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(struct_id);
+            }
+        }
+    }
+
+    fn tuple_struct(&mut self, name: &str) -> Result<String, DebugParseError> {
+        if self.eat(')') {
+            return Ok(self.out.emit_atom("tuple_struct", name));
+        }
+        // The container goes before its items, but whether it is a newtype
+        // is only known once the first item is parsed.
+        let container = (name != "Some").then(|| {
+            (
+                self.out.atom_count(),
+                self.out.emit_atom("tuple_struct", name),
+            )
+        });
+        let first_id = self.value()?;
+        let single = if self.eat(')') {
+            true
+        } else {
+            self.expect(',')?;
+            self.eat(')')
+        };
+        if single {
+            let Some((index, struct_id)) = container else {
+                return Ok(self.out.wrap_some(first_id));
+            };
+            self.out.retype_atom(index, "newtype_struct");
+            self.out.push_relation(
+                "value",
+                vec![struct_id.clone(), first_id],
+                vec!["newtype_struct", "atom"],
+            );
+            return Ok(struct_id);
+        }
+        let struct_id = match container {
+            Some((_, id)) => id,
+            None => self.out.emit_atom("tuple_struct", name),
+        };
+        self.out.push_relation(
+            "idx",
+            vec![struct_id.clone(), "0".to_string(), first_id],
+            vec!["tuple_struct", "index", "atom"],
+        );
+        self.indexed(&struct_id, "tuple_struct", ')', 1)?;
+        Ok(struct_id)
+    }
+
+    fn tuple(&mut self) -> Result<String, DebugParseError> {
+        if self.eat(')') {
+            return Ok(self.out.get_or_create_singleton("unit", "()"));
+        }
+        let index = self.out.atom_count();
+        let tuple_id = self.out.emit_atom("tuple", "tuple");
+        let len = self.indexed(&tuple_id, "tuple", ')', 0)?;
+        self.out.relabel_atom(index, &format!("tuple[{len}]"));
+        Ok(tuple_id)
+    }
+
+    fn sequence(&mut self, close: char) -> Result<String, DebugParseError> {
+        let index = self.out.atom_count();
+        let seq_id = self.out.emit_atom("sequence", "seq");
+        let len = self.indexed(&seq_id, "sequence", close, 0)?;
+        self.out.relabel_atom(index, &format!("seq[{len}]"));
+        Ok(seq_id)
+    }
+
+    /// Comma-separated elements up to `close`, each linked by `idx`,
+    /// numbered from `index`. Returns the container's length.
+    fn indexed(
+        &mut self,
+        container_id: &str,
+        container_type: &str,
+        close: char,
+        mut index: usize,
+    ) -> Result<usize, DebugParseError> {
+        loop {
+            if self.eat(close) {
+                return Ok(index);
+            }
+            let element_id = self.value()?;
+            self.out.push_relation(
+                "idx",
+                vec![container_id.to_string(), index.to_string(), element_id],
+                vec![container_type, "index", "atom"],
+            );
+            index += 1;
+            if !self.eat(',') {
+                self.expect(close)?;
+                return Ok(index);
+            }
+        }
+    }
+
+    fn map(&mut self) -> Result<String, DebugParseError> {
+        let index = self.out.atom_count();
+        let map_id = self.out.emit_atom("map", "map");
+        let mut len = 0usize;
+        loop {
+            if self.eat('}') {
+                break;
+            }
+            let key_id = self.value()?;
+            self.expect(':')?;
+            let value_id = self.value()?;
+            self.out.push_relation(
+                "map_entry",
+                vec![map_id.clone(), key_id, value_id],
+                vec!["map", "atom", "atom"],
+            );
+            len += 1;
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+        self.out.relabel_atom(index, &format!("map[{len}]"));
+        Ok(map_id)
+    }
+
+    /// Anything else: numbers, or opaque text up to the next delimiter.
+    fn bare(&mut self) -> Result<String, DebugParseError> {
+        let start = self.pos;
+        let end = self.scan_item(Some(':')).0;
+        let text = self.src[start..end].trim();
+        if text.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+        self.pos = start + self.src[start..end].trim_end().len();
+        let typ = if text.parse::<i128>().is_ok() || text.parse::<u128>().is_ok() {
+            "int"
+        } else if text.parse::<f64>().is_ok() {
+            "float"
+        } else {
+            "debug"
+        };
+        Ok(self.out.emit_atom(typ, text))
+    }
+
+    fn string_literal(&mut self) -> Result<String, DebugParseError> {
+        self.bump(); // opening quote
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(self.escape()?),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// `'c'` with escapes; `None` if this isn't actually a char literal
+    /// (e.g. a lifetime in custom output), leaving the position unchanged.
+    fn char_literal(&mut self) -> Option<char> {
+        let start = self.pos;
+        let mut literal = || {
+            self.bump();
+            let c = match self.bump()? {
+                '\\' => self.escape().ok()?,
+                c => c,
+            };
+            (self.bump() == Some('\'')).then_some(c)
+        };
+        let c = literal();
+        if c.is_none() {
+            self.pos = start;
+        }
+        c
+    }
+
+    fn escape(&mut self) -> Result<char, DebugParseError> {
+        Ok(match self.bump() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('u') => {
+                self.expect('{')?;
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '}') {
+                    self.bump();
+                }
+                let hex = &self.src[start..self.pos];
+                self.expect('}')?;
+                u32::from_str_radix(hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            Some(c) => c,
+            None => return Err(self.error("unterminated escape")),
+        })
+    }
+
+    /// Whether the `{` just consumed opens a map (`{k: v}`) rather than a set.
+    fn brace_is_map(&self) -> bool {
+        let item = self.rest().trim_start();
+        if item.is_empty() || item.starts_with('}') {
+            return true;
+        }
+        self.scan_item(Some(':')).1 == Some(':')
+    }
+
+    fn scan_item(&self, stop_at: Option<char>) -> (usize, Option<char>) {
+        self.scan_from(self.pos, stop_at)
+    }
+
+    /// Scan forward from `from` to the end of the current item: the first
+    /// top-level `,` or closing bracket (or `stop_at`, when followed by
+    /// whitespace), skipping over nested brackets and quoted literals. Returns
+    /// the byte offset and the delimiter found.
+    fn scan_from(&self, from: usize, stop_at: Option<char>) -> (usize, Option<char>) {
+        let s = &self.src[from..];
+        let mut depth = 0usize;
+        let mut i = 0;
+        while let Some(c) = s[i..].chars().next() {
+            let mut next = i + c.len_utf8();
+            match c {
+                '"' => next = i + quoted_len(&s[i..], '"'),
+                '\'' => next = i + char_literal_len(&s[i..]).unwrap_or(1),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth == 0 => return (from + i, Some(c)),
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => return (from + i, Some(c)),
+                c if depth == 0
+                    && Some(c) == stop_at
+                    && s[next..].chars().next().map_or(true, char::is_whitespace) =>
+                {
+                    return (from + i, Some(c));
+                }
+                _ => {}
+            }
+            i = next;
+        }
+        (self.src.len(), None)
+    }
+}
+
+/// Byte length of the `quote`-delimited literal at the start of `s`
+/// (the whole rest of `s` if unterminated).
+fn quoted_len(s: &str, quote: char) -> usize {
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    s.len()
+}
+
+/// Byte length of the char literal at the start of `s`, if it is one.
+fn char_literal_len(s: &str) -> Option<usize> {
+    let mut chars = s.chars().skip(1);
+    match chars.next()? {
+        '\\' => Some(quoted_len(s, '\'')),
+        c => (chars.next()? == '\'').then_some(2 + c.len_utf8()),
+    }
+}
@@ -0,0 +1,567 @@
+//! Structural diff between two snapshots of the same value.
+//!
+//! [`diff`] exports both values, matches their atoms and returns a [`Diff`]:
+//! one merged instance holding everything from `after` plus whatever was
+//! removed from `before`, and decorators that colour it —
+//!
+//! | Change | Atoms | Edges |
+//! |--------|-------|-------|
+//! | added | green fill | green, thicker |
+//! | removed | red fill | red, dashed |
+//! | changed | yellow fill | — |
+//!
+//! An atom is *changed* when it matched but its label differs, or when one
+//! of its own fields was re-pointed or changed value.
+//!
+//! ## Matching
+//!
+//! Atom ids are assigned in serialization order, so they say nothing about
+//! identity across snapshots. [`Matching::Path`] matches atoms reached by
+//! the same field path from the root (`$/left/right`, `$/items/idx[2]`);
+//! right for values whose shape is stable. When values move around — a
+//! rotation in a balanced tree, an insertion at the front of a list — use
+//! [`Matching::Key`] (or [`diff_by_key`]) with a field that identifies an
+//! atom: atoms with that field match by type and key value, and everything
+//! below them by path from there.
+//!
+//! ```
+//! use caraspace::diff::diff_by_key;
+//! use caraspace::SpytialDecorators;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! struct Node {
+//!     key: u32,
+//!     next: Option<Box<Node>>,
+//! }
+//!
+//! let before = Node { key: 1, next: None };
+//! let after = Node { key: 0, next: Some(Box::new(Node { key: 1, next: None })) };
+//! let d = diff_by_key(&before, &after, "key");
+//! assert_eq!(d.added.len(), 2); // the new node and its key
+//! assert!(d.removed.is_empty());
+//! ```
+//!
+//! ## The merged instance
+//!
+//! Removed atoms keep their `before` id behind a `before:` prefix. To let
+//! selectors find the changes, the instance also carries one hidden marker
+//! atom per kind of change (type `DiffStatus`, labelled `added`, `removed`
+//! or `changed`) linked from each affected atom by a `diffStatus` field,
+//! and, per relation `r` with changes, hidden `diffAdded_r` /
+//! `diffRemoved_r` pairs naming the changed edges. [`Diff::decorators`]
+//! hides all of these.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::export::export_json_instance;
+use crate::jsondata::{IAtom, IRelation, ITuple, JsonDataInstance};
+use crate::spytial_annotations::{
+    self, HasSpytialDecorators, SpytialDecorators, SpytialDecoratorsBuilder,
+};
+
+/// Fill and stroke colours for each kind of change.
+pub const ADDED_COLOR: &str = "#2da44e";
+/// See [`ADDED_COLOR`].
+pub const REMOVED_COLOR: &str = "#cf222e";
+/// See [`ADDED_COLOR`].
+pub const CHANGED_COLOR: &str = "#f2cc60";
+
+/// Type of the hidden marker atoms that `diffStatus` points at.
+const STATUS_TYPE: &str = "DiffStatus";
+
+/// How atoms of the two snapshots are paired up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matching {
+    /// Match atoms reached by the same field path from the root.
+    Path,
+    /// Match atoms that have this field by their type and the field's value;
+    /// other atoms match by path from the nearest such atom (or the root).
+    Key(String),
+}
+
+/// The result of comparing two snapshots; see the [module docs](self).
+#[derive(Debug)]
+pub struct Diff {
+    /// `after`, plus the removed atoms and tuples of `before` and the
+    /// markers the highlighting selectors use.
+    pub instance: JsonDataInstance,
+    /// Highlighting decorators, after any decorators of the value's type.
+    pub decorators: SpytialDecorators,
+    /// Ids (in [`Diff::instance`]) of atoms only in `after`.
+    pub added: Vec<String>,
+    /// Ids (in [`Diff::instance`]) of atoms only in `before`.
+    pub removed: Vec<String>,
+    /// Ids (in [`Diff::instance`]) of matched atoms that changed.
+    pub changed: Vec<String>,
+    /// `(relation, atoms)` of tuples only in `after`.
+    pub added_tuples: Vec<(String, Vec<String>)>,
+    /// `(relation, atoms)` of tuples only in `before`, with atom ids
+    /// translated into [`Diff::instance`].
+    pub removed_tuples: Vec<(String, Vec<String>)>,
+}
+
+impl Diff {
+    /// Whether the two snapshots are structurally identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added_tuples.is_empty()
+            && self.removed_tuples.is_empty()
+    }
+
+    /// Open the highlighted diagram in the browser, like [`crate::diagram`].
+    pub fn diagram(&self) {
+        let spec = spytial_annotations::to_yaml(&self.decorators).unwrap_or_default();
+        crate::diagram_instance(&self.instance, &spec, &crate::options::default_options());
+    }
+
+    /// Render the highlighted diagram as SVG (see [`crate::svg`]).
+    pub fn to_svg(&self) -> String {
+        crate::svg::to_svg(&self.instance, &self.decorators)
+    }
+}
+
+/// Diff two values of the same type, matching atoms by path.
+///
+/// The result's decorators are `T`'s own followed by the highlighting.
+pub fn diff<T: HasSpytialDecorators + Serialize>(before: &T, after: &T) -> Diff {
+    let decorators = T::decorators();
+    with_decorators(
+        decorators,
+        diff_instances(
+            &export_json_instance(before),
+            &export_json_instance(after),
+            &Matching::Path,
+        ),
+    )
+}
+
+/// Diff two values of the same type, matching atoms by their `key` field.
+pub fn diff_by_key<T: HasSpytialDecorators + Serialize>(before: &T, after: &T, key: &str) -> Diff {
+    let decorators = T::decorators();
+    with_decorators(
+        decorators,
+        diff_instances(
+            &export_json_instance(before),
+            &export_json_instance(after),
+            &Matching::Key(key.to_string()),
+        ),
+    )
+}
+
+/// `T`'s decorators, which must be collected before exporting so that
+/// qualified type names are registered, followed by the highlighting.
+fn with_decorators(mut decorators: SpytialDecorators, mut d: Diff) -> Diff {
+    decorators.constraints.append(&mut d.decorators.constraints);
+    decorators.directives.append(&mut d.decorators.directives);
+    d.decorators = decorators;
+    d
+}
+
+/// Diff two exported instances. The result carries only the highlighting
+/// decorators.
+pub fn diff_instances(
+    before: &JsonDataInstance,
+    after: &JsonDataInstance,
+    matching: &Matching,
+) -> Diff {
+    let key = match matching {
+        Matching::Path => None,
+        Matching::Key(field) => Some(field.as_str()),
+    };
+    let before_paths = paths(before, key);
+    let by_path: HashMap<&str, usize> = before_paths
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| Some((p.as_deref()?, i)))
+        .collect();
+    // Atoms only match within a type: a `None` replaced by a node is a
+    // removal and an addition, not a change.
+    let mut matched_before: HashMap<usize, usize> = HashMap::new();
+    for (i, path) in paths(after, key).iter().enumerate() {
+        if let Some(&b) = path.as_deref().and_then(|p| by_path.get(p)) {
+            if before.atoms[b].r#type == after.atoms[i].r#type {
+                matched_before.insert(b, i);
+            }
+        }
+    }
+    // Leaves left over pair up by type and label: a shared `None` or unit
+    // variant is one atom reached by whichever path the walk found first.
+    let mut matched_after: HashSet<usize> = matched_before.values().copied().collect();
+    let (before_leaves, after_leaves) = (leaves(before), leaves(after));
+    let mut spare: HashMap<(&str, &str), VecDeque<usize>> = HashMap::new();
+    for b in
+        (0..before.atoms.len()).filter(|b| before_leaves[*b] && !matched_before.contains_key(b))
+    {
+        let atom = &before.atoms[b];
+        spare
+            .entry((atom.r#type.as_str(), atom.label.as_str()))
+            .or_default()
+            .push_back(b);
+    }
+    let unmatched: Vec<usize> = (0..after.atoms.len())
+        .filter(|a| after_leaves[*a] && !matched_after.contains(a))
+        .collect();
+    for a in unmatched {
+        let atom = &after.atoms[a];
+        if let Some(b) = spare
+            .get_mut(&(atom.r#type.as_str(), atom.label.as_str()))
+            .and_then(VecDeque::pop_front)
+        {
+            matched_before.insert(b, a);
+            matched_after.insert(a);
+        }
+    }
+
+    // `before` ids translated into the merged instance.
+    let before_index = atom_index(before);
+    let translate = |id: &str| -> String {
+        match before_index.get(id) {
+            Some(b) => match matched_before.get(b) {
+                Some(&a) => after.atoms[a].id.clone(),
+                None => format!("before:{id}"),
+            },
+            None => id.to_string(),
+        }
+    };
+
+    let mut added: Vec<String> = Vec::new();
+    let mut changed: BTreeSet<String> = BTreeSet::new();
+    for (i, atom) in after.atoms.iter().enumerate() {
+        if !matched_after.contains(&i) {
+            added.push(atom.id.clone());
+        }
+    }
+    for (&b, &a) in &matched_before {
+        let (old, new) = (&before.atoms[b], &after.atoms[a]);
+        if old.label != new.label {
+            changed.insert(new.id.clone());
+        }
+    }
+    let removed: Vec<String> = (0..before.atoms.len())
+        .filter(|b| !matched_before.contains_key(b))
+        .map(|b| format!("before:{}", before.atoms[b].id))
+        .collect();
+
+    // Tuples, compared after translating `before` into `after` ids.
+    let mut relations: Vec<IRelation> = after.relations.clone();
+    let after_tuples: HashSet<(&str, Vec<&str>)> = after
+        .relations
+        .iter()
+        .flat_map(|r| {
+            r.tuples.iter().map(|t| {
+                (
+                    r.name.as_str(),
+                    t.atoms.iter().map(String::as_str).collect(),
+                )
+            })
+        })
+        .collect();
+    let mut before_tuples: HashSet<(String, Vec<String>)> = HashSet::new();
+    let mut removed_tuples: Vec<(String, Vec<String>)> = Vec::new();
+    for r in sorted(&before.relations) {
+        for t in &r.tuples {
+            let atoms: Vec<String> = t.atoms.iter().map(|id| translate(id)).collect();
+            let view: Vec<&str> = atoms.iter().map(String::as_str).collect();
+            before_tuples.insert((r.name.clone(), atoms.clone()));
+            if after_tuples.contains(&(r.name.as_str(), view)) {
+                continue;
+            }
+            removed_tuples.push((r.name.clone(), atoms.clone()));
+            let tuple = ITuple {
+                atoms,
+                types: t.types.clone(),
+            };
+            match relations.iter_mut().find(|m| m.name == r.name) {
+                Some(m) => m.tuples.push(tuple),
+                None => relations.push(IRelation {
+                    id: r.id.clone(),
+                    name: r.name.clone(),
+                    types: r.types.clone(),
+                    tuples: vec![tuple],
+                }),
+            }
+        }
+    }
+    let mut added_tuples: Vec<(String, Vec<String>)> = Vec::new();
+    for r in sorted(&after.relations) {
+        for t in &r.tuples {
+            let key = (r.name.clone(), t.atoms.clone());
+            if !before_tuples.contains(&key) {
+                added_tuples.push(key);
+            }
+        }
+    }
+
+    // A matched atom also changed when one of its fields was re-pointed, or
+    // now leads to a leaf whose value changed (e.g. a hidden `u32` shown as
+    // an attribute).
+    let added_set: HashSet<&str> = added.iter().map(String::as_str).collect();
+    let after_index = atom_index(after);
+    let is_leaf = |id: &str| after_index.get(id).is_some_and(|&i| after_leaves[i]);
+    let mut owners: Vec<String> = Vec::new();
+    for (_, atoms) in added_tuples.iter().chain(&removed_tuples) {
+        owners.extend(atoms.first().cloned());
+    }
+    for r in &after.relations {
+        for t in &r.tuples {
+            if let (Some(from), Some(to)) = (t.atoms.first(), t.atoms.last()) {
+                if changed.contains(to) && is_leaf(to) {
+                    owners.push(from.clone());
+                }
+            }
+        }
+    }
+    for owner in owners {
+        if !added_set.contains(owner.as_str()) && !owner.starts_with("before:") {
+            changed.insert(owner);
+        }
+    }
+    let changed: Vec<String> = after
+        .atoms
+        .iter()
+        .filter(|a| changed.contains(&a.id))
+        .map(|a| a.id.clone())
+        .collect();
+
+    let mut atoms: Vec<IAtom> = after.atoms.clone();
+    for b in (0..before.atoms.len()).filter(|b| !matched_before.contains_key(b)) {
+        let atom = &before.atoms[b];
+        atoms.push(IAtom {
+            id: format!("before:{}", atom.id),
+            r#type: atom.r#type.clone(),
+            label: atom.label.clone(),
+        });
+    }
+
+    let mut instance = JsonDataInstance::new(atoms, relations);
+    let decorators = highlight(
+        &mut instance,
+        &added,
+        &removed,
+        &changed,
+        &added_tuples,
+        &removed_tuples,
+    );
+    Diff {
+        instance,
+        decorators,
+        added,
+        removed,
+        changed,
+        added_tuples,
+        removed_tuples,
+    }
+}
+
+/// Add the marker atoms and pair relations to `instance` and build the
+/// decorators that colour through them.
+fn highlight(
+    instance: &mut JsonDataInstance,
+    added: &[String],
+    removed: &[String],
+    changed: &[String],
+    added_tuples: &[(String, Vec<String>)],
+    removed_tuples: &[(String, Vec<String>)],
+) -> SpytialDecorators {
+    let types: HashMap<String, String> = instance
+        .atoms
+        .iter()
+        .map(|a| (a.id.clone(), a.r#type.clone()))
+        .collect();
+    let type_of = |id: &str| types.get(id).cloned().unwrap_or_default();
+
+    let mut builder = SpytialDecoratorsBuilder::new();
+    let mut status: Vec<ITuple> = Vec::new();
+    for (ids, label, color) in [
+        (added, "added", ADDED_COLOR),
+        (removed, "removed", REMOVED_COLOR),
+        (changed, "changed", CHANGED_COLOR),
+    ] {
+        if ids.is_empty() {
+            continue;
+        }
+        let marker_id = format!("diff:{label}");
+        instance.atoms.push(IAtom {
+            id: marker_id.clone(),
+            r#type: STATUS_TYPE.to_string(),
+            label: label.to_string(),
+        });
+        for id in ids {
+            status.push(ITuple {
+                atoms: vec![id.clone(), marker_id.clone()],
+                types: vec![type_of(id), STATUS_TYPE.to_string()],
+            });
+        }
+        builder = builder.atom_color(
+            &format!("diffStatus.{{m : {STATUS_TYPE} | @:(m) = {label}}}"),
+            color,
+        );
+    }
+    if !status.is_empty() {
+        instance.relations.push(IRelation {
+            id: "diffStatus".into(),
+            name: "diffStatus".into(),
+            types: vec!["univ".into(), STATUS_TYPE.into()],
+            tuples: status,
+        });
+        builder = builder.hide_atom(STATUS_TYPE);
+    }
+
+    for (tuples, prefix, color, style, weight) in [
+        (added_tuples, "diffAdded_", ADDED_COLOR, None, Some(2.5)),
+        (
+            removed_tuples,
+            "diffRemoved_",
+            REMOVED_COLOR,
+            Some("dashed"),
+            None,
+        ),
+    ] {
+        let mut pairs: Vec<(&str, Vec<ITuple>)> = Vec::new();
+        for (relation, atoms) in tuples {
+            let (Some(from), Some(to)) = (atoms.first(), atoms.last()) else {
+                continue;
+            };
+            let tuple = ITuple {
+                atoms: vec![from.clone(), to.clone()],
+                types: vec![type_of(from), type_of(to)],
+            };
+            match pairs.iter_mut().find(|(r, _)| r == relation) {
+                Some((_, ts)) => ts.push(tuple),
+                None => pairs.push((relation, vec![tuple])),
+            }
+        }
+        for (relation, tuples) in pairs {
+            let name = format!("{prefix}{relation}");
+            instance.relations.push(IRelation {
+                id: name.clone(),
+                name: name.clone(),
+                types: vec!["univ".into(), "univ".into()],
+                tuples,
+            });
+            builder = builder.hide_field(&name, None).edge_style(
+                relation,
+                color,
+                Some(&name),
+                None,
+                style,
+                weight,
+                None,
+                None,
+            );
+        }
+    }
+    builder.build()
+}
+
+/// Each atom's path from the root (see [`Matching`]), or `None` when it is
+/// unreachable and not keyed.
+fn paths(instance: &JsonDataInstance, key: Option<&str>) -> Vec<Option<String>> {
+    let index = atom_index(instance);
+    let label = |id: &str| match index.get(id) {
+        Some(&i) => instance.atoms[i].label.clone(),
+        None => id.to_string(),
+    };
+
+    // Outgoing `(segment, child)` steps, relations in name order.
+    let mut steps: Vec<Vec<(String, usize)>> = vec![Vec::new(); instance.atoms.len()];
+    let mut anchors: Vec<Option<String>> = vec![None; instance.atoms.len()];
+    for r in sorted(&instance.relations) {
+        // A unary tuple marks an atom rather than linking it to another.
+        for t in r.tuples.iter().filter(|t| t.atoms.len() >= 2) {
+            let Some(&from) = t.atoms.first().and_then(|id| index.get(id.as_str())) else {
+                continue;
+            };
+            let last = t.atoms.len() - 1;
+            if Some(r.name.as_str()) == key && last == 1 {
+                let atom = &instance.atoms[from];
+                anchors[from] = Some(format!("@{}={}", atom.r#type, label(&t.atoms[1])));
+            }
+            let middle: Vec<String> = t.atoms[1..last].iter().map(|id| label(id)).collect();
+            let segment = if middle.is_empty() {
+                r.name.clone()
+            } else {
+                format!("{}[{}]", r.name, middle.join(", "))
+            };
+            for (j, id) in t.atoms.iter().enumerate().skip(1) {
+                if let Some(&child) = index.get(id.as_str()) {
+                    let step = if j == last {
+                        segment.clone()
+                    } else {
+                        format!("{segment}#{j}")
+                    };
+                    steps[from].push((step, child));
+                }
+            }
+        }
+    }
+
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut claim = |path: String| -> String {
+        let mut unique = path.clone();
+        let mut n = 1;
+        while !taken.insert(unique.clone()) {
+            n += 1;
+            unique = format!("{path}~{n}");
+        }
+        unique
+    };
+    let mut paths: Vec<Option<String>> = anchors.into_iter().map(|a| a.map(&mut claim)).collect();
+    let mut seen = vec![false; instance.atoms.len()];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    if !instance.atoms.is_empty() {
+        if paths[0].is_none() {
+            paths[0] = Some(claim("$".into()));
+        }
+        seen[0] = true;
+        queue.push_back(0);
+    }
+    while let Some(at) = queue.pop_front() {
+        let base = paths[at].clone().unwrap_or_default();
+        for (step, child) in &steps[at] {
+            if std::mem::replace(&mut seen[*child], true) {
+                continue;
+            }
+            if paths[*child].is_none() {
+                paths[*child] = Some(claim(format!("{base}/{step}")));
+            }
+            queue.push_back(*child);
+        }
+    }
+    paths
+}
+
+/// Atoms that start no tuple.
+fn leaves(instance: &JsonDataInstance) -> Vec<bool> {
+    let index = atom_index(instance);
+    let mut leaf = vec![true; instance.atoms.len()];
+    for r in &instance.relations {
+        for t in &r.tuples {
+            if let Some(&i) = t.atoms.first().and_then(|id| index.get(id.as_str())) {
+                leaf[i] = false;
+            }
+        }
+    }
+    leaf
+}
+
+fn atom_index(instance: &JsonDataInstance) -> HashMap<&str, usize> {
+    instance
+        .atoms
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (a.id.as_str(), i))
+        .collect()
+}
+
+/// Relations in name order: the instance keeps them in hash order.
+fn sorted(relations: &[IRelation]) -> Vec<&IRelation> {
+    let mut sorted: Vec<&IRelation> = relations.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    sorted
+}
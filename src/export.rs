@@ -25,6 +25,7 @@
 //! | `(T1, T2, T3)` | `idx(tuple, "0", elem)` | Fixed positional semantics |
 //! | `HashMap<K,V>` | `map_entry(map, key, val)` | Associative key→value lookup |
 //! | `Point(x, y)` | `idx(point, "0", x_coord)` | Named but positional fields |
+//! | `SharedRef<Rc<T>>` | pointee's atom, once per allocation | Aliasing is heap identity |
 //!
 //! ## Visualization Benefits
 //!
@@ -54,9 +55,18 @@
 //! - **Primitive types** preserved exactly (`i32`, `f64`, `string`)
 //! - **Collection types** use semantic names (`sequence`, `tuple`, `map`)
 //! - **Relations** carry type information for both ends of relationships
+//!
+//! ## Other Output Formats
+//!
+//! An exported instance can also be handed to existing graph tooling:
+//! [`to_dot`] renders it as Graphviz DOT with the decorators translated, and
+//! [`to_mermaid`] / [`to_plantuml`] produce text diagrams that Markdown
+//! renderers draw inline.
 
 use crate::jsondata::*;
-use crate::spytial_annotations::SpytialDecorators;
+use crate::shared::SHARED_TOKEN;
+use crate::spytial_annotations::runtime::bare_type_name;
+use crate::spytial_annotations::{qualified_type_name, SpytialDecorators};
 use serde::ser;
 use serde::ser::{
     Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
@@ -65,20 +75,32 @@ use serde::ser::{
 use std::collections::HashMap;
 use std::fmt;
 
+mod dot;
+mod mermaid;
+mod plantuml;
+
+pub use dot::to_dot;
+pub use mermaid::to_mermaid;
+pub use plantuml::to_plantuml;
+
 /// Export a Rust data structure to our JSON instance format using custom Serde serialization.
 ///
 /// Returns an empty [`JsonDataInstance`] if the value's `Serialize` impl fails. Use
 /// [`try_export_json_instance`] when you need to distinguish success from failure.
+///
+/// Types marked `#[spytial(type_name = "...")]` or `#[spytial(qualified)]`
+/// register their exported name when their `decorators()` first runs, and
+/// this function only knows `T: Serialize`. [`diagram`](crate::diagram),
+/// [`render`](fn@crate::render), [`diff`](fn@crate::diff) and the other
+/// entry points that take a decorated type collect its decorators before
+/// exporting; call `T::decorators()` first when exporting one directly.
 pub fn export_json_instance<T: Serialize>(value: &T) -> JsonDataInstance {
     try_export_json_instance(value).unwrap_or_else(|err| {
         eprintln!(
             "caraspace: serialization failed, returning empty instance: {}",
             err.message()
         );
-        JsonDataInstance {
-            atoms: Vec::new(),
-            relations: Vec::new(),
-        }
+        JsonDataInstance::default()
     })
 }
 
@@ -88,11 +110,8 @@ pub fn try_export_json_instance<T: Serialize>(
     value: &T,
 ) -> Result<JsonDataInstance, SerializationError> {
     let mut serializer = JsonDataSerializer::new();
-    value.serialize(&mut serializer)?;
-    Ok(JsonDataInstance {
-        atoms: serializer.atoms,
-        relations: serializer.relations.into_values().collect(),
-    })
+    serializer.child(value)?;
+    Ok(serializer.into_instance())
 }
 
 /// Export a Rust data structure and collect SpyTial decorators from all encountered types.
@@ -108,13 +127,7 @@ pub fn export_json_instance_with_decorators<T: Serialize>(
             "caraspace: serialization failed, returning empty instance: {}",
             err.message()
         );
-        (
-            JsonDataInstance {
-                atoms: Vec::new(),
-                relations: Vec::new(),
-            },
-            SpytialDecorators::default(),
-        )
+        (JsonDataInstance::default(), SpytialDecorators::default())
     })
 }
 
@@ -125,18 +138,17 @@ pub fn try_export_json_instance_with_decorators<T: Serialize>(
 ) -> Result<(JsonDataInstance, SpytialDecorators), SerializationError> {
     let mut serializer = JsonDataSerializer::new();
     serializer.exclude_type = Some(root_type_name.to_string());
-    value.serialize(&mut serializer)?;
-    let instance = JsonDataInstance {
-        atoms: serializer.atoms,
-        relations: serializer.relations.into_values().collect(),
-    };
-    Ok((instance, serializer.collected_decorators))
+    serializer.child(value)?;
+    let decorators = std::mem::take(&mut serializer.collected_decorators);
+    Ok((serializer.into_instance(), decorators))
 }
 
 /// Custom Serde serializer that preserves semantic structure for different collection types.
 ///
 /// This type is an implementation detail of [`export_json_instance`] and
-/// [`try_export_json_instance`] and is not part of the public API.
+/// [`try_export_json_instance`] and is not part of the public API. The
+/// [`crate::debug_export`] frontend drives its atom/relation helpers directly
+/// so both frontends share one id scheme and singleton table.
 pub(crate) struct JsonDataSerializer {
     counter: usize,
     atoms: Vec<IAtom>,
@@ -146,10 +158,22 @@ pub(crate) struct JsonDataSerializer {
     exclude_type: Option<String>,
     /// Cache for singleton atoms (like None, unit, etc.) that should be reused
     singleton_atoms: HashMap<(String, String), String>, // (type, label) -> atom_id
+    /// Atoms already emitted for shared pointers, keyed by allocation address
+    /// (see [`crate::shared`])
+    shared_atoms: HashMap<usize, String>,
+    /// Shared allocations currently being serialized, mapped to the index of
+    /// the first atom emitted for them; a revisit is a reference cycle
+    shared_in_progress: HashMap<usize, usize>,
+    /// Set while a shared pointer's pointee is about to be emitted: that
+    /// atom stands for one allocation, so it must not be a reused singleton
+    fresh_pointee: bool,
+    /// Full Rust type name of the value about to be serialized, when known,
+    /// to tell apart same-named types (see [`Self::resolve_type`])
+    rust_type: Option<&'static str>,
 }
 
 impl JsonDataSerializer {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             counter: 0,
             atoms: vec![],
@@ -158,7 +182,43 @@ impl JsonDataSerializer {
             visited_types: std::collections::HashSet::new(),
             exclude_type: None,
             singleton_atoms: HashMap::new(),
+            shared_atoms: HashMap::new(),
+            shared_in_progress: HashMap::new(),
+            fresh_pointee: false,
+            rust_type: None,
+        }
+    }
+
+    /// Serialize `value` into this instance, noting its Rust type first.
+    pub(crate) fn child<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<String, SerializationError> {
+        self.rust_type = Some(std::any::type_name::<T>());
+        value.serialize(&mut *self)
+    }
+
+    /// The atom type for the struct or enum serde calls `name`, and the full
+    /// Rust type name it was recognised as, if any.
+    ///
+    /// The Rust type of the value being serialized names the type itself,
+    /// possibly inside wrappers (`alloc::boxed::Box<app::ast::Node>`). A
+    /// type registered with [`register_type_name`] there is exported under
+    /// its registered name; anything else keeps serde's `name`.
+    fn resolve_type(&mut self, name: &str) -> (String, Option<&'static str>) {
+        let Some(rust_type) = self.rust_type.take() else {
+            return (name.to_string(), None);
+        };
+        let mut found = None;
+        for candidate in type_candidates(rust_type) {
+            if let Some(exported) = qualified_type_name(candidate, name) {
+                return (exported, Some(candidate));
+            }
+            if found.is_none() && bare_type_name(candidate) == name {
+                found = Some(candidate);
+            }
         }
+        (name.to_string(), found)
     }
 
     fn fresh_id(&mut self) -> String {
@@ -167,7 +227,8 @@ impl JsonDataSerializer {
         id
     }
 
-    fn emit_atom(&mut self, typ: &str, label: &str) -> String {
+    pub(crate) fn emit_atom(&mut self, typ: &str, label: &str) -> String {
+        self.fresh_pointee = false;
         let id = self.fresh_id();
         self.atoms.push(IAtom {
             id: id.clone(),
@@ -177,9 +238,31 @@ impl JsonDataSerializer {
         id
     }
 
+    /// Number of atoms emitted so far: the index the next one will have.
+    pub(crate) fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// Change the type of the atom at `index`, for a container whose kind
+    /// is only known once its items are parsed.
+    pub(crate) fn retype_atom(&mut self, index: usize, typ: &str) {
+        self.atoms[index].r#type = typ.to_string();
+    }
+
+    /// Change the label of the atom at `index`, e.g. a container whose
+    /// length is only known once its items are parsed.
+    pub(crate) fn relabel_atom(&mut self, index: usize, label: &str) {
+        self.atoms[index].label = label.to_string();
+    }
+
     /// Get or create a singleton atom - atoms that should only exist once
     /// (like None, unit, true, false, etc.)
-    fn get_or_create_singleton(&mut self, typ: &str, label: &str) -> String {
+    pub(crate) fn get_or_create_singleton(&mut self, typ: &str, label: &str) -> String {
+        // A shared pointee gets an atom of its own, or reify would hand one
+        // pointer to every owner of e.g. `None`.
+        if self.fresh_pointee {
+            return self.emit_atom(typ, label);
+        }
         let key = (typ.to_string(), label.to_string());
 
         if let Some(existing_id) = self.singleton_atoms.get(&key) {
@@ -192,7 +275,7 @@ impl JsonDataSerializer {
         id
     }
 
-    fn push_relation(&mut self, name: &str, atoms: Vec<String>, types: Vec<&str>) {
+    pub(crate) fn push_relation(&mut self, name: &str, atoms: Vec<String>, types: Vec<&str>) {
         let types: Vec<String> = types.iter().map(|s| s.to_string()).collect();
         let tuple = ITuple {
             atoms,
@@ -208,6 +291,34 @@ impl JsonDataSerializer {
         rel.tuples.push(tuple);
     }
 
+    /// Finish the export: atoms in emission order, relations grouped by name.
+    pub(crate) fn into_instance(self) -> JsonDataInstance {
+        JsonDataInstance::new(self.atoms, self.relations.into_values().collect())
+    }
+
+    /// Result atom for `Some(inner)`: `inner` itself, unless it is absent or
+    /// optional (a `None` singleton or another `Some` wrapper) — then a `Some`
+    /// wrapper atom, so `Some(None)` stays distinct from `None`.
+    pub(crate) fn wrap_some(&mut self, inner_id: String) -> String {
+        let inner_is_optionish = self
+            .atoms
+            .iter()
+            .find(|a| a.id == inner_id)
+            .map(|a| a.r#type == "None" || a.r#type == "Some")
+            .unwrap_or(false);
+        if inner_is_optionish {
+            let some_id = self.emit_atom("Some", "Some");
+            self.push_relation(
+                "value",
+                vec![some_id.clone(), inner_id],
+                vec!["Some", "atom"],
+            );
+            some_id
+        } else {
+            inner_id
+        }
+    }
+
     /// Merge decorators for `type_name` into the collected set, if it has any
     /// registered and we haven't already visited it this run.
     ///
@@ -215,18 +326,22 @@ impl JsonDataSerializer {
     /// derive macro emits as part of the compile-time decorator walk. So by the
     /// time a value's `Serialize` impl visits a struct, the registry should
     /// already contain that struct's entry (when one exists).
-    fn collect_decorators_for_type(&mut self, type_name: &str) {
+    ///
+    /// `rust_type`, the full Rust type name when known, picks the right entry
+    /// among same-named types.
+    fn collect_decorators_for_type(&mut self, type_name: &str, rust_type: Option<&str>) {
         if let Some(ref exclude) = self.exclude_type {
             if type_name == exclude {
                 return;
             }
         }
 
-        if !self.visited_types.insert(type_name.to_string()) {
+        let key = rust_type.unwrap_or(type_name);
+        if !self.visited_types.insert(key.to_string()) {
             return;
         }
 
-        if let Some(type_decorators) = crate::spytial_annotations::get_type_decorators(type_name) {
+        if let Some(type_decorators) = crate::spytial_annotations::get_type_decorators(key) {
             self.collected_decorators
                 .constraints
                 .extend(type_decorators.constraints);
@@ -237,6 +352,46 @@ impl JsonDataSerializer {
     }
 }
 
+/// Every type named in the full type name `rust_type`, outermost first and
+/// with its type arguments: `alloc::boxed::Box<app::Node<u8>>` gives itself,
+/// `app::Node<u8>` and `u8`.
+fn type_candidates(rust_type: &str) -> Vec<&str> {
+    let bytes = rust_type.as_bytes();
+    let in_path = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b':' || b >= 0x80;
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !in_path(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && in_path(bytes[i]) {
+            i += 1;
+        }
+        let mut end = i;
+        if bytes.get(i) == Some(&b'<') {
+            let mut depth = 0;
+            for j in i..bytes.len() {
+                match bytes[j] {
+                    b'<' => depth += 1,
+                    // `->` in a function type does not close anything
+                    b'>' if bytes[j - 1] != b'-' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = j + 1;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        out.push(&rust_type[start..end]);
+    }
+    out
+}
+
 /// Error returned by [`try_export_json_instance`] and friends when a value's
 /// `Serialize` implementation fails. Wraps the underlying serializer message.
 #[derive(Debug, Clone)]
@@ -355,24 +510,8 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
         // inner is itself absent/optional — a `None` singleton or another `Some`
         // wrapper — insert a `Some` wrapper atom so `Some(None)` stays distinct
         // from `None` and arbitrarily nested options remain recoverable.
-        let inner_id = value.serialize(&mut *self)?;
-        let inner_is_optionish = self
-            .atoms
-            .iter()
-            .find(|a| a.id == inner_id)
-            .map(|a| a.r#type == "None" || a.r#type == "Some")
-            .unwrap_or(false);
-        if inner_is_optionish {
-            let some_id = self.emit_atom("Some", "Some");
-            self.push_relation(
-                "value",
-                vec![some_id.clone(), inner_id],
-                vec!["Some", "atom"],
-            );
-            Ok(some_id)
-        } else {
-            Ok(inner_id)
-        }
+        let inner_id = self.child(value)?;
+        Ok(self.wrap_some(inner_id))
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
@@ -393,7 +532,8 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
     ) -> Result<Self::Ok, Self::Error> {
         // Unit variants are singletons - Color::Red is always the same value
         // This is similar to None, (), true, false - zero-sized types with no data
-        Ok(self.get_or_create_singleton(enum_name, variant))
+        let (enum_type, _) = self.resolve_type(enum_name);
+        Ok(self.get_or_create_singleton(&enum_type, variant))
     }
 
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
@@ -401,8 +541,38 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
         name: &str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error> {
+        // A shared pointer is transparent: it *is* its pointee's atom, and a
+        // second visit to the same allocation reuses that atom.
+        if name == SHARED_TOKEN {
+            if let Some(addr) = crate::shared::take_pending_addr() {
+                if let Some(existing_id) = self.shared_atoms.get(&addr) {
+                    return Ok(existing_id.clone());
+                }
+                // Revisiting an allocation we are still inside of is a
+                // reference cycle: point back at the first atom it emitted
+                // (a struct/container atom is emitted before its children).
+                if let Some(&start) = self.shared_in_progress.get(&addr) {
+                    return self.atoms.get(start).map(|a| a.id.clone()).ok_or_else(|| {
+                        SerializationError(
+                            "reference cycle reached a shared pointer before its atom was emitted"
+                                .to_string(),
+                        )
+                    });
+                }
+                self.shared_in_progress.insert(addr, self.atoms.len());
+                self.fresh_pointee = true;
+                let result = self.child(value);
+                self.fresh_pointee = false;
+                self.shared_in_progress.remove(&addr);
+                let id = result?;
+                self.shared_atoms.insert(addr, id.clone());
+                return Ok(id);
+            }
+            return self.child(value);
+        }
+
         let struct_id = self.emit_atom("newtype_struct", name);
-        let inner_id = value.serialize(&mut *self)?;
+        let inner_id = self.child(value)?;
         self.push_relation(
             "value",
             vec![struct_id.clone(), inner_id],
@@ -418,12 +588,13 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
         variant: &str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        let variant_id = self.emit_atom(enum_name, variant);
-        let inner_id = value.serialize(&mut *self)?;
+        let (enum_type, _) = self.resolve_type(enum_name);
+        let variant_id = self.emit_atom(&enum_type, variant);
+        let inner_id = self.child(value)?;
         self.push_relation(
             "variant_value",
             vec![variant_id.clone(), inner_id],
-            vec![enum_name, "atom"],
+            vec![&enum_type, "atom"],
         );
         Ok(variant_id)
     }
@@ -536,11 +707,12 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
         variant: &str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        let variant_id = self.emit_atom(enum_name, variant);
+        let (enum_type, _) = self.resolve_type(enum_name);
+        let variant_id = self.emit_atom(&enum_type, variant);
         Ok(TupleVariantSerializer {
             serializer: self,
             variant_id,
-            variant_type: enum_name.to_string(),
+            variant_type: enum_type,
             index: 0,
         })
     }
@@ -616,15 +788,17 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
         name: &str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        let struct_id = self.emit_atom(name, name); // struct name IS the type
+        // struct name IS the type (qualified, if the type asked to be)
+        let (struct_type, rust_type) = self.resolve_type(name);
+        let struct_id = self.emit_atom(&struct_type, name);
 
         // Collect decorators for this struct type
-        self.collect_decorators_for_type(name);
+        self.collect_decorators_for_type(name, rust_type);
 
         Ok(StructSerializer {
             serializer: self,
             struct_id,
-            struct_type: name.to_string(),
+            struct_type,
         })
     }
 
@@ -651,11 +825,12 @@ impl<'a> Serializer for &'a mut JsonDataSerializer {
         variant: &str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        let variant_id = self.emit_atom(enum_name, variant);
+        let (enum_type, _) = self.resolve_type(enum_name);
+        let variant_id = self.emit_atom(&enum_type, variant);
         Ok(StructVariantSerializer {
             serializer: self,
             variant_id,
-            variant_type: enum_name.to_string(),
+            variant_type: enum_type,
         })
     }
 }
@@ -681,7 +856,7 @@ impl<'a> SerializeSeq for SequenceSerializer<'a> {
     type Error = SerializationError;
 
     fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
-        let element_id = value.serialize(&mut *self.serializer)?;
+        let element_id = self.serializer.child(value)?;
         // idx(container, position, element) for O(1) indexable sequences
         self.serializer.push_relation(
             "idx",
@@ -709,7 +884,7 @@ impl<'a> SerializeTuple for TupleSerializer<'a> {
     type Error = SerializationError;
 
     fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
-        let element_id = value.serialize(&mut *self.serializer)?;
+        let element_id = self.serializer.child(value)?;
         // Tuples also use idx - fixed positional semantics
         self.serializer.push_relation(
             "idx",
@@ -737,7 +912,7 @@ impl<'a> SerializeTupleStruct for TupleStructSerializer<'a> {
     type Error = SerializationError;
 
     fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
-        let field_id = value.serialize(&mut *self.serializer)?;
+        let field_id = self.serializer.child(value)?;
         // Tuple structs have positional semantics
         self.serializer.push_relation(
             "idx",
@@ -765,7 +940,7 @@ impl<'a> SerializeTupleVariant for TupleVariantSerializer<'a> {
     type Error = SerializationError;
 
     fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
-        let field_id = value.serialize(&mut *self.serializer)?;
+        let field_id = self.serializer.child(value)?;
         self.serializer.push_relation(
             "idx",
             vec![self.variant_id.clone(), self.index.to_string(), field_id],
@@ -802,12 +977,12 @@ impl<'a> SerializeMap for MapSerializer<'a> {
     type Error = SerializationError;
 
     fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
-        self.key_id = Some(key.serialize(&mut *self.serializer)?);
+        self.key_id = Some(self.serializer.child(key)?);
         Ok(())
     }
 
     fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
-        let value_id = value.serialize(&mut *self.serializer)?;
+        let value_id = self.serializer.child(value)?;
         if let Some(key_id) = self.key_id.take() {
             // map_entry(map, key, value) for associative collections
             self.serializer.push_relation(
@@ -850,7 +1025,7 @@ impl<'a> SerializeStruct for StructSerializer<'a> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        let field_id = value.serialize(&mut *self.serializer)?;
+        let field_id = self.serializer.child(value)?;
         // Use field name as relation name: field_name(StructType, value)
         self.serializer.push_relation(
             key,
@@ -880,7 +1055,7 @@ impl<'a> SerializeStructVariant for StructVariantSerializer<'a> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        let field_id = value.serialize(&mut *self.serializer)?;
+        let field_id = self.serializer.child(value)?;
         // Enum struct variants also use field names as relations
         self.serializer.push_relation(
             key,
@@ -0,0 +1,107 @@
+//! Mermaid flowchart output for an exported instance.
+
+use std::fmt::Write as _;
+
+use crate::jsondata::JsonDataInstance;
+use crate::scene::{is_dark, plain_color, Scene};
+use crate::spytial_annotations::SpytialDecorators;
+
+/// Render `instance` as a Mermaid `flowchart`, applying `decorators`.
+///
+/// The output is plain Mermaid source: wrap it in a ` ```mermaid ` fence and
+/// GitHub, GitLab and mdBook (with the mermaid preprocessor) draw it inline,
+/// with no HTML bundle to ship. Nodes, edges and decorators follow
+/// [`to_dot`](super::to_dot): `atomColor` becomes a `style` line, `edgeColor` a
+/// `linkStyle` (dashed and dotted edges use `-.->`), `group` a `subgraph`,
+/// and `attribute` extra label lines. Colours other than `#rgb`, `#rrggbb`
+/// or a name are left out, since Mermaid reads a `,` or `;` in them as the
+/// end of the style. Nodes are named `n0`, `n1`, ... in
+/// atom order, so ids that are not Mermaid identifiers (`end`, `a b`) are
+/// never written out.
+///
+/// ```
+/// use caraspace::export::{export_json_instance, to_mermaid};
+///
+/// let chart = to_mermaid(&export_json_instance(&vec![1, 2]), &Default::default());
+/// assert!(chart.starts_with("flowchart TD\n"));
+/// assert!(chart.contains("-->|idx[0]|"));
+/// ```
+pub fn to_mermaid(instance: &JsonDataInstance, decorators: &SpytialDecorators) -> String {
+    let scene = Scene::build(instance, decorators);
+    let mut out = String::from("flowchart TD\n");
+
+    for (i, node) in scene.nodes.iter().enumerate() {
+        let mut label = escape(&node.label);
+        for (field, value) in &node.attributes {
+            let _ = write!(label, "<br/>{}: {}", escape(field), escape(value));
+        }
+        let _ = writeln!(out, "    n{i}[\"{label}\"]");
+    }
+
+    for (n, members) in scene.clusters() {
+        let _ = writeln!(
+            out,
+            "    subgraph group{n}[\"{}\"]",
+            escape(&scene.groups[n].name)
+        );
+        for m in members {
+            let _ = writeln!(out, "        n{m}");
+        }
+        out.push_str("    end\n");
+    }
+
+    let mut link_styles = Vec::new();
+    for (i, edge) in scene.edges.iter().enumerate() {
+        let arrow = match edge.style.as_deref() {
+            Some("dashed" | "dotted") => "-.->",
+            _ => "-->",
+        };
+        let label = if edge.show_label {
+            format!("|{}|", escape(&edge.label))
+        } else {
+            String::new()
+        };
+        let _ = writeln!(out, "    n{} {arrow}{label} n{}", edge.from, edge.to);
+        let mut style = Vec::new();
+        if let Some(color) = edge.color.as_deref().and_then(plain_color) {
+            style.push(format!("stroke:{color}"));
+            style.push(format!("color:{color}"));
+        }
+        if let Some(weight) = edge.weight {
+            style.push(format!("stroke-width:{weight}px"));
+        }
+        if !style.is_empty() {
+            link_styles.push(format!("    linkStyle {i} {}", style.join(",")));
+        }
+    }
+    for line in link_styles {
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    for (i, node) in scene.nodes.iter().enumerate() {
+        if let Some(color) = node.color.as_deref().and_then(plain_color) {
+            let ink = if is_dark(color) { ",color:#fff" } else { "" };
+            let _ = writeln!(out, "    style n{i} fill:{color}{ink}");
+        }
+    }
+
+    out
+}
+
+/// Mermaid entity codes for everything that would end a quoted label or an
+/// edge label early, or be read as markup.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' | '#' | '|' | '<' | '>' | '&' | '`' => {
+                let _ = write!(out, "#{};", c as u32);
+            }
+            '\n' => out.push_str("<br/>"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
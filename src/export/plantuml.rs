@@ -0,0 +1,104 @@
+//! PlantUML object-diagram output for an exported instance.
+
+use std::fmt::Write as _;
+
+use crate::jsondata::JsonDataInstance;
+use crate::scene::{plain_color, Scene};
+use crate::spytial_annotations::SpytialDecorators;
+
+/// Render `instance` as a PlantUML object diagram, applying `decorators`.
+///
+/// Each atom becomes an `object`, and `attribute` fields become its
+/// `field = value` body lines. Edges, colours and groups follow
+/// [`to_dot`](super::to_dot): `atomColor` becomes the object's background,
+/// `edgeColor` an inline arrow style such as `-[#red,dashed]->`, and `group`
+/// a `package`; colours other than `#rgb`, `#rrggbb` or a name are left
+/// out. Objects are named `n0`, `n1`, ... in atom order, whatever
+/// their ids.
+///
+/// ```
+/// use caraspace::export::{export_json_instance, to_plantuml};
+///
+/// let uml = to_plantuml(&export_json_instance(&(1, "a")), &Default::default());
+/// assert!(uml.starts_with("@startuml\n"));
+/// assert!(uml.trim_end().ends_with("@enduml"));
+/// ```
+pub fn to_plantuml(instance: &JsonDataInstance, decorators: &SpytialDecorators) -> String {
+    let scene = Scene::build(instance, decorators);
+    let mut out = String::from("@startuml\n");
+
+    let object = |out: &mut String, i: usize, indent: &str| {
+        let node = &scene.nodes[i];
+        let _ = write!(out, "{indent}object \"{}\" as n{i}", name(&node.label));
+        if let Some(color) = node.color.as_deref().and_then(plain_color) {
+            let _ = write!(out, " {}", colour(color));
+        }
+        if node.attributes.is_empty() {
+            out.push('\n');
+            return;
+        }
+        out.push_str(" {\n");
+        for (field, value) in &node.attributes {
+            let _ = writeln!(out, "{indent}  {} = {}", line(field), line(value));
+        }
+        let _ = writeln!(out, "{indent}}}");
+    };
+
+    let mut placed = vec![false; scene.nodes.len()];
+    for (n, members) in scene.clusters() {
+        let _ = writeln!(out, "package \"{}\" {{", name(&scene.groups[n].name));
+        for m in members {
+            placed[m] = true;
+            object(&mut out, m, "  ");
+        }
+        out.push_str("}\n");
+    }
+    for i in (0..scene.nodes.len()).filter(|&i| !placed[i]) {
+        object(&mut out, i, "");
+    }
+
+    for edge in &scene.edges {
+        let mut style = Vec::new();
+        if let Some(color) = edge.color.as_deref().and_then(plain_color) {
+            style.push(colour(color));
+        }
+        if let Some(s @ ("dashed" | "dotted" | "bold")) = edge.style.as_deref() {
+            style.push(s.to_string());
+        }
+        if let Some(weight) = edge.weight {
+            style.push(format!("thickness={weight}"));
+        }
+        let arrow = if style.is_empty() {
+            "-->".to_string()
+        } else {
+            format!("-[{}]->", style.join(","))
+        };
+        let _ = write!(out, "n{} {arrow} n{}", edge.from, edge.to);
+        if edge.show_label {
+            let _ = write!(out, " : {}", line(&edge.label));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("@enduml\n");
+    out
+}
+
+/// PlantUML colours are written `#name` or `#rrggbb`.
+fn colour(c: &str) -> String {
+    if c.starts_with('#') {
+        c.to_string()
+    } else {
+        format!("#{c}")
+    }
+}
+
+/// Text for inside a double-quoted name.
+fn name(s: &str) -> String {
+    line(s).replace('"', "&#34;")
+}
+
+/// Text that must stay on one line.
+fn line(s: &str) -> String {
+    s.replace('\r', "").replace('\n', "\\n")
+}
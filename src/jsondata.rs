@@ -5,8 +5,24 @@
 //! [`IRelation`](crate::jsondata::IRelation) edges grouped by name. The same
 //! shape is what spytial-core consumes on the JavaScript side — these structs
 //! are part of the public, stable API.
+//!
+//! # Wire format
+//!
+//! Serialized instances carry a `version` field ([`FORMAT_VERSION`]); JSON
+//! without one is read as version 1, the format caraspace wrote before the
+//! marker existed. [`json_schema`] describes the format, and the same schema
+//! is published with the guide. Tools that write instances by hand should run
+//! them through [`validate`], which catches what the schema cannot: dangling
+//! atom ids, tuples of the wrong arity, and duplicate ids. It also reports
+//! empty tuples and arity-0 relations, which the schema rules out too.
 
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
 
 /// A relational instance: the full set of atoms (nodes) and relations (edges)
 /// extracted from a single Rust value.
@@ -23,47 +39,411 @@ use serde::Serialize;
 /// takes an explicit id for callers that build or reorder an instance themselves.
 ///
 /// There is intentionally **no `rootId` field**. For `export` output it would be
-/// redundant (the root is always `atoms[0]`, and the data is acyclic so the root
-/// is also recoverable as the atom that no relation targets), and it would not
-/// survive a spytial-core round-trip anyway, since unknown JSON keys are dropped.
+/// redundant (the root is always `atoms[0]`, and unless a [`shared`] reference
+/// cycle runs through it, the root is also the atom that no relation targets),
+/// and it would not survive a spytial-core round-trip anyway, since unknown JSON
+/// keys are dropped.
 /// A future producer that needs an explicit root should pass it to
 /// [`from_datum_root`] rather than rely on a field that silently disappears.
+/// (The `version` marker is dropped by spytial-core too; it is only for
+/// tools reading and writing the format.)
 ///
 /// [`export_json_instance`]: crate::export_json_instance
 /// [`from_datum`]: crate::from_datum
 /// [`from_datum_root`]: crate::from_datum_root
-#[derive(Serialize, Debug)]
+/// [`shared`]: crate::shared
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[schemars(
+    title = "caraspace relational instance",
+    description = "Atoms and relations extracted from one Rust value. atoms[0] is the root."
+)]
 pub struct JsonDataInstance {
     /// All atoms (graph nodes), in serialization order — `atoms[0]` is the root
     /// (see the "Root atom" note on [`JsonDataInstance`]).
+    #[schemars(description = "")]
     pub atoms: Vec<IAtom>,
     /// All relations (edges), grouped by relation name.
+    #[schemars(description = "")]
     pub relations: Vec<IRelation>,
+    /// Wire-format version, [`FORMAT_VERSION`] for anything caraspace builds.
+    #[serde(default = "unversioned")]
+    #[schemars(
+        description = "Format version; absent means 1.",
+        range(min = 1, max = FORMAT_VERSION)
+    )]
+    pub version: u32,
+}
+
+/// Version of the instance format this build reads and writes.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Instances written before the `version` field existed are version 1.
+fn unversioned() -> u32 {
+    1
+}
+
+impl Default for JsonDataInstance {
+    fn default() -> Self {
+        Self::new(Vec::new(), Vec::new())
+    }
+}
+
+impl JsonDataInstance {
+    /// An instance in the current format version.
+    pub fn new(atoms: Vec<IAtom>, relations: Vec<IRelation>) -> Self {
+        Self {
+            atoms,
+            relations,
+            version: FORMAT_VERSION,
+        }
+    }
+
+    /// Parse an instance from the JSON that caraspace writes (or that
+    /// `serde_json::to_string` produces for a [`JsonDataInstance`]).
+    ///
+    /// Instances from a newer format version are rejected. The instance is
+    /// not otherwise checked; see [`validate`].
+    pub fn from_json_str(json: &str) -> Result<Self, LoadError> {
+        serde_json::from_str(json)
+            .map_err(|e| LoadError(format!("invalid instance JSON: {e}")))
+            .and_then(Self::supported)
+    }
+
+    /// Like [`JsonDataInstance::from_json_str`], reading from `reader`.
+    pub fn from_reader(reader: impl Read) -> Result<Self, LoadError> {
+        serde_json::from_reader(reader)
+            .map_err(|e| LoadError(format!("invalid instance JSON: {e}")))
+            .and_then(Self::supported)
+    }
+
+    fn supported(self) -> Result<Self, LoadError> {
+        if self.version > FORMAT_VERSION {
+            return Err(LoadError(format!(
+                "instance format version {} is newer than this caraspace supports ({FORMAT_VERSION})",
+                self.version
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// A problem [`validate`] found in an instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// `version` is not a format version this build understands.
+    UnsupportedVersion {
+        /// The instance's `version`.
+        found: u32,
+    },
+    /// Two atoms share an id.
+    DuplicateAtomId {
+        /// The repeated id.
+        id: String,
+    },
+    /// Two relations share an id.
+    DuplicateRelationId {
+        /// The repeated id.
+        id: String,
+    },
+    /// A tuple names an atom that is not in `atoms`.
+    DanglingAtom {
+        /// Name of the relation holding the tuple.
+        relation: String,
+        /// Index of the tuple within the relation.
+        tuple: usize,
+        /// Position of the id within the tuple.
+        position: usize,
+        /// The unknown atom id.
+        id: String,
+    },
+    /// A tuple's length differs from its relation's `types`.
+    ArityMismatch {
+        /// Name of the relation holding the tuple.
+        relation: String,
+        /// Index of the tuple within the relation.
+        tuple: usize,
+        /// Length of the relation's `types`.
+        expected: usize,
+        /// Length of the tuple's `atoms`.
+        found: usize,
+    },
+    /// A relation's `types` is empty: it has arity 0.
+    NullaryRelation {
+        /// Name of the relation.
+        relation: String,
+    },
+    /// A tuple has no atoms.
+    EmptyTuple {
+        /// Name of the relation holding the tuple.
+        relation: String,
+        /// Index of the tuple within the relation.
+        tuple: usize,
+    },
+    /// A tuple's `types` and `atoms` have different lengths.
+    TupleTypesMismatch {
+        /// Name of the relation holding the tuple.
+        relation: String,
+        /// Index of the tuple within the relation.
+        tuple: usize,
+        /// Length of the tuple's `atoms`.
+        atoms: usize,
+        /// Length of the tuple's `types`.
+        types: usize,
+    },
 }
 
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion { found } => write!(
+                f,
+                "format version {found} is not supported (expected at most {FORMAT_VERSION})"
+            ),
+            Self::DuplicateAtomId { id } => write!(f, "duplicate atom id `{id}`"),
+            Self::DuplicateRelationId { id } => write!(f, "duplicate relation id `{id}`"),
+            Self::DanglingAtom {
+                relation,
+                tuple,
+                position,
+                id,
+            } => write!(
+                f,
+                "{relation}[{tuple}] position {position} names unknown atom `{id}`"
+            ),
+            Self::ArityMismatch {
+                relation,
+                tuple,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{relation}[{tuple}] has {found} atoms, but the relation has arity {expected}"
+            ),
+            Self::NullaryRelation { relation } => {
+                write!(f, "{relation} has no types: relations need arity 1 or more")
+            }
+            Self::EmptyTuple { relation, tuple } => write!(f, "{relation}[{tuple}] has no atoms"),
+            Self::TupleTypesMismatch {
+                relation,
+                tuple,
+                atoms,
+                types,
+            } => write!(f, "{relation}[{tuple}] has {atoms} atoms but {types} types"),
+        }
+    }
+}
+
+/// Check the invariants the JSON Schema cannot express.
+///
+/// Positions typed `index` (the middle column of `idx` tuples) hold raw
+/// indices rather than atom ids and are not checked for dangling references.
+/// An empty result means the instance is well formed.
+pub fn validate(instance: &JsonDataInstance) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    if instance.version == 0 || instance.version > FORMAT_VERSION {
+        issues.push(ValidationIssue::UnsupportedVersion {
+            found: instance.version,
+        });
+    }
+
+    let mut atom_ids = HashSet::new();
+    for atom in &instance.atoms {
+        if !atom_ids.insert(atom.id.as_str()) {
+            issues.push(ValidationIssue::DuplicateAtomId {
+                id: atom.id.clone(),
+            });
+        }
+    }
+
+    let mut relation_ids = HashSet::new();
+    for relation in &instance.relations {
+        if !relation_ids.insert(relation.id.as_str()) {
+            issues.push(ValidationIssue::DuplicateRelationId {
+                id: relation.id.clone(),
+            });
+        }
+        if relation.types.is_empty() {
+            issues.push(ValidationIssue::NullaryRelation {
+                relation: relation.name.clone(),
+            });
+        }
+        for (t, tuple) in relation.tuples.iter().enumerate() {
+            if tuple.atoms.is_empty() {
+                issues.push(ValidationIssue::EmptyTuple {
+                    relation: relation.name.clone(),
+                    tuple: t,
+                });
+            }
+            if tuple.atoms.len() != relation.types.len() {
+                issues.push(ValidationIssue::ArityMismatch {
+                    relation: relation.name.clone(),
+                    tuple: t,
+                    expected: relation.types.len(),
+                    found: tuple.atoms.len(),
+                });
+            }
+            if tuple.types.len() != tuple.atoms.len() {
+                issues.push(ValidationIssue::TupleTypesMismatch {
+                    relation: relation.name.clone(),
+                    tuple: t,
+                    atoms: tuple.atoms.len(),
+                    types: tuple.types.len(),
+                });
+            }
+            for (position, id) in tuple.atoms.iter().enumerate() {
+                let raw = tuple.types.get(position).map(String::as_str) == Some("index");
+                if !raw && !atom_ids.contains(id.as_str()) {
+                    issues.push(ValidationIssue::DanglingAtom {
+                        relation: relation.name.clone(),
+                        tuple: t,
+                        position,
+                        id: id.clone(),
+                    });
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// JSON Schema (draft 2020-12) for a serialized [`JsonDataInstance`].
+///
+/// Derived from the Rust types themselves, so the field names, types and
+/// required keys cannot drift from what [`JsonDataInstance`] serializes.
+/// This is the schema published as `internals/instance.schema.json` in the
+/// guide.
+pub fn json_schema() -> serde_json::Value {
+    let mut schema = schemars::schema_for!(JsonDataInstance);
+    schema.insert(
+        "$id".into(),
+        "https://sidprasad.github.io/caraspace/internals/instance.schema.json".into(),
+    );
+    schema.to_value()
+}
+
+/// An instance and its YAML spec, recovered from a diagram HTML page.
+#[derive(Debug, Clone)]
+pub struct SavedDiagram {
+    /// The diagrammed instance.
+    pub instance: JsonDataInstance,
+    /// The SpyTial YAML spec the page was rendered with (may be empty).
+    pub spec: String,
+}
+
+/// Extract the embedded instance and spec from the HTML of a diagram page
+/// written by [`crate::diagram`] and friends.
+///
+/// Pages from caraspace releases that embedded the data in JS template
+/// literals are read too. For a [`crate::Timeline`] page this is the first
+/// step.
+///
+/// ```no_run
+/// use caraspace::jsondata::load_html;
+///
+/// let saved = load_html("target/ci-failure.html").unwrap();
+/// let tree: Vec<u32> = caraspace::from_datum(&saved.instance).unwrap();
+/// ```
+pub fn from_html(html: &str) -> Result<SavedDiagram, LoadError> {
+    let (data, spec) = match (
+        script_block(html, "spytial-data"),
+        script_block(html, "spytial-spec"),
+    ) {
+        (Some(data), Some(spec)) => {
+            let spec: String = serde_json::from_str(spec)
+                .map_err(|e| LoadError(format!("invalid embedded spec: {e}")))?;
+            (data.to_string(), spec)
+        }
+        _ => match (
+            template_literal(html, "jsonData"),
+            template_literal(html, "spytialSpec"),
+        ) {
+            (Some(data), Some(spec)) => (data.to_string(), spec.to_string()),
+            _ => return Err(LoadError("no embedded caraspace diagram found".into())),
+        },
+    };
+    Ok(SavedDiagram {
+        instance: JsonDataInstance::from_json_str(&data)?,
+        spec,
+    })
+}
+
+/// [`from_html`] on the contents of the file at `path`.
+pub fn load_html(path: impl AsRef<Path>) -> Result<SavedDiagram, LoadError> {
+    let path = path.as_ref();
+    let html = fs::read_to_string(path)
+        .map_err(|e| LoadError(format!("could not read {}: {e}", path.display())))?;
+    from_html(&html)
+}
+
+/// Text of `<script type="application/json" id="{id}">`.
+fn script_block<'a>(html: &'a str, id: &str) -> Option<&'a str> {
+    let open = format!(r#"<script type="application/json" id="{id}">"#);
+    let start = html.find(&open)? + open.len();
+    let len = html[start..].find("</script>")?;
+    Some(&html[start..start + len])
+}
+
+/// Body of a legacy `` const {name} = `...`; `` declaration. Those pages
+/// spliced the JSON in verbatim.
+fn template_literal<'a>(html: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("const {name} = `");
+    let start = html.find(&open)? + open.len();
+    let len = html[start..].find("`;")?;
+    Some(&html[start..start + len])
+}
+
+/// Error returned when an instance cannot be read back from JSON or HTML.
+#[derive(Debug, Clone)]
+pub struct LoadError(String);
+
+impl LoadError {
+    /// Borrow the underlying message.
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "load error: {}", self.0)
+    }
+}
+
+impl std::error::Error for LoadError {}
+
 /// A single atom — one node in the relational graph.
 ///
 /// Atoms are created from struct instances, collection containers (sequence,
 /// tuple, map), and primitive leaves. `id` is unique within the instance,
 /// `type` is the Rust type name (e.g. `"Person"`, `"i32"`, `"sequence"`),
 /// and `label` is the human-readable text shown in the diagram.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[schemars(rename = "atom", description = "")]
 pub struct IAtom {
     /// Unique identifier within the enclosing [`JsonDataInstance`].
+    #[schemars(description = "Unique within the instance.")]
     pub id: String,
     /// Type name (e.g. struct name or `"sequence"`/`"tuple"`/`"map"`/`"i32"`).
+    #[schemars(description = "Rust type name, or sequence/tuple/map.")]
     pub r#type: String,
     /// Human-readable label shown on the diagram.
+    #[schemars(description = "Text shown on the diagram.")]
     pub label: String,
 }
 
 /// A single tuple within a relation: the participating atoms and the type
 /// of each position.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[schemars(rename = "tuple", description = "")]
 pub struct ITuple {
     /// Atom IDs in this tuple, in position order.
+    #[schemars(
+        description = "Atom ids, except at positions typed index, which hold raw indices.",
+        length(min = 1)
+    )]
     pub atoms: Vec<String>,
     /// Type names of the atoms in this tuple, parallel to [`Self::atoms`].
+    #[schemars(description = "Type of each position, parallel to atoms.")]
     pub types: Vec<String>,
 }
 
@@ -72,14 +452,22 @@ pub struct ITuple {
 ///
 /// Examples: a field relation `name(Person, string)`, a sequence relation
 /// `idx(sequence, index, T)`, or a map relation `map_entry(map, K, V)`.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[schemars(rename = "relation", description = "")]
 pub struct IRelation {
     /// Stable identifier for the relation (currently the same as [`Self::name`]).
+    #[schemars(description = "Unique within the instance.")]
     pub id: String,
     /// Relation name — `"idx"`, `"map_entry"`, or a struct field name.
+    #[schemars(description = "")]
     pub name: String,
     /// Type names for each position in the tuples, in position order.
+    #[schemars(
+        description = "Type of each position; every tuple has this arity.",
+        length(min = 1)
+    )]
     pub types: Vec<String>,
     /// All tuples belonging to this relation.
+    #[schemars(description = "")]
     pub tuples: Vec<ITuple>,
 }
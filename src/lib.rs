@@ -12,48 +12,62 @@
 
 #![deny(missing_docs)]
 
+/// Project-wide defaults read from `caraspace.toml` or `[package.metadata.caraspace]`.
+pub mod config;
+/// Export of `Debug`-only values by parsing their `{:#?}` text into the [`jsondata`] shape.
+pub mod debug_export;
+/// Structural diff of two snapshots, highlighted as one diagram.
+pub mod diff;
 /// Serde-driven export of Rust values into the relational [`jsondata`] shape.
 pub mod export;
 /// Serializable atom/relation data model consumed by spytial-core.
 pub mod jsondata;
+/// Live-updating diagram server (`SPYTIAL_MODE=serve`).
+pub mod live;
+/// Per-call and process-wide diagram settings ([`diagram_with`], [`set_default_options`]).
+pub mod options;
 /// Reconstruct Rust values from the relational [`jsondata`] shape (inverse of [`export`]).
 pub mod reify;
+/// Rendered diagrams returned as values ([`render`](fn@render)) instead of only opened.
+pub mod render;
+/// Parse and evaluate spytial selectors against an instance, without a browser.
+pub mod selector;
+/// Opt-in `Rc`/`Arc` sharing: one atom per allocation instead of one per owner.
+pub mod shared;
+/// Hand rendered diagrams to custom code instead of a file and a browser.
+pub mod sink;
 /// SpyTial decorator types, derive-macro runtime, and YAML serialization.
 pub mod spytial_annotations;
+/// Static SVG rendering with a native layout; no browser involved.
+pub mod svg;
+/// Box-drawing text rendering for terminals and headless sessions.
+pub mod terminal;
+/// Record successive snapshots and scrub through them on one page.
+pub mod timeline;
 
+mod scene;
+
+pub use debug_export::export_debug_instance;
+pub use diff::{diff, Diff};
 pub use export::export_json_instance;
+pub use options::{set_default_options, DiagramOptions, OutputFormat, Theme};
 pub use reify::{from_datum, from_datum_root, replit, replit_root, ReifyError};
+pub use render::{render, render_with, DiagramError, RenderedDiagram};
+pub use shared::SharedRef;
+pub use timeline::Timeline;
 // Re-export the derive macro for spatial annotations
 pub use caraspace_export_macros::SpytialDecorators;
+use options::OutputFormat as Format;
 use serde::Serialize;
-use std::env;
+use sink::Artifact;
+use std::fmt::Debug;
 use std::fs;
+use std::io;
 use std::path::PathBuf;
-use std::process::{self, Command};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::SystemTime;
-
-static DIAGRAM_COUNTER: AtomicU64 = AtomicU64::new(0);
+use std::process::Command;
 
-/// Pick the path where the rendered HTML diagram should be written.
-///
-/// If `SPYTIAL_OUTPUT_PATH` is set, that path is used verbatim (used by the Docker
-/// setup, which needs a stable filename to serve). Otherwise a unique path under
-/// the OS temp dir is generated using pid + an atomic counter + nanoseconds, so
-/// concurrent `dbg!` calls do not trample each other's files.
-fn diagram_output_path() -> PathBuf {
-    if let Ok(explicit) = env::var("SPYTIAL_OUTPUT_PATH") {
-        return PathBuf::from(explicit);
-    }
-
-    let pid = process::id();
-    let counter = DIAGRAM_COUNTER.fetch_add(1, Ordering::Relaxed);
-    let nanos = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map(|d| d.subsec_nanos())
-        .unwrap_or(0);
-    env::temp_dir().join(format!("caraspace-{pid}-{counter}-{nanos}.html"))
-}
+/// Page title used when [`DiagramOptions::title`] sets none.
+pub(crate) const DEFAULT_TITLE: &str = "CaraSpace";
 
 /// Creates a diagram of the given data structure and opens it in the browser.
 ///
@@ -93,9 +107,39 @@ fn diagram_output_path() -> PathBuf {
 /// };
 /// diagram(&company);  // Shows decorators from both Company AND Person
 /// ```
+///
+/// Output location, format and browser opening follow
+/// [`options::default_options`]; use [`diagram_with`] to choose them per call,
+/// and [`render`](fn@render) to get the page and any error back.
 pub fn diagram<T: spytial_annotations::HasSpytialDecorators + Serialize>(value: &T) {
+    diagram_with(value, &options::default_options());
+}
+
+/// Like [`diagram`], with explicit [`DiagramOptions`] instead of the
+/// process-wide defaults.
+///
+/// ```no_run
+/// use caraspace::{diagram_with, DiagramOptions, SpytialDecorators};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, SpytialDecorators)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let options = DiagramOptions::new()
+///     .output_path("target/point.html")
+///     .title("Origin")
+///     .open(false);
+/// diagram_with(&Point { x: 0, y: 0 }, &options);
+/// ```
+pub fn diagram_with<T: spytial_annotations::HasSpytialDecorators + Serialize>(
+    value: &T,
+    options: &DiagramOptions,
+) {
     let spytial_spec = collect_spytial_spec_for_diagram(value);
-    diagram_impl(value, &spytial_spec);
+    diagram_impl(value, &spytial_spec, options);
 }
 
 /// Collect SpyTial specification using compile-time decorator collection.
@@ -119,7 +163,200 @@ fn collect_spytial_spec_for_diagram<T: spytial_annotations::HasSpytialDecorators
 /// This allows you to provide a custom SpyTial specification instead of using
 /// the automatic compile-time decorator collection.
 pub fn diagram_with_spec<T: Serialize>(value: &T, spec: &str) {
-    diagram_impl(value, spec);
+    diagram_impl(value, spec, &options::default_options());
+}
+
+/// Creates a diagram of a value that only implements [`Debug`].
+///
+/// The value is exported by parsing its `{:#?}` text (see [`debug_export`]),
+/// so it needs no `Serialize` or `SpytialDecorators` impl — useful for
+/// third-party types. The diagram carries no decorators, and type information
+/// is coarser than the serde path: prefer [`diagram`] when both are available.
+pub fn diagram_debug<T: Debug + ?Sized>(value: &T) {
+    diagram_instance(
+        &export_debug_instance(value),
+        "",
+        &options::default_options(),
+    );
+}
+
+/// Renders a value as a self-contained SVG image, without a browser.
+///
+/// Uses the same compile-time decorator collection as [`diagram`], laid out
+/// natively in Rust (see [`svg`] for which decorators a still image honors).
+/// Handy for code review, PR comments and docs.
+///
+/// ```
+/// use caraspace::{render_svg, SpytialDecorators};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, SpytialDecorators)]
+/// #[atom_color(selector = "Point", value = "#ffcc00")]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let image = render_svg(&Point { x: 1, y: 2 });
+/// assert!(image.contains("#ffcc00"));
+/// ```
+pub fn render_svg<T: spytial_annotations::HasSpytialDecorators + Serialize>(value: &T) -> String {
+    let decorators = T::decorators();
+    svg::to_svg(&export_json_instance(value), &decorators)
+}
+
+/// Render the standalone HTML page for `json_instance` and `spec`.
+fn render_html(
+    json_instance: &jsondata::JsonDataInstance,
+    spec: &str,
+    options: &DiagramOptions,
+) -> serde_json::Result<String> {
+    Ok(html_page(
+        &serde_json::to_string_pretty(json_instance)?,
+        &serde_json::to_string(spec)?,
+        "null",
+        "null",
+        options.title.as_deref().unwrap_or(DEFAULT_TITLE),
+        options.theme,
+    ))
+}
+
+/// Fill `templates/template.html` with the vendored assets, the page title
+/// and theme, and four JSON texts: the instance, the spec (as a JSON string), the
+/// live-update settings (`null` outside [`live`] mode) and the [`Timeline`]
+/// steps (`null` for a single diagram).
+///
+/// The JSON lands in `<script type="application/json">` blocks after
+/// [`script_safe`] escaping, so no field value can end the block or run as
+/// script. Placeholders are filled in a single pass, so a value that
+/// happens to contain another placeholder is left alone.
+pub(crate) fn html_page(
+    json_data: &str,
+    spec_json: &str,
+    live_json: &str,
+    timeline_json: &str,
+    title: &str,
+    theme: Theme,
+) -> String {
+    fill_template(
+        include_str!("../templates/template.html"),
+        &[
+            (
+                "/*__SPYTIAL_CORE_CSS__*/",
+                include_str!("../templates/vendor/spytial-core.css"),
+            ),
+            (
+                "/*__REACT_COMPONENTS_CSS__*/",
+                include_str!("../templates/vendor/react-component-integration.css"),
+            ),
+            (
+                "/*__SPYTIAL_CORE_JS__*/",
+                include_str!("../templates/vendor/spytial-core.global.js"),
+            ),
+            (
+                "/*__REACT_COMPONENTS_JS__*/",
+                include_str!("../templates/vendor/react-component-integration.global.js"),
+            ),
+            ("{{ json_data }}", &script_safe(json_data)),
+            ("{{ spytial_spec }}", &script_safe(spec_json)),
+            ("{{ live }}", &script_safe(live_json)),
+            ("{{ timeline }}", &script_safe(timeline_json)),
+            ("{{ title }}", &svg::escape(title)),
+            ("{{ theme }}", theme.as_str()),
+        ],
+    )
+}
+
+/// Make JSON text safe to embed in an HTML `<script>` element.
+///
+/// `<`, `>` and `&` only ever occur inside JSON strings, where their
+/// `\\uXXXX` escapes mean the same thing, so `</script>` and `<!--` cannot
+/// appear in the output. U+2028/U+2029 are escaped too for older JS
+/// parsers.
+fn script_safe(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => out.push_str("\\u003c"),
+            '>' => out.push_str("\\u003e"),
+            '&' => out.push_str("\\u0026"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Replace each `(placeholder, value)` in `template`, scanning the template
+/// once so substituted values are never searched again.
+fn fill_template(template: &str, slots: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(
+        template.len() + slots.iter().map(|(_, value)| value.len()).sum::<usize>(),
+    );
+    let mut rest = template;
+    while let Some((at, placeholder, value)) = slots
+        .iter()
+        .filter_map(|&(placeholder, value)| {
+            rest.find(placeholder).map(|at| (at, placeholder, value))
+        })
+        .min_by_key(|&(at, ..)| at)
+    {
+        out.push_str(&rest[..at]);
+        out.push_str(value);
+        rest = &rest[at + placeholder.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decorators back from a YAML spec, for the native renderers. A spec that
+/// doesn't parse renders undecorated rather than not at all.
+fn decorators_from_spec(spec: &str) -> spytial_annotations::SpytialDecorators {
+    if spec.trim().is_empty() {
+        return Default::default();
+    }
+    serde_yaml_ng::from_str(spec).unwrap_or_else(|err| {
+        eprintln!("caraspace: could not parse spec, rendering without decorators: {err}");
+        Default::default()
+    })
+}
+
+/// Support code for [`dbg!`] and `#[derive(SpytialDecorators)]`; not
+/// public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use linkme;
+
+    use crate::spytial_annotations::HasSpytialDecorators;
+    use serde::Serialize;
+    use std::fmt::Debug;
+
+    /// Picks the richest diagram path for the value at the macro call site,
+    /// using the same inherent-beats-trait resolution as
+    /// [`DecoProbe`](crate::spytial_annotations::DecoProbe): the inherent
+    /// `diagram` exists only for `Serialize + SpytialDecorators` types, and
+    /// [`DebugFallback`] covers everything else that is `Debug`.
+    pub struct DiagramProbe<'a, T: ?Sized>(pub &'a T);
+
+    impl<T: HasSpytialDecorators + Serialize> DiagramProbe<'_, T> {
+        /// Serde path: [`crate::diagram`].
+        pub fn diagram(self) {
+            crate::diagram(self.0)
+        }
+    }
+
+    /// Fallback for types without `Serialize` + `SpytialDecorators`.
+    pub trait DebugFallback {
+        /// Debug path: [`crate::diagram_debug`].
+        fn diagram(self);
+    }
+
+    impl<T: Debug + ?Sized> DebugFallback for DiagramProbe<'_, T> {
+        fn diagram(self) {
+            crate::diagram_debug(self.0)
+        }
+    }
 }
 
 /// Strict superset of [`std::dbg!`]: prints the `Debug` representation to
@@ -137,8 +374,10 @@ pub fn diagram_with_spec<T: Serialize>(value: &T, spec: &str) {
 /// - `dbg!(a, b, …)` — returns a tuple `(a, b, …)`. Each argument is
 ///   diagrammed (opens one tab per argument).
 ///
-/// The expression's type must derive [`std::fmt::Debug`],
-/// [`serde::Serialize`], and [`SpytialDecorators`]. Both owned
+/// The expression's type only needs [`std::fmt::Debug`], like `std::dbg!`.
+/// Types that also derive [`serde::Serialize`] and [`SpytialDecorators`] are
+/// exported through serde with their decorators applied; anything else falls
+/// back to [`diagram_debug`], which parses the `{:#?}` text. Both owned
 /// (`dbg!(x)`) and borrowed (`dbg!(&x)`) forms work.
 ///
 /// # Examples
@@ -168,7 +407,9 @@ pub fn diagram_with_spec<T: Serialize>(value: &T, spec: &str) {
 ///
 /// To suppress browser launch (CI, tests, headless runs), set
 /// `SPYTIAL_NO_OPEN=1`. Stderr output is unaffected, so `cargo test`
-/// captures still behave exactly like they would for `std::dbg!`.
+/// captures still behave exactly like they would for `std::dbg!`. Over SSH,
+/// set `SPYTIAL_MODE=terminal` to get the diagram drawn on stderr as text
+/// (see [`terminal`]) instead of an HTML file.
 #[macro_export]
 macro_rules! dbg {
     () => {
@@ -190,7 +431,11 @@ macro_rules! dbg {
                     ::std::stringify!($val),
                     &tmp,
                 );
-                $crate::diagram(&tmp);
+                {
+                    #[allow(unused_imports)]
+                    use $crate::__private::DebugFallback as _;
+                    $crate::__private::DiagramProbe(&tmp).diagram();
+                }
                 tmp
             }
         }
@@ -205,53 +450,208 @@ macro_rules! dbg {
 /// Best-effort: any failure (serialization, JSON encoding, tempfile write, browser
 /// launch) is reported via `eprintln!` and execution continues. `dbg!(x)` always
 /// returns `x` regardless of whether the diagram step succeeded.
-fn diagram_impl<T: Serialize>(value: &T, spec: &str) {
-    let json_instance = export_json_instance(value);
-    let json_data = match serde_json::to_string_pretty(&json_instance) {
-        Ok(json) => json,
-        Err(err) => {
-            eprintln!("caraspace: could not encode diagram JSON, skipping: {err}");
-            return;
+fn diagram_impl<T: Serialize>(value: &T, spec: &str, options: &DiagramOptions) {
+    diagram_instance(&export_json_instance(value), spec, options);
+}
+
+/// Best-effort [`render_instance`], shared by the serde and [`Debug`]
+/// frontends: a failure is reported on stderr.
+fn diagram_instance(
+    json_instance: &jsondata::JsonDataInstance,
+    spec: &str,
+    options: &DiagramOptions,
+) {
+    if let Err(err) = render_instance(json_instance, spec, options) {
+        eprintln!("caraspace: {err}");
+    }
+}
+
+/// Render an already-exported instance as `options` say and deliver it. The
+/// HTML page is built only for the formats that show it.
+pub(crate) fn render_instance(
+    json_instance: &jsondata::JsonDataInstance,
+    spec: &str,
+    options: &DiagramOptions,
+) -> Result<RenderedDiagram, DiagramError> {
+    let instance = options
+        .prepare(json_instance)
+        .unwrap_or_else(|| json_instance.clone());
+    let spec = options.merge_spec(spec);
+    let (html, path) = match options.format {
+        Format::Html | Format::Serve => {
+            let html = render_html(&instance, &spec, options).map_err(|err| {
+                DiagramError::Encoding(format!("could not encode diagram JSON: {err}"))
+            })?;
+            let path = present_page(&instance, &spec, &html, options)?;
+            (Some(html), path)
+        }
+        Format::Svg | Format::Terminal | Format::Ascii => {
+            (None, present_drawing(&instance, &spec, options)?)
         }
     };
+    Ok(RenderedDiagram {
+        instance,
+        spec,
+        html,
+        path,
+    })
+}
 
-    let template = include_str!("../templates/template.html");
-    let rendered_html = template
-        .replace(
-            "/*__SPYTIAL_CORE_CSS__*/",
-            include_str!("../templates/vendor/spytial-core.css"),
-        )
-        .replace(
-            "/*__REACT_COMPONENTS_CSS__*/",
-            include_str!("../templates/vendor/react-component-integration.css"),
-        )
-        .replace(
-            "/*__SPYTIAL_CORE_JS__*/",
-            include_str!("../templates/vendor/spytial-core.global.js"),
-        )
-        .replace(
-            "/*__REACT_COMPONENTS_JS__*/",
-            include_str!("../templates/vendor/react-component-integration.global.js"),
-        )
-        .replace("{{ json_data }}", &json_data)
-        .replace("{{ spytial_spec }}", spec);
-
-    let temp_file_path = diagram_output_path();
-    if let Err(err) = fs::write(&temp_file_path, rendered_html) {
-        eprintln!(
-            "caraspace: could not write diagram to {}: {err}",
-            temp_file_path.display()
-        );
-        return;
+/// Draw a diagram as terminal text or an SVG image, as the options' format
+/// says, and [`deliver`] it, returning the file it was written to, if any.
+fn present_drawing(
+    json_instance: &jsondata::JsonDataInstance,
+    spec: &str,
+    options: &DiagramOptions,
+) -> Result<Option<PathBuf>, DiagramError> {
+    let charset = match options.format {
+        Format::Terminal => Some(terminal::Charset::Unicode),
+        Format::Ascii => Some(terminal::Charset::Ascii),
+        _ => None,
+    };
+    if let Some(charset) = charset {
+        let mut text = terminal::to_text_with(json_instance, &decorators_from_spec(spec), charset);
+        if let Some(title) = &options.title {
+            text.insert_str(0, &format!("{title}\n"));
+        }
+        return deliver(&options.artifact(&text), options);
+    }
+
+    let image = svg::to_svg(json_instance, &decorators_from_spec(spec));
+    let image = match &options.title {
+        Some(title) => with_svg_title(&image, title),
+        None => image,
+    };
+    deliver(&options.artifact(&image), options)
+}
+
+/// [`deliver`] a diagram's HTML page, returning the file it was written to,
+/// if any.
+///
+/// [`OutputFormat::Serve`], and any HTML diagram while a [`live`] server is
+/// running and no sink is set, goes to the live page instead.
+fn present_page(
+    json_instance: &jsondata::JsonDataInstance,
+    spec: &str,
+    html: &str,
+    options: &DiagramOptions,
+) -> Result<Option<PathBuf>, DiagramError> {
+    if options.format == Format::Serve && live::address().is_none() {
+        let port = options.port;
+        match live::serve(("127.0.0.1", port)) {
+            Ok(addr) => {
+                live::publish(json_instance, spec);
+                let url = format!("http://{addr}/");
+                eprintln!("caraspace: live diagram at {url}");
+                if options.open {
+                    open_in_browser(&url, "page", options)
+                        .map_err(|message| DiagramError::Launch { message, path: None })?;
+                }
+                return Ok(None);
+            }
+            Err(err) => eprintln!(
+                "caraspace: could not start live server on port {port} ({err}), writing a file instead"
+            ),
+        }
+    }
+    if (options.sink.is_none() || options.format == Format::Serve)
+        && live::publish(json_instance, spec)
+    {
+        return Ok(None);
+    }
+
+    deliver(
+        &Artifact {
+            format: Format::Html,
+            content: html,
+            title: options.title.as_deref(),
+        },
+        options,
+    )
+}
+
+/// Hand `artifact` to the options' [`sink`](DiagramOptions::sink), or else
+/// print text to stderr and write documents to a file that is then opened.
+/// Returns the written file, if any.
+pub(crate) fn deliver(
+    artifact: &Artifact<'_>,
+    options: &DiagramOptions,
+) -> Result<Option<PathBuf>, DiagramError> {
+    if let Some(sink) = &options.sink {
+        sink.0
+            .deliver(artifact)
+            .map_err(|err| io::Error::new(err.kind(), format!("diagram sink failed: {err}")))?;
+        return Ok(None);
+    }
+    match artifact.format {
+        Format::Terminal | Format::Ascii => {
+            eprint!("{}", artifact.content);
+            Ok(None)
+        }
+        _ => write_and_open(artifact.content, artifact.extension(), options).map(Some),
+    }
+}
+
+/// `image` with a `<title>` as the first child of its `<svg>` element.
+fn with_svg_title(image: &str, title: &str) -> String {
+    match image
+        .find("<svg")
+        .and_then(|at| image[at..].find('>').map(|end| at + end + 1))
+    {
+        Some(at) => format!(
+            "{}<title>{}</title>{}",
+            &image[..at],
+            svg::escape(title),
+            &image[at..]
+        ),
+        None => image.to_string(),
     }
+}
 
-    let skip_browser_open = env::var("SPYTIAL_NO_OPEN")
-        .map(|raw| matches!(raw.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
-        .unwrap_or(false);
+/// Write a rendered document to the path `options` pick for extension
+/// `ext`, open it, and return the path. A browser that fails to start is a
+/// [`DiagramError::Launch`], with the file left in place.
+pub(crate) fn write_and_open(
+    rendered: &str,
+    ext: &str,
+    options: &DiagramOptions,
+) -> Result<PathBuf, DiagramError> {
+    let path = options.path_for(ext);
+    fs::write(&path, rendered).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("could not write diagram to {}: {err}", path.display()),
+        )
+    })?;
+    if options.open {
+        if let Err(message) = open_in_browser(&path.display().to_string(), "file", options) {
+            return Err(DiagramError::Launch {
+                message,
+                path: Some(path),
+            });
+        }
+    } else {
+        eprintln!("caraspace: diagram written to {}", path.display());
+    }
+    Ok(path)
+}
 
-    if skip_browser_open {
-        eprintln!("caraspace: diagram written to {}", temp_file_path.display());
-        return;
+/// Open `target` (a file path or URL) with the
+/// [`browser`](DiagramOptions::browser) command, or else the platform's
+/// default handler. `what` names it in the error message.
+fn open_in_browser(target: &str, what: &str, options: &DiagramOptions) -> Result<(), String> {
+    let launch = |problem: String| Err(format!("{problem}. Open this {what} manually: {target}"));
+    if let Some(template) = &options.browser {
+        let words = browser_command(template, target);
+        let Some((program, args)) = words.split_first() else {
+            return launch("the browser command is empty".into());
+        };
+        return match Command::new(program).args(args).spawn() {
+            Ok(_) => Ok(()),
+            Err(err) => launch(format!(
+                "failed to run browser command `{template}` ({err})"
+            )),
+        };
     }
 
     #[cfg(target_os = "macos")]
@@ -278,17 +678,41 @@ fn diagram_impl<T: Serialize>(value: &T, spec: &str) {
     let open_cmd: Option<&str> = None;
 
     let Some(open_cmd) = open_cmd else {
-        eprintln!(
-            "caraspace: no known browser-open command for this platform. Open this file manually: {}",
-            temp_file_path.display()
-        );
-        return;
+        return launch("no known browser-open command for this platform".into());
     };
 
-    if let Err(err) = Command::new(open_cmd).arg(&temp_file_path).spawn() {
-        eprintln!(
-            "caraspace: failed to open browser ({err}). Open this file manually: {}",
-            temp_file_path.display()
-        );
+    match Command::new(open_cmd).arg(target).spawn() {
+        Ok(_) => Ok(()),
+        Err(err) => launch(format!("failed to open browser ({err})")),
+    }
+}
+
+/// Split a [`DiagramOptions::browser`] template into words, honouring `'`
+/// and `"` quotes, with `{}` replaced by `target` (appended if absent).
+fn browser_command(template: &str, target: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    if words.iter().any(|w| w.contains("{}")) {
+        for w in &mut words {
+            *w = w.replace("{}", target);
+        }
+    } else if !words.is_empty() {
+        words.push(target.to_string());
     }
+    words
 }
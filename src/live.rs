@@ -0,0 +1,210 @@
+//! Live diagrams: one browser tab that redraws on every `diagram()` call.
+//!
+//! Opening a new page per call floods the browser as soon as `dbg!` sits in
+//! a loop. With `SPYTIAL_MODE=serve`, the first diagram instead starts a
+//! small HTTP server inside the running program (on `127.0.0.1`, port
+//! `SPYTIAL_PORT`, default 8080) and opens it once; every later
+//! [`crate::dbg!`] / [`crate::diagram`] call is pushed to the open tab over
+//! Server-Sent Events and redrawn in place.
+//!
+//! [`serve`] starts the same server from code, e.g. on another address or on
+//! port 0 to let the OS pick one. Once a server is running, every diagram
+//! goes to it regardless of `SPYTIAL_MODE`.
+//!
+//! The server lives on a background thread and stops when the program
+//! exits; the tab keeps showing the last diagram it received.
+//!
+//! | Path      | Serves                                              |
+//! |-----------|-----------------------------------------------------|
+//! | `/`       | The diagram page, showing the latest value          |
+//! | `/events` | `text/event-stream` of `{"version", "data", "spec"}` |
+//! | `/health` | `ok`                                                |
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use crate::jsondata::JsonDataInstance;
+
+/// Shared state between `diagram()` callers and the server thread.
+struct Hub {
+    addr: SocketAddr,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Number of diagrams published so far; also the SSE event id.
+    version: u64,
+    /// Compact JSON of the latest instance and spec.
+    latest: Option<(String, String)>,
+    /// Open `/events` connections.
+    clients: Vec<TcpStream>,
+}
+
+static HUB: OnceLock<Hub> = OnceLock::new();
+static START: Mutex<()> = Mutex::new(());
+
+/// How long a slow tab may hold up a `diagram()` call before it is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Start the live server on `addr`, or return the address it is already
+/// running on.
+///
+/// From then on every diagram in this process is pushed to the server
+/// instead of being written to a file. Nothing is opened in a browser; visit
+/// `http://{address}/` yourself.
+///
+/// ```no_run
+/// let addr = caraspace::live::serve("127.0.0.1:0").unwrap();
+/// println!("watch http://{addr}/");
+/// for i in 0..3 {
+///     caraspace::dbg!(vec![i; i]);
+/// }
+/// ```
+pub fn serve(addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+    let _guard = START.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(hub) = HUB.get() {
+        return Ok(hub.addr);
+    }
+    let listener = TcpListener::bind(addr)?;
+    let hub = HUB.get_or_init(|| Hub {
+        addr: listener
+            .local_addr()
+            .expect("bound listener has an address"),
+        state: Mutex::new(State::default()),
+    });
+    thread::Builder::new()
+        .name("caraspace-live".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => handle(hub, stream),
+                    Err(err) => eprintln!("caraspace: live server connection error: {err}"),
+                }
+            }
+        })?;
+    Ok(hub.addr)
+}
+
+/// Address of the running live server, if [`serve`] has been called.
+pub fn address() -> Option<SocketAddr> {
+    HUB.get().map(|hub| hub.addr)
+}
+
+/// Push a diagram to the live server. Returns `false` when no server is
+/// running, so the caller should render it some other way.
+pub(crate) fn publish(instance: &JsonDataInstance, spec: &str) -> bool {
+    let Some(hub) = HUB.get() else {
+        return false;
+    };
+    let (data, spec) = match (serde_json::to_string(instance), serde_json::to_string(spec)) {
+        (Ok(data), Ok(spec)) => (data, spec),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("caraspace: could not encode diagram JSON, skipping: {err}");
+            return true;
+        }
+    };
+
+    let mut state = hub.state.lock().unwrap_or_else(PoisonError::into_inner);
+    state.version += 1;
+    let event = event(state.version, &data, &spec);
+    state.latest = Some((data, spec));
+    state
+        .clients
+        .retain_mut(|client| client.write_all(event.as_bytes()).is_ok());
+    true
+}
+
+/// One SSE message carrying a whole diagram. Compact JSON has no raw
+/// newlines, so it fits on a single `data:` line.
+fn event(version: u64, data: &str, spec: &str) -> String {
+    format!("id: {version}\ndata: {{\"version\":{version},\"data\":{data},\"spec\":{spec}}}\n\n")
+}
+
+fn handle(hub: &Hub, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(30)));
+    // Read the whole request head: closing with unread bytes resets the
+    // connection before the client sees the response.
+    let mut buffer = [0_u8; 4096];
+    let mut read = 0;
+    while !buffer[..read].windows(4).any(|w| w == b"\r\n\r\n") && read < buffer.len() {
+        match stream.read(&mut buffer[read..]) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => read += n,
+        }
+    }
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut parts = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let raw_path = parts.next().unwrap_or("/");
+    let path = raw_path.split('?').next().unwrap_or(raw_path);
+
+    if method != "GET" {
+        respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed",
+        );
+        return;
+    }
+    match path {
+        "/" | "/index.html" => {
+            let state = hub.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let (data, spec) = state
+                .latest
+                .clone()
+                .unwrap_or_else(|| (r#"{"atoms":[],"relations":[]}"#.into(), r#""""#.into()));
+            let live = format!(r#"{{"events":"/events","version":{}}}"#, state.version);
+            drop(state);
+            let page = crate::html_page(
+                &data,
+                &spec,
+                &live,
+                "null",
+                crate::DEFAULT_TITLE,
+                Default::default(),
+            );
+            respond(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                page.as_bytes(),
+            );
+        }
+        "/events" => {
+            let headers = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+            let mut state = hub.state.lock().unwrap_or_else(PoisonError::into_inner);
+            // Replay the latest diagram so a tab that reconnects catches up;
+            // the page skips versions it already shows.
+            let mut hello = headers.to_string();
+            if let Some((data, spec)) = &state.latest {
+                hello.push_str(&event(state.version, data, spec));
+            }
+            let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+            if stream.write_all(hello.as_bytes()).is_ok() {
+                state.clients.push(stream);
+            }
+        }
+        "/health" => respond(&mut stream, "200 OK", "text/plain", b"ok"),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"Not Found"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    let headers = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream
+        .write_all(headers.as_bytes())
+        .and_then(|()| stream.write_all(body));
+}
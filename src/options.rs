@@ -0,0 +1,468 @@
+//! Settings for one diagram call, or for every call in the process.
+//!
+//! [`crate::diagram`] and [`crate::dbg!`] take their settings from
+//! [`default_options`](crate::options::default_options): whatever was
+//! passed to [`set_default_options`], or, until then, the `SPYTIAL_*`
+//! environment variables ([`DiagramOptions::from_env`]).
+//! [`crate::diagram_with`] takes them per call instead, so a test can pick
+//! its own output file without touching the environment or other threads.
+//!
+//! ```no_run
+//! use caraspace::options::{DiagramOptions, OutputFormat};
+//! use caraspace::{diagram_with, SpytialDecorators};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let options = DiagramOptions::new()
+//!     .output_dir("target/diagrams")
+//!     .file_name("point-{n}.{ext}")
+//!     .format(OutputFormat::Svg)
+//!     .title("A point")
+//!     .open(false);
+//! diagram_with(&Point { x: 1, y: 2 }, &options);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::jsondata::{IAtom, JsonDataInstance};
+use crate::sink::{Artifact, DiagramSink, SharedSink};
+use crate::spytial_annotations::{self, SpytialDecoratorsBuilder};
+
+static DIAGRAM_COUNTER: AtomicU64 = AtomicU64::new(0);
+static DEFAULTS: RwLock<Option<DiagramOptions>> = RwLock::new(None);
+
+/// The file name pattern used unless [`DiagramOptions::file_name`] sets one.
+pub const DEFAULT_FILE_NAME: &str = "caraspace-{pid}-{n}-{nanos}.{ext}";
+
+/// Label that replaces a redacted value.
+pub const REDACTED: &str = "[redacted]";
+
+/// What a diagram call produces.
+///
+/// Spelled in lowercase (`"svg"`, `"terminal"`, ...) in `caraspace.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// An interactive HTML page, written to a file and opened.
+    #[default]
+    Html,
+    /// A static SVG image (see [`crate::svg`]), written to a file and opened.
+    Svg,
+    /// Box-drawing text on stderr (see [`crate::terminal`]); nothing is written.
+    Terminal,
+    /// Like [`OutputFormat::Terminal`], restricted to ASCII.
+    Ascii,
+    /// One browser tab that redraws on every call (see [`crate::live`]).
+    Serve,
+}
+
+/// Colour scheme of the HTML page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Dark drawing on a white page.
+    #[default]
+    Light,
+    /// Dark page, with the drawing's lightness inverted and hues kept.
+    Dark,
+}
+
+impl Theme {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+/// Settings for a diagram call: where the output goes, in what format, and
+/// what is added to or cut from the diagram on the way.
+///
+/// Built with chained setters from [`DiagramOptions::new`] (plain defaults)
+/// or [`DiagramOptions::from_env`] (defaults adjusted by the `SPYTIAL_*`
+/// variables).
+#[derive(Debug, Clone)]
+pub struct DiagramOptions {
+    pub(crate) output_path: Option<PathBuf>,
+    pub(crate) output_dir: Option<PathBuf>,
+    pub(crate) file_name: String,
+    pub(crate) open: bool,
+    pub(crate) format: OutputFormat,
+    pub(crate) title: Option<String>,
+    pub(crate) extra_specs: Vec<String>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) port: u16,
+    pub(crate) theme: Theme,
+    pub(crate) browser: Option<String>,
+    pub(crate) redact_fields: Vec<String>,
+    pub(crate) redact_types: Vec<String>,
+    pub(crate) sink: Option<SharedSink>,
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        Self {
+            output_path: None,
+            output_dir: None,
+            file_name: DEFAULT_FILE_NAME.to_string(),
+            open: true,
+            format: OutputFormat::Html,
+            title: None,
+            extra_specs: Vec::new(),
+            max_depth: None,
+            port: 8080,
+            theme: Theme::Light,
+            browser: None,
+            redact_fields: Vec::new(),
+            redact_types: Vec::new(),
+            sink: None,
+        }
+    }
+}
+
+impl DiagramOptions {
+    /// HTML in a fresh file under the OS temp dir, opened in the browser,
+    /// regardless of the environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// [`DiagramOptions::new`] adjusted by the project's `caraspace.toml`
+    /// (see [`crate::config`]), then by the environment variables the crate
+    /// has always read:
+    ///
+    /// | Variable              | Setting                                   |
+    /// |-----------------------|-------------------------------------------|
+    /// | `SPYTIAL_OUTPUT_PATH` | [`output_path`](Self::output_path)        |
+    /// | `SPYTIAL_NO_OPEN`     | `open(false)` when `1`, `true` or `yes`   |
+    /// | `SPYTIAL_MODE`        | [`format`](Self::format): `terminal`, `ascii`, `serve` |
+    /// | `SPYTIAL_PORT`        | [`port`](Self::port)                      |
+    /// | `SPYTIAL_BROWSER`     | [`browser`](Self::browser)                |
+    pub fn from_env() -> Self {
+        let mut options = match crate::config::project() {
+            Some(config) => config.apply(Self::new()),
+            None => Self::new(),
+        };
+        if let Ok(path) = env::var("SPYTIAL_OUTPUT_PATH") {
+            options.output_path = Some(PathBuf::from(path));
+        }
+        if let Ok(raw) = env::var("SPYTIAL_NO_OPEN") {
+            if matches!(raw.to_ascii_lowercase().as_str(), "1" | "true" | "yes") {
+                options.open = false;
+            }
+        }
+        if let Ok(raw) = env::var("SPYTIAL_MODE") {
+            match raw.to_ascii_lowercase().as_str() {
+                "terminal" => options.format = OutputFormat::Terminal,
+                "ascii" => options.format = OutputFormat::Ascii,
+                "serve" => options.format = OutputFormat::Serve,
+                _ => {}
+            }
+        }
+        if let Some(port) = env::var("SPYTIAL_PORT")
+            .ok()
+            .and_then(|raw| raw.parse().ok())
+        {
+            options.port = port;
+        }
+        if let Ok(command) = env::var("SPYTIAL_BROWSER") {
+            if !command.trim().is_empty() {
+                options.browser = Some(command);
+            }
+        }
+        options
+    }
+
+    /// Write to exactly this file, overriding
+    /// [`output_dir`](Self::output_dir) and [`file_name`](Self::file_name).
+    /// Every call overwrites it.
+    pub fn output_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output_path = Some(path.into());
+        self
+    }
+
+    /// Write into `dir` instead of the OS temp dir. The directory must exist.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(dir.into());
+        self
+    }
+
+    /// Name files after `pattern`, where `{pid}` is the process id, `{n}` a
+    /// per-process counter, `{nanos}` the sub-second clock and `{ext}` the
+    /// format's extension (`html` or `svg`). Defaults to
+    /// [`DEFAULT_FILE_NAME`].
+    pub fn file_name(mut self, pattern: impl Into<String>) -> Self {
+        self.file_name = pattern.into();
+        self
+    }
+
+    /// Whether to open the written file (or live page) in the browser.
+    /// When `false`, the path is printed to stderr instead.
+    pub fn open(mut self, open: bool) -> Self {
+        self.open = open;
+        self
+    }
+
+    /// What to produce; HTML by default.
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Title of the page (or SVG, or a heading line on the terminal).
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Merge a YAML spec into the one derived for the value. Calls add up;
+    /// constraints and directives already present are not repeated.
+    pub fn extra_spec(mut self, yaml: impl Into<String>) -> Self {
+        self.extra_specs.push(yaml.into());
+        self
+    }
+
+    /// Drop atoms more than `depth` relation hops from the root, and the
+    /// tuples that reach them. `0` keeps only the root.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Port for [`OutputFormat::Serve`] on `127.0.0.1`; 8080 by default.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Colour scheme of the HTML page; light by default.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Open files with `command` instead of the platform's default handler.
+    /// `{}` in it stands for the file path or URL, which is appended when
+    /// there is no `{}`; words may be quoted with `'` or `"`.
+    ///
+    /// ```
+    /// # use caraspace::DiagramOptions;
+    /// let options = DiagramOptions::new().browser("firefox --new-tab {}");
+    /// ```
+    pub fn browser(mut self, command: impl Into<String>) -> Self {
+        self.browser = Some(command.into());
+        self
+    }
+
+    /// Hand every rendered diagram to `sink` instead of writing a file and
+    /// opening it (or printing it to stderr). See [`crate::sink`].
+    pub fn sink(mut self, sink: impl DiagramSink + 'static) -> Self {
+        self.sink = Some(SharedSink(Arc::new(sink)));
+        self
+    }
+
+    /// Show [`REDACTED`] instead of the value of every field named `field`.
+    pub fn redact_field(mut self, field: impl Into<String>) -> Self {
+        self.redact_fields.push(field.into());
+        self
+    }
+
+    /// Show every atom of type `type_name` as [`REDACTED`], without its
+    /// fields.
+    pub fn redact_type(mut self, type_name: impl Into<String>) -> Self {
+        self.redact_types.push(type_name.into());
+        self
+    }
+
+    /// The file to write a diagram with extension `ext` to. Unless
+    /// [`output_path`](Self::output_path) pins one, each call gets a new
+    /// name, so concurrent calls do not trample each other's files.
+    pub(crate) fn path_for(&self, ext: &str) -> PathBuf {
+        if let Some(path) = &self.output_path {
+            return path.clone();
+        }
+        let counter = DIAGRAM_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let name = self
+            .file_name
+            .replace("{pid}", &process::id().to_string())
+            .replace("{n}", &counter.to_string())
+            .replace("{nanos}", &nanos.to_string())
+            .replace("{ext}", ext);
+        match &self.output_dir {
+            Some(dir) => dir.join(name),
+            None => env::temp_dir().join(name),
+        }
+    }
+
+    /// `spec` with the [`extra_spec`](Self::extra_spec) YAML merged in.
+    pub(crate) fn merge_spec(&self, spec: &str) -> String {
+        if self.extra_specs.is_empty() {
+            return spec.to_string();
+        }
+        let mut merged =
+            SpytialDecoratorsBuilder::new().extend_with(crate::decorators_from_spec(spec));
+        for extra in &self.extra_specs {
+            match serde_yaml_ng::from_str(extra) {
+                Ok(more) => merged = merged.extend_with(more),
+                Err(err) => eprintln!("caraspace: could not parse extra spec, skipping it: {err}"),
+            }
+        }
+        spytial_annotations::to_yaml(&merged.build()).unwrap_or_default()
+    }
+
+    /// `content` rendered in this options' format.
+    pub(crate) fn artifact<'a>(&'a self, content: &'a str) -> Artifact<'a> {
+        Artifact {
+            format: self.format,
+            content,
+            title: self.title.as_deref(),
+        }
+    }
+
+    /// `instance` redacted and cut to [`max_depth`](Self::max_depth), or
+    /// `None` when there is nothing to do.
+    pub(crate) fn prepare(&self, instance: &JsonDataInstance) -> Option<JsonDataInstance> {
+        if self.max_depth.is_none() && self.redact_fields.is_empty() && self.redact_types.is_empty()
+        {
+            return None;
+        }
+        let mut out = instance.clone();
+        if redact(&mut out, &self.redact_fields, &self.redact_types) || self.max_depth.is_some() {
+            prune(&mut out, self.max_depth);
+        }
+        Some(out)
+    }
+}
+
+/// Use `options` for every [`crate::diagram`], [`crate::dbg!`] and
+/// [`crate::Timeline::open`] call in the process from now on, instead of
+/// [`DiagramOptions::from_env`].
+pub fn set_default_options(options: DiagramOptions) {
+    *DEFAULTS.write().unwrap_or_else(PoisonError::into_inner) = Some(options);
+}
+
+/// Replace the process-wide options with `update` applied to
+/// [`default_options`], atomically.
+pub(crate) fn update_default_options(update: impl FnOnce(DiagramOptions) -> DiagramOptions) {
+    let mut defaults = DEFAULTS.write().unwrap_or_else(PoisonError::into_inner);
+    let current = defaults.take().unwrap_or_else(DiagramOptions::from_env);
+    *defaults = Some(update(current));
+}
+
+/// The options [`crate::diagram`] uses: the last ones passed to
+/// [`set_default_options`], or [`DiagramOptions::from_env`] if there were
+/// none.
+pub fn default_options() -> DiagramOptions {
+    DEFAULTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(DiagramOptions::from_env)
+}
+
+/// Replace the values of `fields` with fresh [`REDACTED`] atoms, and label
+/// atoms of `types` [`REDACTED`] and drop their fields. Returns whether
+/// anything matched.
+fn redact(instance: &mut JsonDataInstance, fields: &[String], types: &[String]) -> bool {
+    let hidden: HashSet<String> = instance
+        .atoms
+        .iter()
+        .filter(|a| types.contains(&a.r#type))
+        .map(|a| a.id.clone())
+        .collect();
+    let type_of: HashMap<String, String> = instance
+        .atoms
+        .iter()
+        .map(|a| (a.id.clone(), a.r#type.clone()))
+        .collect();
+    let mut changed = !hidden.is_empty();
+    for atom in &mut instance.atoms {
+        if hidden.contains(&atom.id) {
+            atom.label = REDACTED.to_string();
+        }
+    }
+
+    let mut fresh = Vec::new();
+    for relation in &mut instance.relations {
+        relation
+            .tuples
+            .retain(|t| t.atoms.first().map_or(true, |id| !hidden.contains(id)));
+        if !fields.contains(&relation.name) {
+            continue;
+        }
+        for tuple in &mut relation.tuples {
+            let Some(value) = tuple.atoms.last_mut() else {
+                continue;
+            };
+            let id = format!("redacted{}", fresh.len());
+            fresh.push(IAtom {
+                id: id.clone(),
+                r#type: type_of.get(value).cloned().unwrap_or_default(),
+                label: REDACTED.to_string(),
+            });
+            *value = id;
+            changed = true;
+        }
+    }
+    instance.atoms.extend(fresh);
+    changed
+}
+
+/// Keep the atoms reachable from the root (`atoms[0]`), within `max_depth`
+/// hops if given, walking each tuple from its first atom to the others, and
+/// the tuples among them.
+fn prune(instance: &mut JsonDataInstance, max_depth: Option<usize>) {
+    let Some(root) = instance.atoms.first() else {
+        return;
+    };
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for tuple in instance.relations.iter().flat_map(|r| &r.tuples) {
+        if let Some((from, rest)) = tuple.atoms.split_first() {
+            edges
+                .entry(from.as_str())
+                .or_default()
+                .extend(rest.iter().map(String::as_str));
+        }
+    }
+
+    let mut kept: HashSet<String> = HashSet::from([root.id.clone()]);
+    let mut frontier = vec![root.id.as_str()];
+    let mut level = 0;
+    while !frontier.is_empty() && max_depth.map_or(true, |max| level < max) {
+        level += 1;
+        let mut next = Vec::new();
+        for id in frontier {
+            for &to in edges.get(id).into_iter().flatten() {
+                if kept.insert(to.to_string()) {
+                    next.push(to);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    instance.atoms.retain(|a| kept.contains(&a.id));
+    for relation in &mut instance.relations {
+        relation
+            .tuples
+            .retain(|t| t.atoms.iter().all(|id| kept.contains(id)));
+    }
+}
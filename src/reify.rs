@@ -11,11 +11,14 @@
 //!
 //! Covers the full serde tree model: primitives, `Option`, sequences, tuples,
 //! tuple/newtype/unit structs, maps, structs, and all enum variant shapes. That
-//! is exactly what [`crate::export`] produces, which is acyclic by construction
-//! — arena/index "graphs" round-trip as plain data (a self-loop is the integer
-//! index `Some(0)`, not a pointer). True `Rc<RefCell>` pointer cycles are not
-//! representable in the exported form and are out of scope; sharing/aliasing is
-//! not preserved (export duplicates it), which is invisible to `==`/`{:?}`.
+//! is exactly what [`crate::export`] produces for unmarked data, which is
+//! acyclic by construction — arena/index "graphs" round-trip as plain data (a
+//! self-loop is the integer index `Some(0)`, not a pointer). `Rc<RefCell>`
+//! pointer cycles exported through [`crate::shared`] render fine but cannot be
+//! rebuilt: reify reports them as a [`ReifyError`] instead of recursing. Plain `Rc`/`Arc`
+//! sharing is duplicated by export (invisible to `==`/`{:?}`); pointers marked
+//! via [`crate::shared`] export as one atom per allocation, and reify hands
+//! every owner of that atom a clone of the same pointer.
 //!
 //! Nested `Option` round-trips faithfully: `export` unwraps `Some(x)` (so the
 //! common case stays a single clean atom) but inserts a `Some` wrapper atom when
@@ -70,7 +73,8 @@ pub fn from_datum<T: DeserializeOwned>(datum: &JsonDataInstance) -> Result<T, Re
 
 /// The root atom: the first atom (in serialization order) that no relation
 /// targets. Robust to the `Some`-wrapper case, where `export` emits the wrapper
-/// *after* its inner — so `atoms[0]` is not always the root.
+/// *after* its inner — so `atoms[0]` is not always the root. When a reference
+/// cycle runs through the root, every atom is targeted and `atoms[0]` is used.
 fn find_root(datum: &JsonDataInstance) -> Result<&str, ReifyError> {
     if datum.atoms.is_empty() {
         return Err(ReifyError::msg("empty data instance: no atoms"));
@@ -88,14 +92,12 @@ fn find_root(datum: &JsonDataInstance) -> Result<&str, ReifyError> {
             }
         }
     }
-    datum
+    Ok(datum
         .atoms
         .iter()
         .map(|a| a.id.as_str())
         .find(|id| !targeted.contains(id))
-        .ok_or_else(|| {
-            ReifyError::msg("no root atom: every atom is referenced (unexpected for export output)")
-        })
+        .unwrap_or(datum.atoms[0].id.as_str()))
 }
 
 /// Reconstruct a `T` starting from an explicit root atom id.
@@ -109,9 +111,11 @@ pub fn from_datum_root<T: DeserializeOwned>(
         .get_key_value(root_id)
         .ok_or_else(|| ReifyError::msg(format!("root atom not found: {root_id}")))?
         .0;
-    T::deserialize(NodeDeserializer {
-        index: &index,
-        atom_id: root_key,
+    crate::shared::with_reify_cache(|| {
+        T::deserialize(NodeDeserializer {
+            index: &index,
+            atom_id: root_key,
+        })
     })
 }
 
@@ -351,9 +355,15 @@ impl<'i, 'a, 'de> Deserializer<'de> for NodeDeserializer<'i, 'a> {
 
     fn deserialize_newtype_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, ReifyError> {
+        // Shared pointers export transparently (no wrapper atom), so the
+        // pointee lives at this same atom.
+        if name == crate::shared::SHARED_TOKEN {
+            crate::shared::set_pending_atom(self.atom_id);
+            return visitor.visit_newtype_struct(self);
+        }
         let inner = self.index.single_target(self.atom_id, "value")?;
         visitor.visit_newtype_struct(self.child(inner))
     }
@@ -0,0 +1,149 @@
+//! Rendered diagrams as values, for tools that embed them elsewhere.
+//!
+//! [`diagram`](crate::diagram) is best-effort: it writes and opens the
+//! diagram and reports any failure on stderr. [`render`](fn@crate::render)
+//! does the same work but returns what it made — the exported instance,
+//! the YAML spec, the HTML page when the format is HTML, and the file it
+//! was written to — and returns failures as a
+//! [`DiagramError`](crate::render::DiagramError).
+//!
+//! ```no_run
+//! use caraspace::{render_with, DiagramOptions, SpytialDecorators};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let rendered = render_with(&Point { x: 1, y: 2 }, &DiagramOptions::new().open(false))?;
+//! if let Some(html) = &rendered.html {
+//!     let report = format!("<section>{html}</section>");
+//! }
+//! println!("also written to {:?}", rendered.path);
+//! # Ok::<(), caraspace::DiagramError>(())
+//! ```
+//!
+//! With a [`sink`](crate::DiagramOptions::sink) set, nothing is written and
+//! [`RenderedDiagram::path`](crate::render::RenderedDiagram::path) is `None`.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::export::{try_export_json_instance, SerializationError};
+use crate::jsondata::JsonDataInstance;
+use crate::options::{self, DiagramOptions};
+use crate::spytial_annotations::{self, HasSpytialDecorators};
+
+/// Everything [`render`](fn@render) produced for one value.
+#[derive(Debug, Clone)]
+pub struct RenderedDiagram {
+    /// The exported instance, after the options' redaction and
+    /// [`max_depth`](DiagramOptions::max_depth).
+    pub instance: JsonDataInstance,
+    /// The YAML spec: the type's decorators plus any
+    /// [`extra_spec`](DiagramOptions::extra_spec).
+    pub spec: String,
+    /// The standalone HTML page, for [`OutputFormat::Html`] and
+    /// [`OutputFormat::Serve`]. `None` for the other formats, which never
+    /// build one.
+    ///
+    /// [`OutputFormat::Html`]: crate::OutputFormat::Html
+    /// [`OutputFormat::Serve`]: crate::OutputFormat::Serve
+    pub html: Option<String>,
+    /// The file the diagram was written to: the HTML page, or the SVG image
+    /// for [`OutputFormat::Svg`](crate::OutputFormat::Svg). `None` when it
+    /// went to a sink, the terminal or the live page.
+    pub path: Option<PathBuf>,
+}
+
+/// Renders `value` like [`crate::diagram`], returning the result instead of
+/// only reporting failures on stderr.
+pub fn render<T: HasSpytialDecorators + Serialize>(
+    value: &T,
+) -> Result<RenderedDiagram, DiagramError> {
+    render_with(value, &options::default_options())
+}
+
+/// Like [`render`](fn@render), with explicit [`DiagramOptions`] instead of the
+/// process-wide defaults.
+///
+/// Unlike [`crate::diagram_with`], a value whose `Serialize` impl fails is
+/// an error rather than an empty diagram.
+pub fn render_with<T: HasSpytialDecorators + Serialize>(
+    value: &T,
+    options: &DiagramOptions,
+) -> Result<RenderedDiagram, DiagramError> {
+    let spec = spytial_annotations::to_yaml(&T::decorators()).map_err(|err| {
+        DiagramError::Encoding(format!("could not encode the decorators as YAML: {err}"))
+    })?;
+    let instance = try_export_json_instance(value)?;
+    crate::render_instance(&instance, &spec, options)
+}
+
+/// Why [`render`](fn@render) failed.
+#[derive(Debug)]
+pub enum DiagramError {
+    /// The value's `Serialize` impl failed.
+    Serialization(SerializationError),
+    /// The instance or spec could not be encoded into the page.
+    Encoding(String),
+    /// The diagram could not be written, or the sink failed.
+    Io(io::Error),
+    /// The browser could not be started. The diagram was still written, to
+    /// `path`, unless it went to the live page.
+    Launch {
+        /// What went wrong, and where to open the diagram by hand.
+        message: String,
+        /// The file the diagram was written to.
+        path: Option<PathBuf>,
+    },
+}
+
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagramError::Serialization(err) => err.fmt(f),
+            DiagramError::Io(err) => err.fmt(f),
+            DiagramError::Encoding(message) | DiagramError::Launch { message, .. } => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiagramError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DiagramError::Serialization(err) => Some(err),
+            DiagramError::Io(err) => Some(err),
+            DiagramError::Encoding(_) | DiagramError::Launch { .. } => None,
+        }
+    }
+}
+
+impl From<SerializationError> for DiagramError {
+    fn from(err: SerializationError) -> Self {
+        DiagramError::Serialization(err)
+    }
+}
+
+impl From<io::Error> for DiagramError {
+    fn from(err: io::Error) -> Self {
+        DiagramError::Io(err)
+    }
+}
+
+/// For [`crate::sink::DiagramSink`]s that render through caraspace.
+impl From<DiagramError> for io::Error {
+    fn from(err: DiagramError) -> Self {
+        match err {
+            DiagramError::Io(err) => err,
+            other => io::Error::other(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,685 @@
+//! Renderer-neutral view of an instance with its decorators applied.
+//!
+//! The browser path hands atoms, relations and the YAML spec to spytial-core
+//! and lets it work out what to draw. The native renderers share this module
+//! instead: [`Scene::build`] resolves every decorator selector once and
+//! produces the nodes, edges, groups and layout hints they all consume.
+//!
+//! | Decorator | Effect on the scene |
+//! |-----------|---------------------|
+//! | `hideAtom` | atom and its edges dropped |
+//! | `hideField` | edges of that relation dropped |
+//! | `attribute` | edges folded into `field: label` lines on the source node; value atoms with no other edge dropped |
+//! | `atomColor` | node fill colour |
+//! | `edgeColor` | edge colour, line style, weight, label and visibility |
+//! | `group` | named clusters (field-based groups absorb their edges) |
+//! | `orientation`, `align` | pairwise layout hints |
+//! | `flag: hideDisconnected` | nodes left without edges dropped |
+//!
+//! Negated constraints (`hold: never`) are forbidding rules rather than
+//! layout hints, so the scene skips them; so does every directive with no
+//! visual meaning outside the browser (`size`, `icon`, `projection`, ...).
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::jsondata::JsonDataInstance;
+use crate::selector::{self, Selection};
+use crate::spytial_annotations::{Constraint, Directive, GroupParams, SpytialDecorators};
+
+/// A visible atom.
+#[derive(Debug, Clone)]
+pub(crate) struct Node {
+    pub id: String,
+    pub r#type: String,
+    pub label: String,
+    /// `field: label` lines promoted by `attribute` directives.
+    pub attributes: Vec<(String, String)>,
+    pub color: Option<String>,
+}
+
+/// A visible edge between two nodes (indices into [`Scene::nodes`]).
+#[derive(Debug, Clone)]
+pub(crate) struct Edge {
+    pub from: usize,
+    pub to: usize,
+    /// Relation the edge came from.
+    pub relation: String,
+    /// Text to show: the relation name, plus `[...]` for the middle columns
+    /// of an n-ary tuple (`idx[0]`, `map_entry["k"]`).
+    pub label: String,
+    pub color: Option<String>,
+    pub style: Option<String>,
+    pub weight: Option<f64>,
+    pub show_label: bool,
+}
+
+/// A named cluster of nodes.
+#[derive(Debug, Clone)]
+pub(crate) struct Group {
+    pub name: String,
+    pub members: Vec<usize>,
+}
+
+/// Which axis an `align` constraint shares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    /// Same row.
+    Horizontal,
+    /// Same column.
+    Vertical,
+}
+
+/// Everything a native renderer needs to draw one diagram.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scene {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub groups: Vec<Group>,
+    /// `(a, b, directions)`: `b` sits in each direction relative to `a`.
+    pub orientations: Vec<(usize, usize, Vec<String>)>,
+    pub aligns: Vec<(usize, usize, Axis)>,
+    /// The node for `atoms[0]`, when it is visible.
+    pub root: Option<usize>,
+}
+
+impl Scene {
+    /// Apply `decorators` to `instance`.
+    ///
+    /// Selectors that fail to evaluate match nothing, mirroring how the
+    /// browser path ignores a broken decorator rather than failing the diagram.
+    pub fn build(instance: &JsonDataInstance, decorators: &SpytialDecorators) -> Scene {
+        let mut cache: HashMap<String, Option<Selection>> = HashMap::new();
+        let mut select = |sel: &str| -> Option<Selection> {
+            cache
+                .entry(sel.to_string())
+                .or_insert_with(|| selector::evaluate(sel, instance).ok())
+                .clone()
+        };
+
+        // Atoms that stay out of the picture entirely.
+        let mut hidden: HashSet<String> = HashSet::new();
+        let mut hide_disconnected = false;
+        for directive in &decorators.directives {
+            match directive {
+                Directive::HideAtom(d) => {
+                    if let Some(sel) = select(&d.hide_atom.selector) {
+                        hidden.extend(sel.atoms().into_iter().map(str::to_string));
+                    }
+                }
+                Directive::Flag(f) if f.flag == "hideDisconnected" => hide_disconnected = true,
+                _ => {}
+            }
+        }
+
+        // Candidate edges, one per tuple, in a stable relation order.
+        let labels: HashMap<&str, &str> = instance
+            .atoms
+            .iter()
+            .map(|a| (a.id.as_str(), a.label.as_str()))
+            .collect();
+        let mut relations: Vec<_> = instance.relations.iter().collect();
+        relations.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut raw: Vec<RawEdge> = Vec::new();
+        let mut endpoints: HashSet<&str> = HashSet::new();
+        let mut middles: HashSet<&str> = HashSet::new();
+        for rel in relations {
+            for tuple in &rel.tuples {
+                let [first, .., last] = tuple.atoms.as_slice() else {
+                    continue;
+                };
+                let middle = &tuple.atoms[1..tuple.atoms.len() - 1];
+                endpoints.extend([first.as_str(), last.as_str()]);
+                middles.extend(middle.iter().map(String::as_str));
+                let label = if middle.is_empty() {
+                    rel.name.clone()
+                } else {
+                    let parts: Vec<&str> = middle
+                        .iter()
+                        .map(|m| labels.get(m.as_str()).copied().unwrap_or(m))
+                        .collect();
+                    format!("{}[{}]", rel.name, parts.join(", "))
+                };
+                raw.push(RawEdge {
+                    from: first.clone(),
+                    to: last.clone(),
+                    relation: rel.name.clone(),
+                    label,
+                    style: EdgeLook::default(),
+                });
+            }
+        }
+
+        // Field-level directives, in declaration order.
+        let mut attributes: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        let mut folded: HashSet<String> = HashSet::new();
+        for directive in &decorators.directives {
+            match directive {
+                Directive::HideField(d) => {
+                    let scope = d.hide_field.selector.as_deref().map(&mut select);
+                    raw.retain(|e| !(e.relation == d.hide_field.field && in_scope(&scope, e)));
+                }
+                Directive::Attribute(d) => {
+                    let scope = d.attribute.selector.as_deref().map(&mut select);
+                    raw.retain(|e| {
+                        if e.relation == d.attribute.field && in_scope(&scope, e) {
+                            let value = labels.get(e.to.as_str()).copied().unwrap_or(&e.to);
+                            attributes
+                                .entry(e.from.clone())
+                                .or_default()
+                                .push((e.label.clone(), value.to_string()));
+                            folded.insert(e.to.clone());
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+                Directive::EdgeStyle(d) => {
+                    let p = &d.edge_style;
+                    let scope = p.selector.as_deref().map(&mut select);
+                    for e in raw
+                        .iter_mut()
+                        .filter(|e| e.relation == p.field && in_scope(&scope, e))
+                    {
+                        e.style = EdgeLook {
+                            color: Some(p.value.clone()),
+                            style: p.style.clone().or(e.style.style.take()),
+                            weight: p.weight.or(e.style.weight),
+                            show_label: p.show_label.or(e.style.show_label),
+                            hidden: p.hidden.or(e.style.hidden),
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        raw.retain(|e| e.style.hidden != Some(true));
+
+        // Field-based groups replace their edges with cluster membership.
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for constraint in &decorators.constraints {
+            let Constraint::Group(g) = constraint else {
+                continue;
+            };
+            match &g.group {
+                GroupParams::SelectorBased {
+                    selector,
+                    name,
+                    negated: false,
+                } => {
+                    if let Some(sel) = select(selector) {
+                        let members = sel.atoms().into_iter().map(str::to_string).collect();
+                        groups.push((name.clone(), members));
+                    }
+                }
+                GroupParams::FieldBased {
+                    field,
+                    group_on,
+                    add_to_group,
+                    selector,
+                    negated: false,
+                } => {
+                    let scope = selector.as_deref().map(&mut select);
+                    let mut by_key: Vec<(String, Vec<String>)> = Vec::new();
+                    let rel = instance.relations.iter().find(|r| &r.name == field);
+                    for tuple in rel.into_iter().flat_map(|r| &r.tuples) {
+                        let (Some(key), Some(member)) = (
+                            tuple.atoms.get(*group_on as usize),
+                            tuple.atoms.get(*add_to_group as usize),
+                        ) else {
+                            continue;
+                        };
+                        if let Some(scope) = &scope {
+                            if !scope
+                                .as_ref()
+                                .is_some_and(|s| s.atoms().contains(key.as_str()))
+                            {
+                                continue;
+                            }
+                        }
+                        match by_key.iter_mut().find(|(k, _)| k == key) {
+                            Some((_, members)) => members.push(member.clone()),
+                            None => by_key.push((key.clone(), vec![member.clone()])),
+                        }
+                    }
+                    for (key, members) in by_key {
+                        raw.retain(|e| {
+                            !(&e.relation == field && e.from == key && members.contains(&e.to))
+                        });
+                        let key_label = labels.get(key.as_str()).copied().unwrap_or(&key);
+                        groups.push((format!("{key_label}.{field}"), members));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Visible nodes. Atoms that only ever appeared inside an n-ary label
+        // (map keys, say), or as the value of an `attribute` field, have been
+        // folded into their edge or node and need no box.
+        raw.retain(|e| !hidden.contains(&e.from) && !hidden.contains(&e.to));
+        let linked: HashSet<&str> = raw
+            .iter()
+            .flat_map(|e| [e.from.as_str(), e.to.as_str()])
+            .collect();
+        let root_id = instance.atoms.first().map(|a| a.id.as_str());
+        let mut keep: Vec<bool> = instance
+            .atoms
+            .iter()
+            .map(|a| {
+                let id = a.id.as_str();
+                let placed = if folded.contains(id) {
+                    linked.contains(id)
+                } else {
+                    endpoints.contains(id) || !middles.contains(id)
+                };
+                !hidden.contains(id) && (Some(id) == root_id || placed)
+            })
+            .collect();
+        if hide_disconnected {
+            for (k, atom) in keep.iter_mut().zip(&instance.atoms) {
+                *k &= linked.contains(atom.id.as_str()) || attributes.contains_key(&atom.id);
+            }
+        }
+
+        let mut scene = Scene::default();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for (atom, _) in instance.atoms.iter().zip(&keep).filter(|(_, k)| **k) {
+            index.insert(atom.id.as_str(), scene.nodes.len());
+            scene.nodes.push(Node {
+                id: atom.id.clone(),
+                r#type: atom.r#type.clone(),
+                label: atom.label.clone(),
+                attributes: attributes.remove(&atom.id).unwrap_or_default(),
+                color: None,
+            });
+        }
+        scene.root = root_id.and_then(|id| index.get(id).copied());
+
+        for e in raw {
+            let (Some(&from), Some(&to)) = (index.get(e.from.as_str()), index.get(e.to.as_str()))
+            else {
+                continue;
+            };
+            scene.edges.push(Edge {
+                from,
+                to,
+                relation: e.relation,
+                label: e.label,
+                color: e.style.color,
+                style: e.style.style,
+                weight: e.style.weight,
+                show_label: e.style.show_label.unwrap_or(true),
+            });
+        }
+
+        for directive in &decorators.directives {
+            if let Directive::AtomColor(d) = directive {
+                if let Some(sel) = select(&d.atom_color.selector) {
+                    for id in sel.atoms() {
+                        if let Some(&i) = index.get(id) {
+                            scene.nodes[i].color = Some(d.atom_color.value.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        for (name, members) in groups {
+            let members: BTreeSet<usize> = members
+                .iter()
+                .filter_map(|m| index.get(m.as_str()).copied())
+                .collect();
+            if !members.is_empty() {
+                scene.groups.push(Group {
+                    name,
+                    members: members.into_iter().collect(),
+                });
+            }
+        }
+
+        for constraint in &decorators.constraints {
+            match constraint {
+                Constraint::Orientation(o) if !o.orientation.negated => {
+                    let Some(sel) = select(&o.orientation.selector) else {
+                        continue;
+                    };
+                    for (a, b) in sel.pairs() {
+                        if let (Some(&a), Some(&b)) = (index.get(a), index.get(b)) {
+                            let dirs = o.orientation.directions.clone();
+                            scene.orientations.push((a, b, dirs));
+                        }
+                    }
+                }
+                Constraint::Align(al) if !al.align.negated => {
+                    let Some(sel) = select(&al.align.selector) else {
+                        continue;
+                    };
+                    let axis = if al.align.direction == "vertical" {
+                        Axis::Vertical
+                    } else {
+                        Axis::Horizontal
+                    };
+                    let pairs: Vec<(&str, &str)> = if sel.arity == 1 {
+                        // A set of atoms: align each with the next.
+                        let atoms: Vec<&str> = sel.atoms().into_iter().collect();
+                        atoms.windows(2).map(|w| (w[0], w[1])).collect()
+                    } else {
+                        sel.pairs()
+                    };
+                    for (a, b) in pairs {
+                        if let (Some(&a), Some(&b)) = (index.get(a), index.get(b)) {
+                            scene.aligns.push((a, b, axis));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        scene
+    }
+}
+
+impl Scene {
+    /// The nodes each group draws, for formats where a node sits in one
+    /// cluster: `(group index, members)`, the first group listing a node
+    /// keeping it, and groups left with no members skipped.
+    pub fn clusters(&self) -> Vec<(usize, Vec<usize>)> {
+        let mut claimed = vec![false; self.nodes.len()];
+        self.groups
+            .iter()
+            .enumerate()
+            .filter_map(|(n, group)| {
+                let members: Vec<usize> = group
+                    .members
+                    .iter()
+                    .copied()
+                    .filter(|&m| !std::mem::replace(&mut claimed[m], true))
+                    .collect();
+                (!members.is_empty()).then_some((n, members))
+            })
+            .collect()
+    }
+
+    /// Horizontal lean of `child` relative to `parent` from orientation
+    /// hints: 0 for left, 1 for none, 2 for right. Sorting siblings by it
+    /// puts `left` children first.
+    pub fn side(&self, parent: usize, child: usize) -> u8 {
+        let has = |dir: &str| {
+            self.orientations.iter().any(|(a, b, ds)| {
+                *a == parent
+                    && *b == child
+                    && ds.iter().any(|d| {
+                        d == dir
+                            || d.strip_prefix("directly")
+                                .is_some_and(|r| r.eq_ignore_ascii_case(dir))
+                    })
+            })
+        };
+        if has("left") {
+            0
+        } else if has("right") {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Place every node on a grid: `(column, row)` per node, where columns
+    /// are in slot units (halves occur when a parent centres over one child)
+    /// and row 0 is the top.
+    ///
+    /// Rows come from breadth-first depth along edges, adjusted by vertical
+    /// orientations and horizontal alignment; columns from a tidy-tree pass
+    /// over the breadth-first spanning forest, ordering `left`-oriented
+    /// children before `right`-oriented ones. A final sweep keeps nodes in
+    /// the same row at least one slot apart.
+    pub fn grid(&self) -> Vec<(f64, usize)> {
+        let n = self.nodes.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut has_parent = vec![false; n];
+        for e in &self.edges {
+            if e.from != e.to && !children[e.from].contains(&e.to) {
+                children[e.from].push(e.to);
+                has_parent[e.to] = true;
+            }
+        }
+
+        // Breadth-first spanning forest: the root first, then every other
+        // parentless node, then whatever is left (cycles).
+        let starts: Vec<usize> = self
+            .root
+            .into_iter()
+            .chain((0..n).filter(|&i| !has_parent[i]))
+            .chain(0..n)
+            .collect();
+        let mut depth: Vec<Option<usize>> = vec![None; n];
+        let mut tree: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut roots = Vec::new();
+        for start in starts {
+            if depth[start].is_some() {
+                continue;
+            }
+            roots.push(start);
+            depth[start] = Some(0);
+            let mut queue = std::collections::VecDeque::from([start]);
+            while let Some(at) = queue.pop_front() {
+                for &child in &children[at] {
+                    if depth[child].is_none() {
+                        depth[child] = Some(depth[at].unwrap_or(0) + 1);
+                        tree[at].push(child);
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+        let mut row: Vec<usize> = depth.into_iter().map(|d| d.unwrap_or(0)).collect();
+
+        // Vertical hints, relaxed until stable (bounded, since contradictory
+        // hints never settle).
+        for _ in 0..=n {
+            let mut changed = false;
+            for (a, b, ds) in &self.orientations {
+                for d in ds {
+                    let (upper, lower) = match d.as_str() {
+                        "below" | "directlyBelow" => (*a, *b),
+                        "above" | "directlyAbove" => (*b, *a),
+                        _ => continue,
+                    };
+                    if row[lower] <= row[upper] {
+                        row[lower] = row[upper] + 1;
+                        changed = true;
+                    }
+                }
+            }
+            for (a, b, axis) in &self.aligns {
+                if *axis == Axis::Horizontal && row[*a] != row[*b] {
+                    let r = row[*a].max(row[*b]);
+                    row[*a] = r;
+                    row[*b] = r;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        // Tidy columns over the spanning forest.
+        let side = |parent: usize, child: usize| self.side(parent, child);
+        for (parent, kids) in tree.iter_mut().enumerate() {
+            kids.sort_by_key(|&c| side(parent, c));
+        }
+        let mut col = vec![0.0_f64; n];
+        let mut next_slot = 0.0_f64;
+        for root in roots {
+            place(root, &tree, &side, &mut col, &mut next_slot);
+        }
+
+        // Horizontal hints outside the spanning forest, then vertical
+        // alignment, each nudging only the target.
+        for _ in 0..=n {
+            let mut changed = false;
+            for (a, b, ds) in &self.orientations {
+                for d in ds {
+                    let fix = match d.as_str() {
+                        "left" | "directlyLeft" if col[*b] >= col[*a] => col[*a] - 1.0,
+                        "right" | "directlyRight" if col[*b] <= col[*a] => col[*a] + 1.0,
+                        _ => continue,
+                    };
+                    col[*b] = fix;
+                    changed = true;
+                }
+            }
+            for (a, b, axis) in &self.aligns {
+                if *axis == Axis::Vertical && col[*a] != col[*b] {
+                    col[*b] = col[*a];
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        // Keep each row readable: at least one slot between neighbours.
+        let rows = row.iter().copied().max().unwrap_or(0) + 1;
+        for r in 0..rows {
+            let mut in_row: Vec<usize> = (0..n).filter(|&i| row[i] == r).collect();
+            in_row.sort_by(|&a, &b| col[a].total_cmp(&col[b]));
+            for w in 1..in_row.len() {
+                let min = col[in_row[w - 1]] + 1.0;
+                if col[in_row[w]] < min {
+                    col[in_row[w]] = min;
+                }
+            }
+        }
+
+        let min_col = col.iter().copied().fold(f64::INFINITY, f64::min);
+        col.iter().zip(row).map(|(c, r)| (c - min_col, r)).collect()
+    }
+}
+
+/// Lay out the subtree at `node`: leaves take the next free slot, parents
+/// centre over their children. A parent whose children all lean one way
+/// reserves an empty slot on the other side so the lean stays visible.
+fn place(
+    node: usize,
+    tree: &[Vec<usize>],
+    side: &dyn Fn(usize, usize) -> u8,
+    col: &mut [f64],
+    next_slot: &mut f64,
+) {
+    let kids = &tree[node];
+    if kids.is_empty() {
+        col[node] = *next_slot;
+        *next_slot += 1.0;
+        return;
+    }
+    let all_right = kids.iter().all(|&c| side(node, c) == 2);
+    let all_left = kids.iter().all(|&c| side(node, c) == 0);
+    let first = if all_right {
+        *next_slot += 1.0;
+        *next_slot - 1.0
+    } else {
+        *next_slot
+    };
+    for &child in kids {
+        place(child, tree, side, col, next_slot);
+    }
+    let first = if all_right { first } else { col[kids[0]] };
+    let last = if all_left {
+        *next_slot += 1.0;
+        *next_slot - 1.0
+    } else {
+        col[kids[kids.len() - 1]]
+    };
+    col[node] = (first + last) / 2.0;
+}
+
+#[derive(Debug, Clone, Default)]
+struct EdgeLook {
+    color: Option<String>,
+    style: Option<String>,
+    weight: Option<f64>,
+    show_label: Option<bool>,
+    hidden: Option<bool>,
+}
+
+#[derive(Debug)]
+struct RawEdge {
+    from: String,
+    to: String,
+    relation: String,
+    label: String,
+    style: EdgeLook,
+}
+
+/// Whether `edge` falls under an optional directive selector: a set of atoms
+/// scopes by source, a relation scopes by `(source, target)` pair. A selector
+/// that failed to evaluate scopes to nothing.
+fn in_scope(scope: &Option<Option<Selection>>, edge: &RawEdge) -> bool {
+    match scope {
+        None => true,
+        Some(None) => false,
+        Some(Some(sel)) if sel.arity == 1 => sel.atoms().contains(edge.from.as_str()),
+        Some(Some(sel)) => sel
+            .pairs()
+            .contains(&(edge.from.as_str(), edge.to.as_str())),
+    }
+}
+
+/// `color` if it is a plain `#rgb`/`#rrggbb` (with optional alpha) or a
+/// colour name, the forms every text format takes as is; `None` for
+/// anything else, such as `rgb(...)` or text that would break out of a
+/// style line.
+pub(crate) fn plain_color(color: &str) -> Option<&str> {
+    let color = color.trim();
+    let valid = match color.strip_prefix('#') {
+        Some(hex) => {
+            matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic()),
+    };
+    valid.then_some(color)
+}
+
+/// Whether text on a `fill` background reads better in white.
+pub(crate) fn is_dark(fill: &str) -> bool {
+    let rgb = match fill.trim().to_ascii_lowercase().as_str() {
+        "black" => (0, 0, 0),
+        "red" => (255, 0, 0),
+        "maroon" | "darkred" => (128, 0, 0),
+        "green" | "darkgreen" => (0, 100, 0),
+        "blue" => (0, 0, 255),
+        "navy" | "darkblue" => (0, 0, 128),
+        "purple" => (128, 0, 128),
+        "gray" | "grey" | "dimgray" | "dimgrey" => (105, 105, 105),
+        "brown" => (165, 42, 42),
+        "teal" => (0, 128, 128),
+        hex => match parse_hex(hex) {
+            Some(rgb) => rgb,
+            None => return false,
+        },
+    };
+    let (r, g, b) = (f64::from(rgb.0), f64::from(rgb.1), f64::from(rgb.2));
+    0.299 * r + 0.587 * g + 0.114 * b < 140.0
+}
+
+fn parse_hex(s: &str) -> Option<(u8, u8, u8)> {
+    let hex = s.strip_prefix('#')?;
+    let channel = |i: usize, len: usize| {
+        let v = u8::from_str_radix(hex.get(i * len..(i + 1) * len)?, 16).ok()?;
+        Some(if len == 1 { v * 17 } else { v })
+    };
+    let len = match hex.len() {
+        3 => 1,
+        6 => 2,
+        _ => return None,
+    };
+    Some((channel(0, len)?, channel(1, len)?, channel(2, len)?))
+}
@@ -0,0 +1,1135 @@
+//! Parse and evaluate spytial selector strings.
+//!
+//! Decorators name the atoms they apply to with selectors such as
+//! `{x, y : Node | x->y in left}`. spytial-core evaluates these in the browser;
+//! this module evaluates them directly against a [`JsonDataInstance`], so the
+//! native renderers, tests and tools get the same answers without one.
+//!
+//! The supported language is the relational core that caraspace's own
+//! decorators use:
+//!
+//! | Form | Meaning |
+//! |------|---------|
+//! | `Node`, `u32` | every atom whose `type` is that name |
+//! | `left`, `idx` | the relation of that name, with all its columns |
+//! | `univ`, `none`, `iden` | all atoms, the empty set, the identity relation |
+//! | `a + b`, `a & b`, `a - b` | union, intersection, difference |
+//! | `a.b`, `a -> b`, `~a`, `^a`, `*a` | join, product, transpose, closures |
+//! | `{x, y : T \| body}` | comprehension over `T` |
+//!
+//! Comprehension bodies combine `e in f`, `e = f`, `e != f`, `some e`, `no e`,
+//! `true` and `false` with `and`/`or`/`not` (or `&&`/`||`/`!`). `@:(e)` compares by label, so
+//! `@:(x.color) = Red` matches atoms whose `color` field is labelled `Red`.
+//!
+//! ```
+//! use caraspace::{export_json_instance, selector};
+//!
+//! let instance = export_json_instance(&vec![3u8, 1, 2]);
+//! let small = selector::evaluate("{x : u8 | @:(x) != 3}", &instance).unwrap();
+//! assert_eq!(small.len(), 2);
+//!
+//! let err = selector::evaluate("u8 +", &instance).unwrap_err();
+//! assert_eq!(err.position(), Some(4));
+//! ```
+//!
+//! [`check`] runs every selector in a decorator set and reports the ones
+//! that fail or match nothing.
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::jsondata::JsonDataInstance;
+use crate::spytial_annotations::{Constraint, Directive, GroupParams, SpytialDecorators};
+
+/// A parsed selector, ready to evaluate against any number of instances.
+#[derive(Debug)]
+pub struct Selector {
+    source: String,
+    expr: Expr,
+}
+
+impl Selector {
+    /// Parse `source`.
+    pub fn parse(source: &str) -> Result<Self, SelectorError> {
+        let fail = |f: Fail| f.into_error(source);
+        let tokens = tokenize(source).map_err(fail)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.len(),
+        };
+        let expr = parser.expr().map_err(fail)?;
+        if let Some((tok, at)) = parser.tokens.get(parser.pos) {
+            return Err(fail(Fail::at(format!("unexpected `{tok}`"), *at)));
+        }
+        Ok(Selector {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// The text this selector was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The tuples of `instance` this selector matches.
+    pub fn evaluate(&self, instance: &JsonDataInstance) -> Result<Selection, SelectorError> {
+        let universe = Universe::new(instance);
+        let rel = universe
+            .eval(&self.expr, &mut Vec::new())
+            .map_err(|f| f.into_error(&self.source))?;
+        Ok(Selection {
+            arity: rel.arity,
+            tuples: rel
+                .tuples
+                .iter()
+                .map(|t| t.iter().map(|&i| universe.ids[i].clone()).collect())
+                .collect(),
+        })
+    }
+}
+
+/// Result of evaluating a selector: a set of atom-id tuples of one arity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    /// Number of atoms in each tuple (1 for an atom set, 2 for pairs).
+    pub arity: usize,
+    /// Matching tuples, as atom ids, in a stable order.
+    pub tuples: Vec<Vec<String>>,
+}
+
+impl Selection {
+    /// Atom ids in the first column, without duplicates.
+    pub fn atoms(&self) -> BTreeSet<&str> {
+        self.tuples
+            .iter()
+            .filter_map(|t| t.first().map(String::as_str))
+            .collect()
+    }
+
+    /// `(first, last)` pairs of every tuple with at least two columns.
+    pub fn pairs(&self) -> Vec<(&str, &str)> {
+        self.tuples
+            .iter()
+            .filter(|t| t.len() >= 2)
+            .map(|t| (t[0].as_str(), t[t.len() - 1].as_str()))
+            .collect()
+    }
+
+    /// Number of matching tuples.
+    pub fn len(&self) -> usize {
+        self.tuples.len()
+    }
+
+    /// Whether the selector matched nothing.
+    pub fn is_empty(&self) -> bool {
+        self.tuples.is_empty()
+    }
+}
+
+/// Parse `selector` and evaluate it against `instance`.
+///
+/// A name that is neither a type nor a relation in `instance` matches
+/// nothing rather than failing, since it is most often a type that simply
+/// has no atoms in this particular value.
+pub fn evaluate(selector: &str, instance: &JsonDataInstance) -> Result<Selection, SelectorError> {
+    Selector::parse(selector)?.evaluate(instance)
+}
+
+/// Error returned when a selector fails to parse or evaluate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    selector: String,
+    message: String,
+    position: Option<usize>,
+}
+
+impl SelectorError {
+    /// Borrow the underlying message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The selector that failed.
+    pub fn selector(&self) -> &str {
+        &self.selector
+    }
+
+    /// Byte offset into [`SelectorError::selector`] where the problem was
+    /// found, when it can be pinned to one place.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(at) = self.position {
+            let column = self.selector[..at].chars().count() + 1;
+            write!(f, " at column {column}")?;
+        }
+        write!(f, " in selector `{}`", self.selector)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// A decorator selector that [`check`] flagged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectorIssue {
+    /// The selector does not parse or cannot be evaluated.
+    Invalid {
+        /// YAML key of the decorator using the selector (e.g. `"atomColor"`).
+        decorator: &'static str,
+        /// What went wrong.
+        error: SelectorError,
+    },
+    /// The selector matched nothing in the instance.
+    Empty {
+        /// YAML key of the decorator using the selector.
+        decorator: &'static str,
+        /// The selector text.
+        selector: String,
+    },
+}
+
+impl fmt::Display for SelectorIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { decorator, error } => write!(f, "{decorator}: {error}"),
+            Self::Empty {
+                decorator,
+                selector,
+            } => write!(f, "{decorator}: selector `{selector}` matches nothing"),
+        }
+    }
+}
+
+/// Evaluate every selector in `decorators` against `instance` and report
+/// the ones that are invalid or match nothing.
+///
+/// An empty selector is not always a mistake — `hide_atom(selector = "None")`
+/// on a value with no `None` in it is fine — so treat
+/// [`SelectorIssue::Empty`] as a hint. Each selector is reported once per
+/// decorator kind.
+pub fn check(decorators: &SpytialDecorators, instance: &JsonDataInstance) -> Vec<SelectorIssue> {
+    let mut seen = HashSet::new();
+    let mut issues = Vec::new();
+    for (decorator, selector) in selectors(decorators) {
+        if !seen.insert((decorator, selector)) {
+            continue;
+        }
+        match evaluate(selector, instance) {
+            Err(error) => issues.push(SelectorIssue::Invalid { decorator, error }),
+            Ok(sel) if sel.is_empty() => issues.push(SelectorIssue::Empty {
+                decorator,
+                selector: selector.to_string(),
+            }),
+            Ok(_) => {}
+        }
+    }
+    issues
+}
+
+/// Every selector in `decorators`, with the YAML key of its decorator.
+fn selectors(decorators: &SpytialDecorators) -> Vec<(&'static str, &str)> {
+    let mut out = Vec::new();
+    for constraint in &decorators.constraints {
+        match constraint {
+            Constraint::Orientation(c) => out.push(("orientation", &*c.orientation.selector)),
+            Constraint::Align(c) => out.push(("align", &*c.align.selector)),
+            Constraint::Cyclic(c) => out.push(("cyclic", &*c.cyclic.selector)),
+            Constraint::Group(c) => match &c.group {
+                GroupParams::SelectorBased { selector, .. } => out.push(("group", selector)),
+                GroupParams::FieldBased { selector, .. } => {
+                    out.extend(selector.as_deref().map(|s| ("group", s)))
+                }
+            },
+        }
+    }
+    for directive in &decorators.directives {
+        match directive {
+            Directive::AtomColor(d) => out.push(("atomColor", &*d.atom_color.selector)),
+            Directive::Size(d) => out.push(("size", &*d.size.selector)),
+            Directive::Icon(d) => out.push(("icon", &*d.icon.selector)),
+            Directive::EdgeStyle(d) => {
+                out.extend(d.edge_style.selector.as_deref().map(|s| ("edgeColor", s)))
+            }
+            Directive::Attribute(d) => {
+                out.extend(d.attribute.selector.as_deref().map(|s| ("attribute", s)))
+            }
+            Directive::HideField(d) => {
+                out.extend(d.hide_field.selector.as_deref().map(|s| ("hideField", s)))
+            }
+            Directive::HideAtom(d) => out.push(("hideAtom", &*d.hide_atom.selector)),
+            Directive::InferredEdge(d) => out.push(("inferredEdge", &*d.inferred_edge.selector)),
+            Directive::Tag(d) => {
+                out.push(("tag", &*d.tag.to_tag));
+                out.push(("tag", &*d.tag.value));
+            }
+            Directive::Projection(_) | Directive::Flag(_) => {}
+        }
+    }
+    out
+}
+
+/// Rename the type `from` to `to` wherever a selector in `decorators` names
+/// it, and in projection signatures. Used when a type is exported under a
+/// qualified name, so its own decorators keep matching its atoms.
+pub(crate) fn rename_type(decorators: &mut SpytialDecorators, from: &str, to: &str) {
+    let rename = |selector: &mut String| *selector = renamed(selector, from, to);
+    let rename_opt = |selector: &mut Option<String>| {
+        if let Some(selector) = selector {
+            *selector = renamed(selector, from, to);
+        }
+    };
+    for constraint in &mut decorators.constraints {
+        match constraint {
+            Constraint::Orientation(c) => rename(&mut c.orientation.selector),
+            Constraint::Align(c) => rename(&mut c.align.selector),
+            Constraint::Cyclic(c) => rename(&mut c.cyclic.selector),
+            Constraint::Group(c) => match &mut c.group {
+                GroupParams::SelectorBased { selector, .. } => rename(selector),
+                GroupParams::FieldBased { selector, .. } => rename_opt(selector),
+            },
+        }
+    }
+    for directive in &mut decorators.directives {
+        match directive {
+            Directive::AtomColor(d) => rename(&mut d.atom_color.selector),
+            Directive::Size(d) => rename(&mut d.size.selector),
+            Directive::Icon(d) => rename(&mut d.icon.selector),
+            Directive::EdgeStyle(d) => rename_opt(&mut d.edge_style.selector),
+            Directive::Attribute(d) => rename_opt(&mut d.attribute.selector),
+            Directive::HideField(d) => rename_opt(&mut d.hide_field.selector),
+            Directive::HideAtom(d) => rename(&mut d.hide_atom.selector),
+            Directive::InferredEdge(d) => rename(&mut d.inferred_edge.selector),
+            Directive::Tag(d) => {
+                rename(&mut d.tag.to_tag);
+                rename(&mut d.tag.value);
+            }
+            Directive::Projection(d) => {
+                if d.projection.sig == from {
+                    d.projection.sig = to.to_string();
+                }
+            }
+            Directive::Flag(_) => {}
+        }
+    }
+}
+
+/// `selector` with every name token equal to `from` replaced by `to`.
+/// Quoted strings and qualified names are left alone, as is a selector that
+/// does not tokenize.
+fn renamed(selector: &str, from: &str, to: &str) -> String {
+    let Ok(tokens) = tokenize(selector) else {
+        return selector.to_string();
+    };
+    let mut out = String::with_capacity(selector.len());
+    let mut copied = 0;
+    for (token, at) in tokens {
+        if matches!(&token, Token::Ident(name) if name == from) {
+            out.push_str(&selector[copied..at]);
+            out.push_str(to);
+            copied = at + from.len();
+        }
+    }
+    out.push_str(&selector[copied..]);
+    out
+}
+
+/// An error and the byte offset it points at, before it is tied to the
+/// selector text.
+#[derive(Debug)]
+struct Fail {
+    message: String,
+    at: usize,
+}
+
+impl Fail {
+    fn at(message: impl Into<String>, at: usize) -> Self {
+        Fail {
+            message: message.into(),
+            at,
+        }
+    }
+
+    fn into_error(self, selector: &str) -> SelectorError {
+        SelectorError {
+            selector: selector.to_string(),
+            message: self.message,
+            position: Some(self.at),
+        }
+    }
+}
+
+// ── Tokens ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Sym(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => f.write_str(s),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Sym(s) => f.write_str(s),
+        }
+    }
+}
+
+/// Longest symbols first so `->` wins over `-` and `!=` over `!`.
+const SYMBOLS: &[&str] = &[
+    "->", "!=", "&&", "||", "@", "{", "}", "(", ")", ",", ":", "|", "+", "&", "-", ".", "~", "^",
+    "*", "=", "!",
+];
+
+/// Tokens with the byte offset each starts at.
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, Fail> {
+    let mut out = Vec::new();
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        let at = src.len() - rest.len();
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| Fail::at("unterminated string", at))?;
+            out.push((Token::Str(rest[1..1 + end].to_string()), at));
+            rest = &rest[end + 2..];
+        } else if c.is_alphanumeric() || c == '_' {
+            // Identifiers may be module-qualified (`ast::Node`).
+            let mut len = 0;
+            loop {
+                len += rest[len..]
+                    .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                    .unwrap_or(rest.len() - len);
+                let after = &rest[len..];
+                if after.starts_with("::")
+                    && after[2..].starts_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+                {
+                    len += 2;
+                } else {
+                    break;
+                }
+            }
+            out.push((Token::Ident(rest[..len].to_string()), at));
+            rest = &rest[len..];
+        } else if let Some(sym) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            out.push((Token::Sym(sym), at));
+            rest = &rest[sym.len()..];
+        } else {
+            return Err(Fail::at(format!("unexpected character `{c}`"), at));
+        }
+    }
+    Ok(out)
+}
+
+// ── Syntax ──────────────────────────────────────────────────────────────
+
+/// Nodes that can fail during evaluation keep the byte offset of their
+/// operator (or of themselves) for error positions.
+#[derive(Debug)]
+enum Expr {
+    Name(String),
+    Str(String, usize),
+    Label(Box<Expr>, usize),
+    Binary(&'static str, Box<Expr>, Box<Expr>, usize),
+    Unary(&'static str, Box<Expr>, usize),
+    /// `{x, y : T, z : U | body}`: each variable indexes into `domains`,
+    /// which keep the offset they start at.
+    Comprehension {
+        vars: Vec<(String, usize)>,
+        domains: Vec<(Expr, usize)>,
+        body: Box<Formula>,
+    },
+}
+
+#[derive(Debug)]
+enum Formula {
+    In(Expr, Expr),
+    Eq(Expr, Expr, bool),
+    Some(Expr),
+    No(Expr),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Not(Box<Formula>),
+    Const(bool),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Length of the source, reported for errors at the end of input.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(tok, _)| tok)
+    }
+
+    /// Offset of the next token, or the end of the source.
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, at)| *at)
+    }
+
+    fn peek_sym(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(Token::Sym(s)) if *s == sym)
+    }
+
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s == word)
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        let hit = self.peek_sym(sym);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let hit = self.peek_word(word);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    /// `true` or `false` standing alone as a formula (`{x : T | true}`);
+    /// elsewhere they are ordinary words, such as `bool` labels.
+    fn constant(&mut self) -> Option<bool> {
+        let value = match self.peek() {
+            Some(Token::Ident(word)) if word == "true" => true,
+            Some(Token::Ident(word)) if word == "false" => false,
+            _ => return None,
+        };
+        let ends = match self.tokens.get(self.pos + 1).map(|(tok, _)| tok) {
+            None => true,
+            Some(Token::Sym(s)) => matches!(*s, ")" | "}" | "&&" | "||"),
+            Some(Token::Ident(w)) => w == "and" || w == "or",
+            Some(Token::Str(_)) => false,
+        };
+        if ends {
+            self.pos += 1;
+        }
+        ends.then_some(value)
+    }
+
+    /// An "expected ..., found ..." error at the next token.
+    fn unexpected(&self, expected: &str) -> Fail {
+        let found = match self.peek() {
+            Some(tok) => format!("`{tok}`"),
+            None => "end of selector".to_string(),
+        };
+        Fail::at(format!("expected {expected}, found {found}"), self.offset())
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> Result<(), Fail> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{sym}`")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, Fail> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    /// `+` and `-`, loosest.
+    fn expr(&mut self) -> Result<Expr, Fail> {
+        let mut lhs = self.intersection()?;
+        loop {
+            let at = self.offset();
+            let op = if self.eat_sym("+") {
+                "+"
+            } else if self.eat_sym("-") {
+                "-"
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.intersection()?), at);
+        }
+    }
+
+    fn intersection(&mut self) -> Result<Expr, Fail> {
+        let mut lhs = self.product()?;
+        let mut at = self.offset();
+        while self.eat_sym("&") {
+            lhs = Expr::Binary("&", Box::new(lhs), Box::new(self.product()?), at);
+            at = self.offset();
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, Fail> {
+        let mut lhs = self.join()?;
+        let mut at = self.offset();
+        while self.eat_sym("->") {
+            lhs = Expr::Binary("->", Box::new(lhs), Box::new(self.join()?), at);
+            at = self.offset();
+        }
+        Ok(lhs)
+    }
+
+    fn join(&mut self) -> Result<Expr, Fail> {
+        let mut lhs = self.unary()?;
+        let mut at = self.offset();
+        while self.eat_sym(".") {
+            lhs = Expr::Binary(".", Box::new(lhs), Box::new(self.unary()?), at);
+            at = self.offset();
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Fail> {
+        let at = self.offset();
+        for op in ["~", "^", "*"] {
+            if self.eat_sym(op) {
+                return Ok(Expr::Unary(op, Box::new(self.unary()?), at));
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, Fail> {
+        let at = self.offset();
+        if self.eat_sym("(") {
+            let inner = self.expr()?;
+            self.expect_sym(")")?;
+            return Ok(inner);
+        }
+        if self.eat_sym("@") {
+            self.expect_sym(":")?;
+            return Ok(Expr::Label(Box::new(self.unary()?), at));
+        }
+        if self.eat_sym("{") {
+            let (mut vars, mut domains) = (Vec::new(), Vec::new());
+            loop {
+                vars.push((self.ident()?, domains.len()));
+                while self.eat_sym(",") {
+                    vars.push((self.ident()?, domains.len()));
+                }
+                self.expect_sym(":")?;
+                let domain_at = self.offset();
+                domains.push((self.expr()?, domain_at));
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+            self.expect_sym("|")?;
+            let body = self.formula()?;
+            self.expect_sym("}")?;
+            return Ok(Expr::Comprehension {
+                vars,
+                domains,
+                body: Box::new(body),
+            });
+        }
+        match self.peek() {
+            Some(Token::Str(s)) => {
+                let s = s.clone();
+                self.pos += 1;
+                Ok(Expr::Str(s, at))
+            }
+            _ => Ok(Expr::Name(self.ident()?)),
+        }
+    }
+
+    fn formula(&mut self) -> Result<Formula, Fail> {
+        let mut lhs = self.conjunction()?;
+        while self.eat_word("or") || self.eat_sym("||") {
+            lhs = Formula::Or(Box::new(lhs), Box::new(self.conjunction()?));
+        }
+        Ok(lhs)
+    }
+
+    fn conjunction(&mut self) -> Result<Formula, Fail> {
+        let mut lhs = self.negation()?;
+        while self.eat_word("and") || self.eat_sym("&&") {
+            lhs = Formula::And(Box::new(lhs), Box::new(self.negation()?));
+        }
+        Ok(lhs)
+    }
+
+    fn negation(&mut self) -> Result<Formula, Fail> {
+        if self.eat_word("not") || self.eat_sym("!") {
+            return Ok(Formula::Not(Box::new(self.negation()?)));
+        }
+        if self.eat_word("some") {
+            return Ok(Formula::Some(self.expr()?));
+        }
+        if self.eat_word("no") {
+            return Ok(Formula::No(self.expr()?));
+        }
+        if let Some(value) = self.constant() {
+            return Ok(Formula::Const(value));
+        }
+        // `(` opens either a nested formula or an expression; try the
+        // formula first and rewind if it doesn't close cleanly.
+        if self.peek_sym("(") {
+            let start = self.pos;
+            self.pos += 1;
+            if let Ok(inner) = self.formula() {
+                if self.eat_sym(")") {
+                    return Ok(inner);
+                }
+            }
+            self.pos = start;
+        }
+        let lhs = self.expr()?;
+        let negated = self.eat_word("not") || self.eat_sym("!");
+        let formula = if self.eat_word("in") {
+            Formula::In(lhs, self.expr()?)
+        } else if !negated && self.eat_sym("=") {
+            Formula::Eq(lhs, self.expr()?, true)
+        } else if !negated && self.eat_sym("!=") {
+            Formula::Eq(lhs, self.expr()?, false)
+        } else {
+            return Err(self.unexpected("`in`, `=` or `!=`"));
+        };
+        Ok(if negated {
+            Formula::Not(Box::new(formula))
+        } else {
+            formula
+        })
+    }
+}
+
+// ── Evaluation ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct Relation {
+    arity: usize,
+    tuples: BTreeSet<Vec<usize>>,
+}
+
+impl Relation {
+    fn empty(arity: usize) -> Self {
+        Relation {
+            arity,
+            tuples: BTreeSet::new(),
+        }
+    }
+
+    fn unary(atoms: impl IntoIterator<Item = usize>) -> Self {
+        Relation {
+            arity: 1,
+            tuples: atoms.into_iter().map(|a| vec![a]).collect(),
+        }
+    }
+}
+
+/// Every id that appears in the instance, interned to an index. Tuple
+/// positions that are not atoms (such as `idx` positions) get ids too, so
+/// they can still be joined on.
+///
+/// Types, relations and the built-in names are resolved once here and
+/// borrowed by every evaluation, so a comprehension body costs no more per
+/// binding than the formula itself.
+struct Universe<'a> {
+    instance: &'a JsonDataInstance,
+    ids: Vec<String>,
+    relations: HashMap<&'a str, Relation>,
+    types: HashMap<&'a str, Relation>,
+    univ: Relation,
+    iden: Relation,
+}
+
+/// A relation borrowed from the [`Universe`] or computed for one expression.
+type Rel<'u> = Cow<'u, Relation>;
+
+impl<'a> Universe<'a> {
+    fn new(instance: &'a JsonDataInstance) -> Self {
+        let mut ids: Vec<String> = instance.atoms.iter().map(|a| a.id.clone()).collect();
+        let mut index: HashMap<String, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.clone(), i))
+            .collect();
+        let mut relations: HashMap<&str, Relation> = HashMap::new();
+        for rel in &instance.relations {
+            // Empty tuples have nothing to join on; `validate` reports them.
+            for tuple in rel.tuples.iter().filter(|t| !t.atoms.is_empty()) {
+                let row: Vec<usize> = tuple
+                    .atoms
+                    .iter()
+                    .map(|id| {
+                        *index.entry(id.clone()).or_insert_with(|| {
+                            ids.push(id.clone());
+                            ids.len() - 1
+                        })
+                    })
+                    .collect();
+                let entry = relations
+                    .entry(rel.name.as_str())
+                    .or_insert_with(|| Relation::empty(row.len()));
+                if entry.arity == row.len() {
+                    entry.tuples.insert(row);
+                }
+            }
+        }
+        let mut types: HashMap<&str, Relation> = HashMap::new();
+        for (i, atom) in instance.atoms.iter().enumerate() {
+            types
+                .entry(atom.r#type.as_str())
+                .or_insert_with(|| Relation::empty(1))
+                .tuples
+                .insert(vec![i]);
+        }
+        let atoms = 0..instance.atoms.len();
+        Universe {
+            instance,
+            ids,
+            relations,
+            types,
+            univ: Relation::unary(atoms.clone()),
+            iden: Relation {
+                arity: 2,
+                tuples: atoms.map(|i| vec![i, i]).collect(),
+            },
+        }
+    }
+
+    fn label(&self, i: usize) -> &str {
+        self.instance
+            .atoms
+            .get(i)
+            .map(|a| a.label.as_str())
+            .unwrap_or(&self.ids[i])
+    }
+
+    fn resolve(&self, name: &str, env: &[(String, usize)]) -> Option<Rel<'_>> {
+        if let Some((_, atom)) = env.iter().rev().find(|(var, _)| var == name) {
+            return Some(Cow::Owned(Relation::unary([*atom])));
+        }
+        match name {
+            "univ" => Some(Cow::Borrowed(&self.univ)),
+            "none" => Some(Cow::Owned(Relation::empty(1))),
+            "iden" => Some(Cow::Borrowed(&self.iden)),
+            _ => self
+                .types
+                .get(name)
+                .or_else(|| self.relations.get(name))
+                .map(Cow::Borrowed),
+        }
+    }
+
+    fn eval(&self, expr: &Expr, env: &mut Vec<(String, usize)>) -> Result<Rel<'_>, Fail> {
+        Ok(match expr {
+            // An unknown name is most often a type with no atoms in this
+            // particular value (`hide_atom(selector = "Color + None")`).
+            Expr::Name(name) => self
+                .resolve(name, env)
+                .unwrap_or(Cow::Owned(Relation::empty(1))),
+            Expr::Str(_, at) | Expr::Label(_, at) => {
+                return Err(Fail::at(
+                    "a label can only be compared with `=` or `!=`",
+                    *at,
+                ))
+            }
+            Expr::Unary(op, inner, at) => {
+                let rel = self.eval(inner, env)?;
+                Cow::Owned(match *op {
+                    "~" => Relation {
+                        arity: rel.arity,
+                        tuples: rel
+                            .tuples
+                            .iter()
+                            .map(|t| t.iter().rev().copied().collect())
+                            .collect(),
+                    },
+                    "^" => closure(rel.into_owned()).map_err(|m| Fail::at(m, *at))?,
+                    _ => {
+                        let mut closed = closure(rel.into_owned()).map_err(|m| Fail::at(m, *at))?;
+                        closed.tuples.extend(self.iden.tuples.iter().cloned());
+                        closed
+                    }
+                })
+            }
+            Expr::Binary(op, lhs, rhs, at) => {
+                let (l, r) = (self.eval(lhs, env)?, self.eval(rhs, env)?);
+                Cow::Owned(match *op {
+                    "+" | "&" | "-" => set_op(op, &l, &r).map_err(|m| Fail::at(m, *at))?,
+                    "->" => Relation {
+                        arity: l.arity + r.arity,
+                        tuples: l
+                            .tuples
+                            .iter()
+                            .flat_map(|a| r.tuples.iter().map(move |b| [&a[..], &b[..]].concat()))
+                            .collect(),
+                    },
+                    _ => join(&l, &r).map_err(|m| Fail::at(m, *at))?,
+                })
+            }
+            Expr::Comprehension {
+                vars,
+                domains,
+                body,
+            } => {
+                let evaluated = domains
+                    .iter()
+                    .map(|(d, _)| self.eval(d, env))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some((var, d)) = vars.iter().find(|(_, d)| evaluated[*d].arity != 1) {
+                    return Err(Fail::at(
+                        format!("`{var}` must range over a set of atoms"),
+                        domains[*d].1,
+                    ));
+                }
+                if let Some(out) = self.membership(vars, &evaluated, body, env)? {
+                    return Ok(Cow::Owned(out));
+                }
+                let mut out = Relation::empty(vars.len());
+                self.bind(vars, &evaluated, body, env, &mut out)?;
+                Cow::Owned(out)
+            }
+        })
+    }
+
+    /// Fast path for comprehensions whose body is `x->y in R`, with each
+    /// variable once on the left and none of them in `R`: evaluate `R` once
+    /// and keep the tuples whose columns lie in the variables' domains,
+    /// instead of testing every binding. `None` for any other body.
+    fn membership(
+        &self,
+        vars: &[(String, usize)],
+        domains: &[Rel<'_>],
+        body: &Formula,
+        env: &mut Vec<(String, usize)>,
+    ) -> Result<Option<Relation>, Fail> {
+        let Formula::In(lhs, rhs) = body else {
+            return Ok(None);
+        };
+        let mut columns = Vec::new();
+        if !product_of_names(lhs, &mut columns) || columns.len() != vars.len() {
+            return Ok(None);
+        }
+        // For each column of `R`, the comprehension variable it binds.
+        let Some(slots) = columns
+            .iter()
+            .map(|name| vars.iter().position(|(var, _)| var == name))
+            .collect::<Option<Vec<usize>>>()
+        else {
+            return Ok(None);
+        };
+        let distinct: HashSet<usize> = slots.iter().copied().collect();
+        if distinct.len() != vars.len() || mentions(rhs, vars) {
+            return Ok(None);
+        }
+
+        let rel = self.eval(rhs, env)?;
+        let mut out = Relation::empty(vars.len());
+        if rel.arity != vars.len() {
+            return Ok(Some(out));
+        }
+        for tuple in &rel.tuples {
+            let in_domains = tuple
+                .iter()
+                .zip(&slots)
+                .all(|(&atom, &slot)| domains[vars[slot].1].tuples.contains(&[atom][..]));
+            if in_domains {
+                let mut row = vec![0; vars.len()];
+                for (&atom, &slot) in tuple.iter().zip(&slots) {
+                    row[slot] = atom;
+                }
+                out.tuples.insert(row);
+            }
+        }
+        Ok(Some(out))
+    }
+
+    /// Enumerate every binding of the remaining `vars` and keep the rows
+    /// where `body` holds. Bound variables are the tail of `env`.
+    fn bind(
+        &self,
+        vars: &[(String, usize)],
+        domains: &[Rel<'_>],
+        body: &Formula,
+        env: &mut Vec<(String, usize)>,
+        out: &mut Relation,
+    ) -> Result<(), Fail> {
+        let Some(((var, domain), rest)) = vars.split_first() else {
+            if self.holds(body, env)? {
+                let row = env[env.len() - out.arity..].iter().map(|(_, a)| *a);
+                out.tuples.insert(row.collect());
+            }
+            return Ok(());
+        };
+        for t in &domains[*domain].tuples {
+            env.push((var.clone(), t[0]));
+            self.bind(rest, domains, body, env, out)?;
+            env.pop();
+        }
+        Ok(())
+    }
+
+    fn holds(&self, formula: &Formula, env: &mut Vec<(String, usize)>) -> Result<bool, Fail> {
+        Ok(match formula {
+            Formula::In(lhs, rhs) => {
+                let (l, r) = (self.eval(lhs, env)?, self.eval(rhs, env)?);
+                l.tuples.is_subset(&r.tuples)
+            }
+            Formula::Eq(lhs, rhs, positive) => {
+                let equal = if is_label(lhs) || is_label(rhs) {
+                    self.labels(lhs, env)? == self.labels(rhs, env)?
+                } else {
+                    self.eval(lhs, env)?.tuples == self.eval(rhs, env)?.tuples
+                };
+                equal == *positive
+            }
+            Formula::Some(e) => !self.eval(e, env)?.tuples.is_empty(),
+            Formula::No(e) => self.eval(e, env)?.tuples.is_empty(),
+            Formula::And(a, b) => self.holds(a, env)? && self.holds(b, env)?,
+            Formula::Or(a, b) => self.holds(a, env)? || self.holds(b, env)?,
+            Formula::Not(f) => !self.holds(f, env)?,
+            Formula::Const(value) => *value,
+        })
+    }
+
+    /// The label side of a comparison: a quoted string or bare word stands for
+    /// itself, anything else for the labels of the atoms it evaluates to.
+    fn labels(
+        &self,
+        expr: &Expr,
+        env: &mut Vec<(String, usize)>,
+    ) -> Result<BTreeSet<String>, Fail> {
+        let rel = match expr {
+            Expr::Str(s, _) => return Ok(BTreeSet::from([s.clone()])),
+            Expr::Name(n) if self.resolve(n, env).is_none() => {
+                return Ok(BTreeSet::from([n.clone()]))
+            }
+            Expr::Label(inner, _) => self.eval(inner, env)?,
+            other => self.eval(other, env)?,
+        };
+        Ok(rel
+            .tuples
+            .iter()
+            .filter_map(|t| t.last())
+            .map(|&i| self.label(i).to_string())
+            .collect())
+    }
+}
+
+/// Push the names of `a -> b -> c` onto `names`; false if `expr` is
+/// anything else.
+fn product_of_names<'e>(expr: &'e Expr, names: &mut Vec<&'e str>) -> bool {
+    match expr {
+        Expr::Name(name) => {
+            names.push(name);
+            true
+        }
+        Expr::Binary("->", lhs, rhs, _) => {
+            product_of_names(lhs, names) && product_of_names(rhs, names)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` uses any of `vars`, even where an inner comprehension
+/// shadows it.
+fn mentions(expr: &Expr, vars: &[(String, usize)]) -> bool {
+    match expr {
+        Expr::Name(name) => vars.iter().any(|(var, _)| var == name),
+        Expr::Str(..) => false,
+        Expr::Label(inner, _) | Expr::Unary(_, inner, _) => mentions(inner, vars),
+        Expr::Binary(_, lhs, rhs, _) => mentions(lhs, vars) || mentions(rhs, vars),
+        Expr::Comprehension { domains, body, .. } => {
+            domains.iter().any(|(d, _)| mentions(d, vars)) || formula_mentions(body, vars)
+        }
+    }
+}
+
+fn formula_mentions(formula: &Formula, vars: &[(String, usize)]) -> bool {
+    match formula {
+        Formula::In(a, b) | Formula::Eq(a, b, _) => mentions(a, vars) || mentions(b, vars),
+        Formula::Some(e) | Formula::No(e) => mentions(e, vars),
+        Formula::And(a, b) | Formula::Or(a, b) => {
+            formula_mentions(a, vars) || formula_mentions(b, vars)
+        }
+        Formula::Not(f) => formula_mentions(f, vars),
+        Formula::Const(_) => false,
+    }
+}
+
+fn is_label(expr: &Expr) -> bool {
+    matches!(expr, Expr::Label(..) | Expr::Str(..))
+}
+
+fn set_op(op: &str, l: &Relation, r: &Relation) -> Result<Relation, String> {
+    // An empty side adopts the other's arity, so `Missing + left` still works.
+    let arity = match (l.tuples.is_empty(), r.tuples.is_empty()) {
+        (true, _) => r.arity,
+        (_, true) => l.arity,
+        _ if l.arity == r.arity => l.arity,
+        _ => {
+            return Err(format!(
+                "cannot combine arity {} and arity {} with `{op}`",
+                l.arity, r.arity
+            ))
+        }
+    };
+    let tuples = match op {
+        "+" => l.tuples.union(&r.tuples).cloned().collect(),
+        "&" => l.tuples.intersection(&r.tuples).cloned().collect(),
+        _ => l.tuples.difference(&r.tuples).cloned().collect(),
+    };
+    Ok(Relation { arity, tuples })
+}
+
+fn join(l: &Relation, r: &Relation) -> Result<Relation, String> {
+    if l.arity == 0 || r.arity == 0 {
+        return Err("cannot join a relation of arity 0 with `.`".to_string());
+    }
+    if l.arity + r.arity < 3 {
+        return Err("cannot join two sets of atoms with `.`".to_string());
+    }
+    // Tuples are sorted, so those of `r` starting with one atom are a range.
+    let mut tuples = BTreeSet::new();
+    for a in &l.tuples {
+        let head = a[a.len() - 1];
+        for b in r.tuples.range(vec![head]..).take_while(|b| b[0] == head) {
+            tuples.insert([&a[..a.len() - 1], &b[1..]].concat());
+        }
+    }
+    Ok(Relation {
+        arity: l.arity + r.arity - 2,
+        tuples,
+    })
+}
+
+fn closure(rel: Relation) -> Result<Relation, String> {
+    if rel.arity != 2 && !rel.tuples.is_empty() {
+        return Err("closure needs a binary relation".to_string());
+    }
+    let mut closed = Relation {
+        arity: 2,
+        tuples: rel.tuples,
+    };
+    loop {
+        let step = join(&closed, &closed)?;
+        let before = closed.tuples.len();
+        closed.tuples.extend(step.tuples);
+        if closed.tuples.len() == before {
+            return Ok(closed);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Opt-in preservation of `Rc`/`Arc` sharing across [`crate::export`] and
+//! [`crate::reify`].
+//!
+//! Serde sees through smart pointers, so by default two `Rc`s pointing at the
+//! same allocation serialize as two independent subtrees and the diagram shows
+//! an exploded tree instead of the real DAG. Marking a pointer as shared tells
+//! the exporter to key it by allocation address: the first visit emits the
+//! pointee as usual, every later visit reuses that atom, so the shared node
+//! ends up with one incoming edge per owner.
+//!
+//! There are two ways to opt in:
+//!
+//! - wrap the pointer in [`SharedRef`] (works anywhere a type is expected,
+//!   e.g. `Vec<SharedRef<Rc<Node>>>`), or
+//! - annotate an `Rc`/`Arc` field with `#[serde(with = "caraspace::shared")]`.
+//!
+//! ```
+//! use std::rc::Rc;
+//! use caraspace::{export_json_instance, SharedRef};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Leaf {
+//!     v: i32,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Pair {
+//!     #[serde(with = "caraspace::shared")]
+//!     left: Rc<Leaf>,
+//!     right: SharedRef<Rc<Leaf>>,
+//! }
+//!
+//! let leaf = Rc::new(Leaf { v: 1 });
+//! let pair = Pair { left: leaf.clone(), right: SharedRef(leaf) };
+//! let inst = export_json_instance(&pair);
+//! assert_eq!(inst.atoms.iter().filter(|a| a.r#type == "Leaf").count(), 1);
+//! ```
+//!
+//! [`from_datum`](crate::from_datum) rebuilds the sharing: every owner of the
+//! same atom gets a clone of the same pointer, so `Rc::ptr_eq` holds again.
+//!
+//! Other serializers are unaffected — a marked pointer serializes exactly like
+//! the pointee, the same as an unmarked `Rc`.
+//!
+//! # Reference cycles
+//!
+//! Because export tracks allocations it is still inside of, marked pointers
+//! also make `Rc<RefCell<_>>` cycles exportable: revisiting an allocation emits
+//! an edge back to its existing atom instead of recursing forever. Back-pointers
+//! are usually `Weak`, which [`SharedRef`] (and [`weak`] for
+//! `#[serde(with = "caraspace::shared::weak")]`) accept too — a live `Weak`
+//! exports as an edge to its target's atom, a dangling one as `None`.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::{Rc, Weak};
+//! use caraspace::{export_json_instance, SharedRef};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Node {
+//!     val: i32,
+//!     next: Option<SharedRef<Rc<RefCell<Node>>>>,
+//!     prev: SharedRef<Weak<RefCell<Node>>>,
+//! }
+//!
+//! let a = Rc::new(RefCell::new(Node { val: 1, next: None, prev: SharedRef(Weak::new()) }));
+//! let b = Rc::new(RefCell::new(Node { val: 2, next: None, prev: SharedRef(Rc::downgrade(&a)) }));
+//! a.borrow_mut().next = Some(SharedRef(b.clone()));
+//!
+//! let inst = export_json_instance(&SharedRef(a));
+//! assert_eq!(inst.atoms.iter().filter(|x| x.r#type == "Node").count(), 2);
+//! ```
+//!
+//! Reify cannot rebuild a cycle: a pointer that leads back into an allocation
+//! still being reconstructed is reported as a [`ReifyError`](crate::ReifyError).
+
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::rc::{self, Rc};
+use std::sync::{self, Arc};
+
+/// Newtype-struct name that marks a shared pointer on the wire. Only
+/// caraspace's own serializer and deserializer look for it.
+pub(crate) const SHARED_TOKEN: &str = "$caraspace::private::Shared";
+
+thread_local! {
+    /// Allocation address of the shared pointer currently being serialized,
+    /// handed from [`SharedRef`]'s `Serialize` impl to the export serializer.
+    static PENDING_ADDR: Cell<Option<usize>> = const { Cell::new(None) };
+
+    /// Per-[`from_datum`](crate::from_datum) cache of rebuilt pointers, keyed
+    /// by atom id. `None` outside a reify call.
+    static REIFY_CACHE: RefCell<Option<ReifyCache>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct ReifyCache {
+    /// Atom id of the shared pointer currently being deserialized.
+    pending_atom: Option<String>,
+    /// Atoms whose pointer is still being rebuilt; reaching one again is a cycle.
+    in_progress: HashSet<String>,
+    pointers: HashMap<String, Box<dyn Any>>,
+}
+
+/// Smart pointers whose allocation identity can be preserved by
+/// [`SharedRef`]. Implemented for [`Rc`] and [`Arc`].
+pub trait SharedPointer: Deref + Clone + 'static {
+    /// Address of the pointed-to allocation; equal for all clones.
+    fn allocation(this: &Self) -> usize;
+
+    /// Allocate a fresh pointer around `value` (used by reify).
+    fn from_value(value: Self::Target) -> Self
+    where
+        Self::Target: Sized;
+}
+
+impl<T: ?Sized + 'static> SharedPointer for Rc<T> {
+    fn allocation(this: &Self) -> usize {
+        Rc::as_ptr(this) as *const () as usize
+    }
+
+    fn from_value(value: T) -> Self
+    where
+        T: Sized,
+    {
+        Rc::new(value)
+    }
+}
+
+impl<T: ?Sized + 'static> SharedPointer for Arc<T> {
+    fn allocation(this: &Self) -> usize {
+        Arc::as_ptr(this) as *const () as usize
+    }
+
+    fn from_value(value: T) -> Self
+    where
+        T: Sized,
+    {
+        Arc::new(value)
+    }
+}
+
+/// Wrapper that exports a `Rc`/`Arc` as a shared node: every `SharedRef`
+/// pointing at the same allocation becomes an edge to the same atom.
+///
+/// Derefs to the wrapped pointer, so existing code that reads through the
+/// pointer keeps working.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedRef<P>(pub P);
+
+impl<P> Deref for SharedRef<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P> From<P> for SharedRef<P> {
+    fn from(ptr: P) -> Self {
+        SharedRef(ptr)
+    }
+}
+
+impl<P> Serialize for SharedRef<P>
+where
+    P: SharedPointer,
+    P::Target: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, P> Deserialize<'de> for SharedRef<P>
+where
+    P: SharedPointer,
+    P::Target: DeserializeOwned + Sized,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(SharedRef)
+    }
+}
+
+/// `serialize_with` half of `#[serde(with = "caraspace::shared")]`.
+pub fn serialize<P, S>(ptr: &P, serializer: S) -> Result<S::Ok, S::Error>
+where
+    P: SharedPointer,
+    P::Target: Serialize,
+    S: Serializer,
+{
+    PENDING_ADDR.with(|addr| addr.set(Some(P::allocation(ptr))));
+    serializer.serialize_newtype_struct(SHARED_TOKEN, &**ptr)
+}
+
+/// `deserialize_with` half of `#[serde(with = "caraspace::shared")]`.
+pub fn deserialize<'de, P, D>(deserializer: D) -> Result<P, D::Error>
+where
+    P: SharedPointer,
+    P::Target: DeserializeOwned + Sized,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_newtype_struct(SHARED_TOKEN, SharedVisitor(PhantomData))
+}
+
+struct SharedVisitor<P>(PhantomData<P>);
+
+impl<'de, P> Visitor<'de> for SharedVisitor<P>
+where
+    P: SharedPointer,
+    P::Target: DeserializeOwned + Sized,
+{
+    type Value = P;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a shared pointer")
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<P, D::Error> {
+        let atom = REIFY_CACHE.with(|cache| {
+            cache
+                .borrow_mut()
+                .as_mut()
+                .and_then(|cache| cache.pending_atom.take())
+        });
+        let Some(atom) = atom else {
+            return P::Target::deserialize(deserializer).map(P::from_value);
+        };
+
+        if let Some(existing) = cached_pointer::<P>(&atom) {
+            return Ok(existing);
+        }
+        let entered = REIFY_CACHE.with(|cache| {
+            cache
+                .borrow_mut()
+                .as_mut()
+                .map_or(true, |cache| cache.in_progress.insert(atom.clone()))
+        });
+        if !entered {
+            return Err(de::Error::custom(format!(
+                "reference cycle through atom {atom} cannot be rebuilt"
+            )));
+        }
+        let result = P::Target::deserialize(deserializer).map(P::from_value);
+        REIFY_CACHE.with(|cache| {
+            if let Some(cache) = cache.borrow_mut().as_mut() {
+                cache.in_progress.remove(&atom);
+                if let Ok(ptr) = &result {
+                    cache.pointers.insert(atom, Box::new(ptr.clone()));
+                }
+            }
+        });
+        result
+    }
+}
+
+fn cached_pointer<P: SharedPointer>(atom: &str) -> Option<P> {
+    REIFY_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .as_ref()
+            .and_then(|cache| cache.pointers.get(atom))
+            .and_then(|ptr| ptr.downcast_ref::<P>())
+            .cloned()
+    })
+}
+
+/// Non-owning counterparts of [`SharedPointer`]s. Implemented for
+/// [`rc::Weak`] and [`sync::Weak`].
+pub trait WeakPointer: Clone + 'static {
+    /// The owning pointer this upgrades to.
+    type Strong: SharedPointer;
+
+    /// Upgrade to an owning pointer, or `None` if the target was dropped.
+    fn upgrade(this: &Self) -> Option<Self::Strong>;
+
+    /// Downgrade an owning pointer.
+    fn downgrade(strong: &Self::Strong) -> Self;
+
+    /// A pointer that never upgrades.
+    fn dangling() -> Self;
+}
+
+impl<T: 'static> WeakPointer for rc::Weak<T> {
+    type Strong = Rc<T>;
+
+    fn upgrade(this: &Self) -> Option<Rc<T>> {
+        this.upgrade()
+    }
+
+    fn downgrade(strong: &Rc<T>) -> Self {
+        Rc::downgrade(strong)
+    }
+
+    fn dangling() -> Self {
+        rc::Weak::new()
+    }
+}
+
+impl<T: 'static> WeakPointer for sync::Weak<T> {
+    type Strong = Arc<T>;
+
+    fn upgrade(this: &Self) -> Option<Arc<T>> {
+        this.upgrade()
+    }
+
+    fn downgrade(strong: &Arc<T>) -> Self {
+        Arc::downgrade(strong)
+    }
+
+    fn dangling() -> Self {
+        sync::Weak::new()
+    }
+}
+
+impl<T: Serialize + 'static> Serialize for SharedRef<rc::Weak<T>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        weak::serialize(&self.0, serializer)
+    }
+}
+
+impl<T: Serialize + 'static> Serialize for SharedRef<sync::Weak<T>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        weak::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T: DeserializeOwned + 'static> Deserialize<'de> for SharedRef<rc::Weak<T>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        weak::deserialize(deserializer).map(SharedRef)
+    }
+}
+
+impl<'de, T: DeserializeOwned + 'static> Deserialize<'de> for SharedRef<sync::Weak<T>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        weak::deserialize(deserializer).map(SharedRef)
+    }
+}
+
+/// `#[serde(with = "caraspace::shared::weak")]` for `Weak` fields.
+///
+/// A live `Weak` serializes like `Some` of its upgraded pointer (so export
+/// points it at the target's existing atom); a dangling one as `None`.
+pub mod weak {
+    use super::{SharedRef, WeakPointer};
+    use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    /// `serialize_with` half of `#[serde(with = "caraspace::shared::weak")]`.
+    pub fn serialize<W, S>(ptr: &W, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        W: WeakPointer,
+        <W::Strong as std::ops::Deref>::Target: Serialize,
+        S: Serializer,
+    {
+        match W::upgrade(ptr) {
+            Some(strong) => serializer.serialize_some(&SharedRef(strong)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// `deserialize_with` half of `#[serde(with = "caraspace::shared::weak")]`.
+    ///
+    /// Inside [`from_datum`](crate::from_datum) the result points at the same
+    /// allocation as the other owners of that atom. Elsewhere nothing else
+    /// owns the target, so — like serde's own `Weak` impl — it comes back
+    /// dangling.
+    pub fn deserialize<'de, W, D>(deserializer: D) -> Result<W, D::Error>
+    where
+        W: WeakPointer,
+        <W::Strong as std::ops::Deref>::Target: DeserializeOwned + Sized,
+        D: Deserializer<'de>,
+    {
+        let strong = Option::<SharedRef<W::Strong>>::deserialize(deserializer)?;
+        Ok(strong.map_or_else(W::dangling, |strong| W::downgrade(&strong.0)))
+    }
+}
+
+/// Take the address recorded by the most recent shared-pointer `serialize`.
+pub(crate) fn take_pending_addr() -> Option<usize> {
+    PENDING_ADDR.with(Cell::take)
+}
+
+/// Record that the next shared pointer deserialized starts at `atom_id`.
+pub(crate) fn set_pending_atom(atom_id: &str) {
+    REIFY_CACHE.with(|cache| {
+        if let Some(cache) = cache.borrow_mut().as_mut() {
+            cache.pending_atom = Some(atom_id.to_string());
+        }
+    });
+}
+
+/// Run `f` with a fresh pointer cache, restoring the previous one afterwards
+/// so nested reify calls don't share pointers.
+pub(crate) fn with_reify_cache<R>(f: impl FnOnce() -> R) -> R {
+    let outer = REIFY_CACHE.with(|cache| cache.replace(Some(ReifyCache::default())));
+    let result = f();
+    REIFY_CACHE.with(|cache| cache.replace(outer));
+    result
+}
@@ -0,0 +1,325 @@
+//! Hand rendered diagrams to your own code instead of a file and a browser.
+//!
+//! By default a diagram is written to a file and opened (or, for the
+//! terminal formats, printed to stderr). A
+//! [`DiagramSink`](crate::sink::DiagramSink) set with
+//! [`DiagramOptions::sink`](crate::DiagramOptions::sink) receives the
+//! rendered [`Artifact`](crate::sink::Artifact) instead and decides what happens to it: copy it to
+//! a shared directory, hand it to an editor extension, attach it to a
+//! report. Any `Fn(&Artifact) -> io::Result<()>` closure is a sink.
+//!
+//! ```no_run
+//! use caraspace::sink::Artifact;
+//! use caraspace::{diagram_with, DiagramOptions, SpytialDecorators};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let shared = DiagramOptions::new().sink(|artifact: &Artifact| {
+//!     let name = format!("latest.{}", artifact.extension());
+//!     std::fs::write(std::path::Path::new("/mnt/team").join(name), artifact.content)
+//! });
+//! diagram_with(&Point { x: 1, y: 2 }, &shared);
+//! ```
+//!
+//! To only change *how* the written file is opened, use
+//! [`DiagramOptions::browser`](crate::DiagramOptions::browser) or
+//! `SPYTIAL_BROWSER` instead.
+//!
+//! # Built-in sinks
+//!
+//! | Sink | Delivers to |
+//! |------|-------------|
+//! | [`FileSink::temp`](crate::sink::FileSink::temp) | A fresh file under the temp dir, like the default |
+//! | [`FileSink::at`](crate::sink::FileSink::at) | One fixed path, overwritten each time |
+//! | [`StdoutSink`](crate::sink::StdoutSink) | Standard output |
+//! | [`MemorySink`](crate::sink::MemorySink) | A list you can inspect, e.g. in tests |
+//! | [`HttpSink`](crate::sink::HttpSink) | A running `viz_server`, by HTTP POST |
+//!
+//! [`set_default_sink`](crate::sink::set_default_sink) sends every diagram
+//! in the process to one sink:
+//!
+//! ```
+//! use caraspace::sink::{set_default_sink, MemorySink};
+//! use caraspace::SpytialDecorators;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! struct Stack {
+//!     items: Vec<u32>,
+//! }
+//!
+//! let captured = MemorySink::new();
+//! set_default_sink(captured.clone());
+//! caraspace::diagram(&Stack { items: vec![1, 2, 3] });
+//! assert_eq!(captured.len(), 1);
+//! ```
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::options::{self, DiagramOptions, OutputFormat};
+
+/// A rendered diagram, as handed to a [`DiagramSink`].
+#[derive(Debug, Clone, Copy)]
+pub struct Artifact<'a> {
+    /// What [`Artifact::content`] is. Never [`OutputFormat::Serve`]: live
+    /// diagrams go to the live server.
+    pub format: OutputFormat,
+    /// The HTML page, SVG document or terminal text.
+    pub content: &'a str,
+    /// The title set in the options, if any.
+    pub title: Option<&'a str>,
+}
+
+impl Artifact<'_> {
+    /// File extension for [`Artifact::content`]: `html`, `svg` or `txt`.
+    pub fn extension(&self) -> &'static str {
+        match self.format {
+            OutputFormat::Html | OutputFormat::Serve => "html",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Terminal | OutputFormat::Ascii => "txt",
+        }
+    }
+}
+
+/// Receives every rendered diagram in place of the default delivery.
+///
+/// Called on the thread that drew the diagram; an error is reported on
+/// stderr and otherwise ignored, like every other diagram failure, or
+/// returned as a [`crate::DiagramError::Io`] by [`crate::render`](fn@crate::render).
+pub trait DiagramSink: Send + Sync {
+    /// Deliver one rendered diagram.
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()>;
+}
+
+impl<F> DiagramSink for F
+where
+    F: Fn(&Artifact<'_>) -> io::Result<()> + Send + Sync,
+{
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        self(artifact)
+    }
+}
+
+/// Keep an `Arc` of a sink to look at what it received, e.g. in a test.
+impl<S: DiagramSink + ?Sized> DiagramSink for Arc<S> {
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        (**self).deliver(artifact)
+    }
+}
+
+/// A sink shared between clones of [`crate::DiagramOptions`].
+#[derive(Clone)]
+pub(crate) struct SharedSink(pub(crate) Arc<dyn DiagramSink>);
+
+impl fmt::Debug for SharedSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DiagramSink")
+    }
+}
+
+/// Send every diagram drawn with the process-wide options (by
+/// [`crate::diagram`], [`crate::dbg!`], ...) to `sink` from now on.
+///
+/// The other [`options::default_options`] are kept; a later
+/// [`crate::set_default_options`] replaces the sink along with them.
+pub fn set_default_sink(sink: impl DiagramSink + 'static) {
+    options::update_default_options(|options| options.sink(sink));
+}
+
+/// Writes each diagram to a file, and optionally opens it.
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    options: DiagramOptions,
+}
+
+impl FileSink {
+    /// A fresh file under the OS temp dir per diagram, named like the
+    /// default ([`options::DEFAULT_FILE_NAME`]).
+    pub fn temp() -> Self {
+        Self {
+            options: DiagramOptions::new().open(false),
+        }
+    }
+
+    /// A fresh file in `dir` per diagram. The directory must exist.
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            options: DiagramOptions::new().output_dir(dir).open(false),
+        }
+    }
+
+    /// Always `path`, overwritten by each diagram.
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self {
+            options: DiagramOptions::new().output_path(path).open(false),
+        }
+    }
+
+    /// Also open each written file with the platform's default handler.
+    pub fn open(mut self, open: bool) -> Self {
+        self.options = self.options.open(open);
+        self
+    }
+}
+
+impl DiagramSink for FileSink {
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        crate::write_and_open(artifact.content, artifact.extension(), &self.options)?;
+        Ok(())
+    }
+}
+
+/// Writes each diagram to standard output, e.g. to pipe an SVG into
+/// another tool.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutSink;
+
+impl DiagramSink for StdoutSink {
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        out.write_all(artifact.content.as_bytes())?;
+        if !artifact.content.ends_with('\n') {
+            out.write_all(b"\n")?;
+        }
+        out.flush()
+    }
+}
+
+/// An [`Artifact`] kept by a [`MemorySink`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedDiagram {
+    /// See [`Artifact::format`].
+    pub format: OutputFormat,
+    /// See [`Artifact::content`].
+    pub content: String,
+    /// See [`Artifact::title`].
+    pub title: Option<String>,
+}
+
+/// Keeps every diagram in memory, so tests can assert on diagrams without
+/// touching the filesystem. Clones share the same list.
+///
+/// An HTML page can be read back with [`crate::jsondata::from_html`].
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    diagrams: Arc<Mutex<Vec<CapturedDiagram>>>,
+}
+
+impl MemorySink {
+    /// An empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The diagrams received so far, oldest first.
+    pub fn diagrams(&self) -> Vec<CapturedDiagram> {
+        self.lock().clone()
+    }
+
+    /// The most recent diagram.
+    pub fn last(&self) -> Option<CapturedDiagram> {
+        self.lock().last().cloned()
+    }
+
+    /// Remove and return the diagrams received so far.
+    pub fn take(&self) -> Vec<CapturedDiagram> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Number of diagrams received (and not taken).
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether nothing has been received (or everything was taken).
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<CapturedDiagram>> {
+        self.diagrams.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl DiagramSink for MemorySink {
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        self.lock().push(CapturedDiagram {
+            format: artifact.format,
+            content: artifact.content.to_string(),
+            title: artifact.title.map(str::to_string),
+        });
+        Ok(())
+    }
+}
+
+/// POSTs each diagram to `/diagram` on a running `viz_server` (the binary
+/// in this package), which then serves it at `/rust_viz_data.html`.
+///
+/// ```no_run
+/// use caraspace::sink::{set_default_sink, HttpSink};
+///
+/// // viz_server listening on port 8080, e.g. in a container
+/// set_default_sink(HttpSink::new("127.0.0.1:8080"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpSink {
+    addr: String,
+}
+
+/// How long [`HttpSink`] waits on the server before giving up.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl HttpSink {
+    /// A sink posting to the server at `addr` (`host:port`, no scheme).
+    pub fn new(addr: impl Into<String>) -> Self {
+        let addr = addr.into();
+        let addr = addr
+            .strip_prefix("http://")
+            .unwrap_or(&addr)
+            .trim_end_matches('/')
+            .to_string();
+        Self { addr }
+    }
+}
+
+impl DiagramSink for HttpSink {
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        let content_type = match artifact.format {
+            OutputFormat::Svg => "image/svg+xml",
+            OutputFormat::Terminal | OutputFormat::Ascii => "text/plain; charset=utf-8",
+            OutputFormat::Html | OutputFormat::Serve => "text/html; charset=utf-8",
+        };
+        let mut stream = TcpStream::connect(&self.addr)?;
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+        write!(
+            stream,
+            "POST /diagram HTTP/1.1\r\nHost: {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nX-Caraspace-Sink: 1\r\nConnection: close\r\n\r\n",
+            self.addr,
+            artifact.content.len()
+        )?;
+        stream.write_all(artifact.content.as_bytes())?;
+        stream.flush()?;
+
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status)?;
+        let status = status.trim_end();
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "{} answered `{status}` to POST /diagram",
+                self.addr
+            ))),
+        }
+    }
+}
@@ -2,14 +2,87 @@
 //!
 //! Compile-time decorator system for SpyTial spatial layout and visualization.
 //! Provides type-level decorator collection via derive macros and YAML serialization.
+//!
+//! ## Checked at compile time
+//!
+//! `#[derive(SpytialDecorators)]` parses every selector in the decorator
+//! attributes and rejects one that would not parse, pointing at the string:
+//!
+//! ```compile_fail
+//! use caraspace::SpytialDecorators;
+//!
+//! #[derive(serde::Serialize, SpytialDecorators)]
+//! #[orientation(selector = "{x, y : Node | x->y in }", directions = ["below"])]
+//! struct Node {
+//!     key: u32,
+//!     left: Option<Box<Node>>,
+//! }
+//! ```
+//!
+//! When every field of a struct is a primitive, a standard container of
+//! one, or the struct itself, the derive knows all the type and field names
+//! a selector could mean, and a name one typo away from one of them is an
+//! error too ("did you mean `Node`?"):
+//!
+//! ```compile_fail
+//! use caraspace::SpytialDecorators;
+//!
+//! #[derive(serde::Serialize, SpytialDecorators)]
+//! #[orientation(selector = "{x, y : Nod | x->y in lft}", directions = ["below"])]
+//! struct Node {
+//!     key: u32,
+//!     left: Option<Box<Node>>,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! use caraspace::SpytialDecorators;
+//!
+//! #[derive(serde::Serialize, SpytialDecorators)]
+//! #[attribute(field = "kye")]
+//! struct Node {
+//!     key: u32,
+//!     left: Option<Box<Node>>,
+//! }
+//! ```
+//!
+//! Names unlike any of them are allowed, since a hand-written `Serialize`
+//! impl or a type defined elsewhere can produce them:
+//!
+//! ```
+//! use caraspace::SpytialDecorators;
+//!
+//! #[derive(serde::Serialize, SpytialDecorators)]
+//! #[orientation(selector = "{x, y : Node | x->y in left}", directions = ["below"])]
+//! #[attribute(field = "key")]
+//! #[hide_atom(selector = "Sentinel")]
+//! struct Node {
+//!     key: u32,
+//!     left: Option<Box<Node>>,
+//! }
+//! ```
+//!
+//! Decorators written on a field with `#[spytial(...)]` name the field by
+//! being on it, so an unknown key there is an error as well:
+//!
+//! ```compile_fail
+//! use caraspace::SpytialDecorators;
+//!
+//! #[derive(serde::Serialize, SpytialDecorators)]
+//! struct Node {
+//!     #[spytial(atribute)]
+//!     key: u32,
+//!     left: Option<Box<Node>>,
+//! }
+//! ```
 
 /// Decorator data types, derive-macro runtime, and YAML serialization plumbing.
 pub mod runtime;
 
 // Re-export the main types and functions
 pub use runtime::{
-    get_type_decorators, register_type_decorators, to_yaml, AlignConstraint, AlignParams,
-    Constraint, CyclicConstraint, CyclicParams, DecoProbe, DefaultDecorators, Directive,
-    GroupConstraint, GroupParams, HasSpytialDecorators, OrientationConstraint, OrientationParams,
-    SpytialDecorators, SpytialDecoratorsBuilder,
+    get_type_decorators, qualified_type_name, register_type_decorators, register_type_name,
+    to_yaml, AlignConstraint, AlignParams, Constraint, CyclicConstraint, CyclicParams, DecoProbe,
+    DefaultDecorators, Directive, GroupConstraint, GroupParams, HasSpytialDecorators,
+    OrientationConstraint, OrientationParams, SpytialDecorators, SpytialDecoratorsBuilder,
 };
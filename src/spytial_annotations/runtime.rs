@@ -38,8 +38,10 @@ fn is_not_negated(negated: &bool) -> bool {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct SpytialDecorators {
     /// Layout/structural constraints (orientation, alignment, cycles, grouping).
+    #[serde(default)]
     pub constraints: Vec<Constraint>,
     /// Visual/behavioral directives (color, size, icon, edges, tags, flags, etc.).
+    #[serde(default)]
     pub directives: Vec<Directive>,
 }
 
@@ -498,15 +500,18 @@ impl<T> DefaultDecorators for DecoProbe<T> {
     }
 }
 
-/// Global registry for type-level decorators keyed by type name
+/// Global registry for type-level decorators keyed by full type name, as
+/// given by [`std::any::type_name`] (module path and type arguments included)
 static TYPE_REGISTRY: LazyLock<Mutex<HashMap<String, SpytialDecorators>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
 /// Register SpyTial decorators for a type, keyed by name.
 ///
 /// Called by code generated from `#[derive(SpytialDecorators)]` the first
-/// time `T::decorators()` is invoked. End users normally do not call this
-/// directly.
+/// time `T::decorators()` is invoked (every time, for a generic type), with
+/// `std::any::type_name::<T>()` as the name, so `Wrapper<Person>` and
+/// `Wrapper<Order>`, or two `Node`s in different modules, get their own
+/// entries. End users normally do not call this directly.
 pub fn register_type_decorators(type_name: &str, decorators: SpytialDecorators) {
     // Recover from a poisoned lock: a panic in a previous decorator builder
     // should not permanently brick decorator collection for the rest of the
@@ -520,13 +525,182 @@ pub fn register_type_decorators(type_name: &str, decorators: SpytialDecorators)
 
 /// Look up previously-registered decorators for `type_name`, if any.
 ///
-/// Returns `None` if the type has never had its `decorators()` method called
-/// (and therefore never registered itself with the global registry).
+/// `type_name` is normally a full name from [`std::any::type_name`]. A bare
+/// name such as `"Person"` (what serde reports for a struct) also matches,
+/// when exactly one registered type has that name once its module path and
+/// type arguments are stripped.
+///
+/// A derived type that is not generic is also listed at link time, so it is
+/// found whether or not its `decorators()` has run yet. A generic instantiation is found once its `decorators()` has
+/// run. Returns `None` for anything else, or if a bare name is ambiguous.
 pub fn get_type_decorators(type_name: &str) -> Option<SpytialDecorators> {
+    if let Some(decorators) = registered_decorators(type_name) {
+        return Some(decorators);
+    }
+    // Not collected yet: ask the type itself, outside the registry lock,
+    // since its `decorators()` registers it.
+    let bare = !type_name.contains([':', '<']);
+    let mut candidates = linked_registrations().filter(|r| {
+        let name = (r.type_name)();
+        name == type_name || (bare && bare_type_name(name) == type_name)
+    });
+    match (candidates.next(), candidates.next()) {
+        (Some(registration), None) => Some((registration.decorators)()),
+        _ => None,
+    }
+}
+
+fn registered_decorators(type_name: &str) -> Option<SpytialDecorators> {
     let registry = TYPE_REGISTRY
         .lock()
         .unwrap_or_else(|poisoned| poisoned.into_inner());
-    registry.get(type_name).cloned()
+    if let Some(decorators) = registry.get(type_name) {
+        return Some(decorators.clone());
+    }
+    if type_name.contains([':', '<']) {
+        return None;
+    }
+    let mut matches = registry
+        .iter()
+        .filter(|(name, _)| bare_type_name(name) == type_name);
+    match (matches.next(), matches.next()) {
+        (Some((_, decorators)), None) => Some(decorators.clone()),
+        _ => None,
+    }
+}
+
+/// A derived type that can be asked for its decorators by name. Every
+/// non-generic `#[derive(SpytialDecorators)]` type adds one to
+/// [`TYPE_REGISTRATIONS`]; not public API.
+#[doc(hidden)]
+pub struct TypeRegistration {
+    /// [`std::any::type_name`] of the type.
+    pub type_name: fn() -> &'static str,
+    /// The type's [`HasSpytialDecorators::decorators`].
+    pub decorators: fn() -> SpytialDecorators,
+}
+
+/// Every non-generic derived type in the program, gathered by the linker,
+/// so that a generic type's unbounded parameter finds its argument's
+/// decorators without that argument having been used first.
+#[doc(hidden)]
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "fuchsia"
+))]
+#[linkme::distributed_slice]
+pub static TYPE_REGISTRATIONS: [TypeRegistration];
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "fuchsia"
+))]
+fn linked_registrations() -> impl Iterator<Item = &'static TypeRegistration> {
+    TYPE_REGISTRATIONS.iter()
+}
+
+/// Without linker support, types are found once their `decorators()` ran.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "fuchsia"
+)))]
+fn linked_registrations() -> impl Iterator<Item = &'static TypeRegistration> {
+    std::iter::empty()
+}
+
+/// Adds a non-generic derived type to [`TYPE_REGISTRATIONS`]; emitted by
+/// `#[derive(SpytialDecorators)]`, not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_decorators {
+    ($ty:ty) => {
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "windows",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "illumos",
+            target_os = "fuchsia"
+        ))]
+        const _: () = {
+            #[$crate::__private::linkme::distributed_slice(
+                $crate::spytial_annotations::runtime::TYPE_REGISTRATIONS
+            )]
+            #[linkme(crate = $crate::__private::linkme)]
+            static REGISTRATION: $crate::spytial_annotations::runtime::TypeRegistration =
+                $crate::spytial_annotations::runtime::TypeRegistration {
+                    type_name: ::std::any::type_name::<$ty>,
+                    decorators:
+                        <$ty as $crate::spytial_annotations::HasSpytialDecorators>::decorators,
+                };
+        };
+    };
+}
+
+/// `name` without its module path or type arguments:
+/// `app::model::Wrapper<app::Person>` is `Wrapper`.
+pub(crate) fn bare_type_name(name: &str) -> &str {
+    let path = name.split('<').next().unwrap_or(name);
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Types exported under a qualified name, keyed by full type name: the
+/// name serde reports for them and the name their atoms get instead
+static TYPE_NAMES: LazyLock<Mutex<HashMap<String, (String, String)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Register that atoms of `type_name` (a full name from
+/// [`std::any::type_name`]), which serde names `serde_name`, are exported
+/// with the type `exported` instead.
+///
+/// Called by code generated from `#[derive(SpytialDecorators)]` for types
+/// marked `#[spytial(type_name = "...")]` or `#[spytial(qualified)]`, when
+/// their `decorators()` first runs. End users normally do not call this
+/// directly.
+pub fn register_type_name(type_name: &str, serde_name: &str, exported: &str) {
+    let mut names = TYPE_NAMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    names.insert(
+        type_name.to_string(),
+        (serde_name.to_string(), exported.to_string()),
+    );
+}
+
+/// The name atoms of `type_name` are exported with, if it registered one
+/// and serde calls it `serde_name`.
+pub fn qualified_type_name(type_name: &str, serde_name: &str) -> Option<String> {
+    let names = TYPE_NAMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match names.get(type_name) {
+        Some((serde, exported)) if serde == serde_name => Some(exported.clone()),
+        _ => None,
+    }
 }
 
 /// Serialize a [`SpytialDecorators`] value to its YAML wire format.
@@ -781,14 +955,41 @@ impl SpytialDecoratorsBuilder {
         self
     }
 
+    /// Rename the type `from` to `to` in the selectors pushed so far.
+    ///
+    /// Used by the derive macro for a type exported under a qualified name,
+    /// before the decorators of its field types are merged in, so the
+    /// type's own selectors match its atoms.
+    pub fn rename_type(mut self, from: &str, to: &str) -> Self {
+        let mut own = SpytialDecorators {
+            constraints: std::mem::take(&mut self.constraints),
+            directives: std::mem::take(&mut self.directives),
+        };
+        crate::selector::rename_type(&mut own, from, to);
+        self.constraints = own.constraints;
+        self.directives = own.directives;
+        self
+    }
+
     /// Merge another set of decorators into this builder.
     ///
     /// Used by the derive macro together with [`DecoProbe`] for safe
     /// compile-time decorator collection from field types that may or may
     /// not implement [`HasSpytialDecorators`].
+    ///
+    /// Constraints and directives already in the builder are not added
+    /// again, so a type reached along several paths contributes once.
     pub fn extend_with(mut self, other: SpytialDecorators) -> Self {
-        self.constraints.extend(other.constraints);
-        self.directives.extend(other.directives);
+        for constraint in other.constraints {
+            if !self.constraints.contains(&constraint) {
+                self.constraints.push(constraint);
+            }
+        }
+        for directive in other.directives {
+            if !self.directives.contains(&directive) {
+                self.directives.push(directive);
+            }
+        }
         self
     }
 
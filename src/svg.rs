@@ -0,0 +1,288 @@
+//! Static SVG rendering, entirely in Rust.
+//!
+//! [`to_svg`] lays out a [`JsonDataInstance`] natively and writes a
+//! self-contained SVG document: no browser, no JavaScript, no spytial-core.
+//! The result drops straight into a PR comment, a README or an mdBook page.
+//!
+//! The layout is a layered tree layout rather than spytial-core's
+//! constraint solver, so it honors the decorators that make sense for a
+//! still image — `orientation`, `align`, `group`, `atomColor`, `edgeColor`,
+//! `hideField`, `hideAtom` and `attribute` — and ignores the interactive or
+//! negated ones. Use the HTML path ([`crate::diagram`]) when you need the
+//! full solver.
+//!
+//! ```
+//! use caraspace::{export_json_instance, svg, SpytialDecorators};
+//! use caraspace::spytial_annotations::HasSpytialDecorators;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! #[attribute(field = "key")]
+//! struct Node {
+//!     key: u32,
+//!     left: Option<Box<Node>>,
+//! }
+//!
+//! let tree = Node { key: 2, left: Some(Box::new(Node { key: 1, left: None })) };
+//! let image = svg::to_svg(&export_json_instance(&tree), &Node::decorators());
+//! assert!(image.starts_with("<svg"));
+//! assert!(image.contains("key: 2"));
+//! ```
+
+use std::fmt::Write as _;
+
+use crate::jsondata::JsonDataInstance;
+use crate::scene::{is_dark, Scene};
+use crate::spytial_annotations::SpytialDecorators;
+
+/// Approximate advance width of one character at [`FONT_SIZE`].
+const CHAR_WIDTH: f64 = 7.2;
+const FONT_SIZE: f64 = 12.0;
+const LINE_HEIGHT: f64 = 15.0;
+const PAD_X: f64 = 10.0;
+const PAD_Y: f64 = 6.0;
+const MIN_NODE_WIDTH: f64 = 36.0;
+/// Space between node columns and rows, and around the whole drawing.
+const GAP_X: f64 = 28.0;
+const GAP_Y: f64 = 56.0;
+const MARGIN: f64 = 20.0;
+const GROUP_PAD: f64 = 12.0;
+
+/// Render `instance` as an SVG document, applying `decorators`.
+pub fn to_svg(instance: &JsonDataInstance, decorators: &SpytialDecorators) -> String {
+    let scene = Scene::build(instance, decorators);
+    let grid = scene.grid();
+
+    let lines: Vec<Vec<String>> = scene
+        .nodes
+        .iter()
+        .map(|n| {
+            std::iter::once(n.label.clone())
+                .chain(n.attributes.iter().map(|(k, v)| format!("{k}: {v}")))
+                .collect()
+        })
+        .collect();
+    let sizes: Vec<(f64, f64)> = lines
+        .iter()
+        .map(|ls| {
+            let chars = ls.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            let w = (chars as f64 * CHAR_WIDTH + 2.0 * PAD_X).max(MIN_NODE_WIDTH);
+            let h = ls.len() as f64 * LINE_HEIGHT + 2.0 * PAD_Y;
+            (w, h)
+        })
+        .collect();
+    let slot_w = sizes.iter().map(|s| s.0).fold(MIN_NODE_WIDTH, f64::max) + GAP_X;
+    let row_h = sizes.iter().map(|s| s.1).fold(0.0, f64::max) + GAP_Y;
+
+    // Node centres. Group padding is reserved around everything so cluster
+    // outlines never leave the canvas.
+    let offset = MARGIN + GROUP_PAD + LINE_HEIGHT;
+    let centres: Vec<(f64, f64)> = grid
+        .iter()
+        .map(|&(c, r)| {
+            (
+                offset + c * slot_w + slot_w / 2.0,
+                offset + r as f64 * row_h + row_h / 2.0,
+            )
+        })
+        .collect();
+    let width = centres.iter().map(|c| c.0).fold(0.0, f64::max) + slot_w / 2.0 + offset;
+    let height = centres.iter().map(|c| c.1).fold(0.0, f64::max) + row_h / 2.0 + offset;
+    let bbox = |i: usize| {
+        let ((cx, cy), (w, h)) = (centres[i], sizes[i]);
+        (cx - w / 2.0, cy - h / 2.0, w, h)
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="{FONT_SIZE}">"#,
+        w = num(width.max(2.0 * MARGIN)),
+        h = num(height.max(2.0 * MARGIN)),
+    );
+
+    // One arrowhead per edge colour: `context-stroke` is too new to rely on.
+    let mut colours: Vec<&str> = Vec::new();
+    for e in &scene.edges {
+        let c = e.color.as_deref().unwrap_or(DEFAULT_STROKE);
+        if !colours.contains(&c) {
+            colours.push(c);
+        }
+    }
+    out.push_str("<defs>\n");
+    for (i, c) in colours.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            r#"<marker id="arrow{i}" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="7" markerHeight="7" orient="auto"><path d="M0,0 L10,5 L0,10 z" fill="{}"/></marker>"#,
+            escape(c)
+        );
+    }
+    out.push_str("</defs>\n");
+
+    for g in &scene.groups {
+        let (mut x0, mut y0, mut x1, mut y1) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for &m in &g.members {
+            let (x, y, w, h) = bbox(m);
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x + w);
+            y1 = y1.max(y + h);
+        }
+        let (x0, y0) = (x0 - GROUP_PAD, y0 - GROUP_PAD - LINE_HEIGHT);
+        let (x1, y1) = (x1 + GROUP_PAD, y1 + GROUP_PAD);
+        let _ = writeln!(
+            out,
+            r##"<g class="group"><rect x="{}" y="{}" width="{}" height="{}" rx="8" fill="#f4f6fb" stroke="#8a94a6" stroke-dasharray="4 3"/><text x="{}" y="{}" fill="#4a5468">{}</text></g>"##,
+            num(x0),
+            num(y0),
+            num(x1 - x0),
+            num(y1 - y0),
+            num(x0 + 6.0),
+            num(y0 + LINE_HEIGHT),
+            escape(&g.name)
+        );
+    }
+
+    for e in &scene.edges {
+        let colour = e.color.as_deref().unwrap_or(DEFAULT_STROKE);
+        let marker = colours.iter().position(|c| *c == colour).unwrap_or(0);
+        let width = e.weight.unwrap_or(1.2);
+        let dash = match e.style.as_deref() {
+            Some("dashed") => r#" stroke-dasharray="6 4""#,
+            Some("dotted") => r#" stroke-dasharray="2 3""#,
+            _ => "",
+        };
+        let (path, (lx, ly)) = if e.from == e.to {
+            let (x, y, w, _) = bbox(e.from);
+            let (sx, sy) = (x + w * 0.75, y);
+            let (ex, ey) = (x + w, y + 8.0);
+            (
+                format!(
+                    "M{},{} C{},{} {},{} {},{}",
+                    num(sx),
+                    num(sy),
+                    num(sx + 4.0),
+                    num(sy - 28.0),
+                    num(ex + 28.0),
+                    num(ey - 4.0),
+                    num(ex),
+                    num(ey)
+                ),
+                (ex + 14.0, sy - 16.0),
+            )
+        } else {
+            let (a, b) = (centres[e.from], centres[e.to]);
+            let start = clip(a, b, sizes[e.from]);
+            let end = clip(b, a, sizes[e.to]);
+            (
+                format!(
+                    "M{},{} L{},{}",
+                    num(start.0),
+                    num(start.1),
+                    num(end.0),
+                    num(end.1)
+                ),
+                ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0),
+            )
+        };
+        let _ = write!(
+            out,
+            r#"<g class="edge" data-relation="{}"><path d="{path}" fill="none" stroke="{}" stroke-width="{}"{dash} marker-end="url(#arrow{marker})"/>"#,
+            escape(&e.relation),
+            escape(colour),
+            num(width),
+        );
+        if e.show_label {
+            let _ = write!(
+                out,
+                r#"<text x="{}" y="{}" text-anchor="middle" fill="{}" stroke="white" stroke-width="3" paint-order="stroke">{}</text>"#,
+                num(lx),
+                num(ly - 3.0),
+                escape(colour),
+                escape(&e.label)
+            );
+        }
+        out.push_str("</g>\n");
+    }
+
+    for (i, node) in scene.nodes.iter().enumerate() {
+        let (x, y, w, h) = bbox(i);
+        let fill = node.color.as_deref().unwrap_or("white");
+        let ink = if is_dark(fill) { "white" } else { "#1d2330" };
+        let _ = write!(
+            out,
+            r#"<g class="atom" data-id="{}" data-type="{}"><rect x="{}" y="{}" width="{}" height="{}" rx="4" fill="{}" stroke="{DEFAULT_STROKE}"/>"#,
+            escape(&node.id),
+            escape(&node.r#type),
+            num(x),
+            num(y),
+            num(w),
+            num(h),
+            escape(fill),
+        );
+        for (l, line) in lines[i].iter().enumerate() {
+            let weight = if l == 0 { r#" font-weight="bold""# } else { "" };
+            let _ = write!(
+                out,
+                r#"<text x="{}" y="{}" text-anchor="middle" fill="{ink}"{weight}>{}</text>"#,
+                num(x + w / 2.0),
+                num(y + PAD_Y + LINE_HEIGHT * (l as f64 + 1.0) - 3.0),
+                escape(line)
+            );
+        }
+        out.push_str("</g>\n");
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+const DEFAULT_STROKE: &str = "#333333";
+
+/// Point where the segment from the centre `from` towards `to` leaves a box
+/// of `size` centred on `from`.
+fn clip(from: (f64, f64), to: (f64, f64), size: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    if dx == 0.0 && dy == 0.0 {
+        return from;
+    }
+    let sx = if dx == 0.0 {
+        f64::INFINITY
+    } else {
+        size.0 / 2.0 / dx.abs()
+    };
+    let sy = if dy == 0.0 {
+        f64::INFINITY
+    } else {
+        size.1 / 2.0 / dy.abs()
+    };
+    let t = sx.min(sy);
+    (from.0 + dx * t, from.1 + dy * t)
+}
+
+/// Coordinates with at most one decimal, without a trailing `.0`.
+fn num(v: f64) -> String {
+    let rounded = (v * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{rounded:.1}")
+    }
+}
+
+/// Escape text for use in SVG character data and attribute values.
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c if c.is_control() && c != '\t' => out.push('\u{FFFD}'),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,232 @@
+//! Plain-text rendering for terminals and headless sessions.
+//!
+//! [`to_text`] draws a diagram with box-drawing characters, so it reads fine
+//! over SSH or in a CI log where there is no browser to open. Set
+//! `SPYTIAL_MODE=terminal` and [`crate::dbg!`] / [`crate::diagram`] print
+//! this to stderr instead of writing an HTML file.
+//!
+//! Values whose visible edges form a forest (every node reached at most
+//! once, no cycles) print as indented trees, with `left`-oriented children
+//! before `right`-oriented ones. Leaves reached more than once, such as the
+//! one `None` atom every empty child shares, are repeated under each parent:
+//!
+//! ```text
+//! Node (key: 5)
+//! ├─ left ─▶ Node (key: 3) [red]
+//! └─ right ─▶ Node (key: 8)
+//!    └─ right ─▶ Node (key: 9) [red]
+//! ```
+//!
+//! Anything else — shared nodes, cycles — falls back to an adjacency listing
+//! where each node is numbered once and edges refer back by number:
+//!
+//! ```text
+//! [0] Node (val: 1)
+//!     └─ next ─▶ [1]
+//! [1] Node (val: 2)
+//!     └─ prev ─▶ [0]
+//! ```
+//!
+//! Decorators are applied as for the other native renderers: hidden atoms
+//! and fields are left out, `attribute` fields appear in parentheses,
+//! `atomColor` in brackets and groups in a trailing list.
+
+use std::fmt::Write as _;
+
+use crate::jsondata::JsonDataInstance;
+use crate::scene::Scene;
+use crate::spytial_annotations::SpytialDecorators;
+
+/// Characters used to draw tree branches and edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// Unicode box-drawing characters (`├─`, `└─`, `│`, `─▶`).
+    Unicode,
+    /// 7-bit ASCII (`|-`, `` `- ``, `|`, `->`) for terminals and logs that
+    /// mangle Unicode.
+    Ascii,
+}
+
+impl Charset {
+    fn tee(self) -> &'static str {
+        match self {
+            Charset::Unicode => "├─ ",
+            Charset::Ascii => "|- ",
+        }
+    }
+
+    fn elbow(self) -> &'static str {
+        match self {
+            Charset::Unicode => "└─ ",
+            Charset::Ascii => "`- ",
+        }
+    }
+
+    fn pipe(self) -> &'static str {
+        match self {
+            Charset::Unicode => "│  ",
+            Charset::Ascii => "|  ",
+        }
+    }
+
+    fn arrow(self) -> &'static str {
+        match self {
+            Charset::Unicode => "─▶",
+            Charset::Ascii => "->",
+        }
+    }
+}
+
+/// Render `instance` as text with Unicode box-drawing characters.
+///
+/// ```
+/// use caraspace::export::export_json_instance;
+/// use caraspace::terminal;
+///
+/// let text = terminal::to_text(&export_json_instance(&vec![10, 20]), &Default::default());
+/// assert_eq!(text, "seq[2]\n├─ idx[0] ─▶ 10\n└─ idx[1] ─▶ 20\n");
+/// ```
+pub fn to_text(instance: &JsonDataInstance, decorators: &SpytialDecorators) -> String {
+    to_text_with(instance, decorators, Charset::Unicode)
+}
+
+/// Render `instance` as text using the given [`Charset`].
+pub fn to_text_with(
+    instance: &JsonDataInstance,
+    decorators: &SpytialDecorators,
+    charset: Charset,
+) -> String {
+    let scene = Scene::build(instance, decorators);
+    let n = scene.nodes.len();
+
+    let mut out_edges: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut indegree = vec![0usize; n];
+    for (i, e) in scene.edges.iter().enumerate() {
+        out_edges[e.from].push(i);
+        indegree[e.to] += 1;
+    }
+    for (from, edges) in out_edges.iter_mut().enumerate() {
+        edges.sort_by_key(|&i| scene.side(from, scene.edges[i].to));
+    }
+
+    let mut out = String::new();
+    if is_forest(&scene, &indegree, &out_edges) {
+        let mut roots: Vec<usize> = (0..n).filter(|&i| indegree[i] == 0).collect();
+        if let Some(at) = roots.iter().position(|&r| Some(r) == scene.root) {
+            roots[..=at].rotate_right(1);
+        }
+        for (k, &root) in roots.iter().enumerate() {
+            if k > 0 {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "{}", describe(&scene, root));
+            tree(&scene, &out_edges, root, "", charset, &mut out);
+        }
+    } else {
+        for (i, edges) in out_edges.iter().enumerate() {
+            let _ = writeln!(out, "[{i}] {}", describe(&scene, i));
+            for (k, &e) in edges.iter().enumerate() {
+                let branch = if k + 1 == edges.len() {
+                    charset.elbow()
+                } else {
+                    charset.tee()
+                };
+                let edge = &scene.edges[e];
+                let _ = writeln!(
+                    out,
+                    "    {branch}{}[{}]",
+                    edge_label(&scene, e, charset),
+                    edge.to
+                );
+            }
+        }
+    }
+
+    if !scene.groups.is_empty() {
+        out.push('\n');
+        for g in &scene.groups {
+            let members: Vec<String> = g.members.iter().map(|&m| describe(&scene, m)).collect();
+            let _ = writeln!(out, "group {}: {}", g.name, members.join(", "));
+        }
+    }
+    out
+}
+
+/// Every node other than a leaf has at most one incoming edge, and none
+/// sits on a cycle.
+fn is_forest(scene: &Scene, indegree: &[usize], out_edges: &[Vec<usize>]) -> bool {
+    if (0..indegree.len()).any(|i| indegree[i] > 1 && !out_edges[i].is_empty()) {
+        return false;
+    }
+    // With in-degree <= 1 everywhere but at leaves, which cannot be on a
+    // cycle, a cycle is exactly a node that no in-degree-0 node reaches.
+    let mut seen = vec![false; indegree.len()];
+    let mut stack: Vec<usize> = (0..indegree.len()).filter(|&i| indegree[i] == 0).collect();
+    while let Some(at) = stack.pop() {
+        if !std::mem::replace(&mut seen[at], true) {
+            stack.extend(out_edges[at].iter().map(|&e| scene.edges[e].to));
+        }
+    }
+    seen.iter().all(|&s| s)
+}
+
+fn tree(
+    scene: &Scene,
+    out_edges: &[Vec<usize>],
+    at: usize,
+    prefix: &str,
+    charset: Charset,
+    out: &mut String,
+) {
+    let edges = &out_edges[at];
+    for (k, &e) in edges.iter().enumerate() {
+        let last = k + 1 == edges.len();
+        let branch = if last { charset.elbow() } else { charset.tee() };
+        let child = scene.edges[e].to;
+        let _ = writeln!(
+            out,
+            "{prefix}{branch}{}{}",
+            edge_label(scene, e, charset),
+            describe(scene, child)
+        );
+        let deeper = format!("{prefix}{}", if last { "   " } else { charset.pipe() });
+        tree(scene, out_edges, child, &deeper, charset, out);
+    }
+}
+
+/// `label ─▶ ` for a shown label, or just the arrow.
+fn edge_label(scene: &Scene, edge: usize, charset: Charset) -> String {
+    let e = &scene.edges[edge];
+    if e.show_label {
+        format!("{} {} ", one_line(&e.label), charset.arrow())
+    } else {
+        format!("{} ", charset.arrow())
+    }
+}
+
+/// `Label (field: value, ...) [colour]` for one node.
+fn describe(scene: &Scene, i: usize) -> String {
+    let node = &scene.nodes[i];
+    let mut text = one_line(&node.label);
+    if !node.attributes.is_empty() {
+        let attrs: Vec<String> = node
+            .attributes
+            .iter()
+            .map(|(k, v)| format!("{}: {}", one_line(k), one_line(v)))
+            .collect();
+        let _ = write!(text, " ({})", attrs.join(", "));
+    }
+    if let Some(color) = &node.color {
+        let _ = write!(text, " [{color}]");
+    }
+    text
+}
+
+/// Keep each node on its own line: control characters print escaped.
+fn one_line(s: &str) -> String {
+    if s.chars().any(char::is_control) {
+        s.escape_debug().to_string()
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,170 @@
+//! Record a sequence of diagrams and scrub through them on one page.
+//!
+//! Calling `dbg!` at every step of an algorithm opens one unrelated tab per
+//! call. A [`Timeline`] collects the snapshots instead, each labelled with
+//! the call site that recorded it, and writes a single HTML page with a
+//! slider and step buttons (the ← / → keys work too) to move between them.
+//!
+//! Each step is laid out starting from the previous step's positions, so
+//! atoms that persist between steps stay put. Atom ids come from
+//! serialization order, so an atom "persists" when it is serialized in the
+//! same position in both snapshots.
+//!
+//! ```no_run
+//! use caraspace::{SpytialDecorators, Timeline};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, SpytialDecorators)]
+//! struct Stack {
+//!     items: Vec<u32>,
+//! }
+//!
+//! let mut stack = Stack { items: vec![] };
+//! let mut timeline = Timeline::new();
+//! for i in 0..5 {
+//!     stack.items.push(i);
+//!     timeline.record(&stack);
+//! }
+//! timeline.open();
+//! ```
+
+use std::fs;
+use std::io;
+use std::panic::Location;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::export::export_json_instance;
+use crate::jsondata::JsonDataInstance;
+use crate::options::DiagramOptions;
+use crate::spytial_annotations::HasSpytialDecorators;
+
+/// A recorded sequence of diagram snapshots.
+#[derive(Debug, Default)]
+pub struct Timeline {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Serialize)]
+struct Step {
+    label: String,
+    data: JsonDataInstance,
+    spec: String,
+}
+
+impl Timeline {
+    /// An empty timeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot `value`, labelled with the caller's `file:line`.
+    ///
+    /// Decorators are collected the same way as for [`crate::diagram`].
+    #[track_caller]
+    pub fn record<T: HasSpytialDecorators + Serialize>(&mut self, value: &T) {
+        let caller = Location::caller();
+        self.record_labeled(format!("{}:{}", caller.file(), caller.line()), value);
+    }
+
+    /// Snapshot `value` under a label of your choosing.
+    pub fn record_labeled<T: HasSpytialDecorators + Serialize>(
+        &mut self,
+        label: impl Into<String>,
+        value: &T,
+    ) {
+        let spec = crate::spytial_annotations::to_yaml(&T::decorators()).unwrap_or_default();
+        self.push(label, export_json_instance(value), spec);
+    }
+
+    /// Append an already-exported instance with a hand-written YAML spec.
+    pub fn push(
+        &mut self,
+        label: impl Into<String>,
+        instance: JsonDataInstance,
+        spec: impl Into<String>,
+    ) {
+        self.steps.push(Step {
+            label: label.into(),
+            data: instance,
+            spec: spec.into(),
+        });
+    }
+
+    /// Number of recorded steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether nothing has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Labels of the recorded steps, in order.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.steps.iter().map(|s| s.label.as_str())
+    }
+
+    /// The standalone HTML page, opening on the first step, with the title
+    /// and theme of [`crate::options::default_options`].
+    pub fn to_html(&self) -> String {
+        self.to_html_with(&crate::options::default_options())
+    }
+
+    /// Like [`Timeline::to_html`], with the [`title`](DiagramOptions::title)
+    /// and [`theme`](DiagramOptions::theme) of `options`.
+    pub fn to_html_with(&self, options: &DiagramOptions) -> String {
+        #[derive(Serialize)]
+        struct Steps<'a> {
+            steps: &'a [Step],
+        }
+
+        let (data, spec) = match self.steps.first() {
+            Some(first) => (to_json(&first.data), to_json(&first.spec)),
+            None => (r#"{"atoms":[],"relations":[]}"#.into(), r#""""#.into()),
+        };
+        let timeline = if self.steps.is_empty() {
+            "null".into()
+        } else {
+            to_json(&Steps { steps: &self.steps })
+        };
+        crate::html_page(
+            &data,
+            &spec,
+            "null",
+            &timeline,
+            options.title.as_deref().unwrap_or(crate::DEFAULT_TITLE),
+            options.theme,
+        )
+    }
+
+    /// Write the page from [`Timeline::to_html`] to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_html())
+    }
+
+    /// Write the page where [`crate::options::default_options`] says and
+    /// open it in the browser (or hand it to their sink), like
+    /// [`crate::diagram`] does.
+    ///
+    /// Best-effort: failures are reported on stderr.
+    pub fn open(&self) {
+        let options = crate::options::default_options();
+        let html = self.to_html_with(&options);
+        let artifact = crate::sink::Artifact {
+            format: crate::OutputFormat::Html,
+            content: &html,
+            title: options.title.as_deref(),
+        };
+        if let Err(err) = crate::deliver(&artifact, &options) {
+            eprintln!("caraspace: {err}");
+        }
+    }
+}
+
+/// Timeline data is plain strings and vectors, so encoding cannot fail.
+fn to_json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).expect("timeline data always serializes")
+}
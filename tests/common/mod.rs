@@ -0,0 +1,52 @@
+//! Helpers shared by the integration tests. Each test binary uses a subset.
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use caraspace::spytial_annotations::{Directive, HasSpytialDecorators, SpytialDecorators};
+use caraspace::DiagramOptions;
+
+/// A fresh, empty directory under the temp dir for one test.
+///
+/// `tag` must be unique within a test binary; the process id keeps binaries
+/// running in parallel apart.
+pub fn scratch_dir(tag: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("caraspace-{tag}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Options that write the diagram without opening a browser.
+pub fn quiet() -> DiagramOptions {
+    DiagramOptions::new().open(false)
+}
+
+/// The `(selector, value)` of each atom colour `T` collected, in order.
+pub fn atom_colors<T: HasSpytialDecorators>() -> Vec<(String, String)> {
+    atom_colors_in(&T::decorators())
+}
+
+/// The `(selector, value)` of each atom colour in `decorators`, in order.
+pub fn atom_colors_in(decorators: &SpytialDecorators) -> Vec<(String, String)> {
+    decorators
+        .directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::AtomColor(c) => {
+                Some((c.atom_color.selector.clone(), c.atom_color.value.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Owned `(selector, value)` pairs to compare [`atom_colors`] against.
+pub fn colors(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(selector, value)| (selector.to_string(), value.to_string()))
+        .collect()
+}
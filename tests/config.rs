@@ -0,0 +1,190 @@
+//! Project configuration: finding and parsing `caraspace.toml` and
+//! `[package.metadata.caraspace]`, and what the settings do to a diagram.
+
+mod common;
+
+use caraspace::config::ProjectConfig;
+use caraspace::jsondata::load_html;
+use caraspace::options::REDACTED;
+use caraspace::sink::MemorySink;
+use caraspace::{
+    diagram_with, render_with, DiagramOptions, OutputFormat, SpytialDecorators, Theme,
+};
+use common::scratch_dir;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, SpytialDecorators)]
+struct Account {
+    user: String,
+    password: String,
+    key: ApiKey,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct ApiKey {
+    secret: String,
+}
+
+fn sample() -> Account {
+    Account {
+        user: "ada".into(),
+        password: "hunter2".into(),
+        key: ApiKey {
+            secret: "sk-123".into(),
+        },
+    }
+}
+
+fn only_file(dir: &Path) -> PathBuf {
+    let files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), 1, "{files:?}");
+    files.into_iter().next().unwrap()
+}
+
+#[test]
+fn caraspace_toml_is_found_from_a_subdirectory() {
+    let root = scratch_dir("find");
+    fs::write(
+        root.join("caraspace.toml"),
+        "output_dir = \"diagrams\"\nformat = \"svg\"\nopen = false\n",
+    )
+    .unwrap();
+    let nested = root.join("crates").join("app");
+    fs::create_dir_all(&nested).unwrap();
+
+    let config = ProjectConfig::find(&nested).unwrap().unwrap();
+    assert_eq!(config.output_dir, Some(root.join("diagrams")));
+    assert_eq!(config.format, Some(OutputFormat::Svg));
+    assert_eq!(config.open, Some(false));
+}
+
+#[test]
+fn package_metadata_is_read_when_there_is_no_file() {
+    let root = scratch_dir("manifest");
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"app\"\n\n[package.metadata.caraspace]\ntheme = \"dark\"\nmax_depth = 3\n\n[package.metadata.caraspace.redact]\nfields = [\"password\"]\n",
+    )
+    .unwrap();
+
+    let config = ProjectConfig::find(&root).unwrap().unwrap();
+    assert_eq!(config.theme, Some(Theme::Dark));
+    assert_eq!(config.max_depth, Some(3));
+    assert_eq!(config.redact.fields, ["password"]);
+
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+    assert!(ProjectConfig::find(&root).unwrap().is_none());
+}
+
+#[test]
+fn unknown_keys_are_errors() {
+    let root = scratch_dir("unknown");
+    fs::write(root.join("caraspace.toml"), "formt = \"svg\"\n").unwrap();
+
+    let err = ProjectConfig::find(&root).unwrap_err();
+    assert!(err.message().contains("caraspace.toml"), "{err}");
+    assert!(err.message().contains("formt"), "{err}");
+}
+
+#[test]
+fn settings_shape_the_written_diagram() {
+    let root = scratch_dir("apply");
+    fs::create_dir_all(root.join("out")).unwrap();
+    fs::write(
+        root.join("team.yaml"),
+        "directives:\n  - flag: hideDisconnected\n",
+    )
+    .unwrap();
+    let config = ProjectConfig::from_toml_str(
+        r#"
+            output_dir = "out"
+            open = false
+            theme = "dark"
+            title = "Accounts"
+            spec_file = "team.yaml"
+
+            [redact]
+            fields = ["password"]
+            types = ["ApiKey"]
+        "#,
+        &root,
+    )
+    .unwrap();
+
+    diagram_with(&sample(), &config.apply(DiagramOptions::new()));
+
+    let path = only_file(&root.join("out"));
+    let html = fs::read_to_string(&path).unwrap();
+    assert!(html.contains(r#"data-theme="dark""#));
+    assert!(html.contains("<title>Accounts</title>"));
+    assert!(!html.contains("hunter2") && !html.contains("sk-123"));
+
+    let saved = load_html(&path).unwrap();
+    assert!(saved.spec.contains("hideDisconnected"), "{}", saved.spec);
+    let label = |type_name: &str| {
+        saved
+            .instance
+            .atoms
+            .iter()
+            .filter(|a| a.r#type == type_name)
+            .map(|a| a.label.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(label("ApiKey"), [REDACTED]);
+    assert!(label("string").contains(&"ada"));
+    assert!(label("string").contains(&REDACTED));
+    assert!(saved
+        .instance
+        .relations
+        .iter()
+        .all(|r| r.name != "secret" || r.tuples.is_empty()));
+}
+
+#[test]
+fn spec_file_is_read_once() {
+    let root = scratch_dir("spec-file");
+    fs::write(
+        root.join("team.yaml"),
+        "directives:\n  - flag: hideDisconnected\n",
+    )
+    .unwrap();
+    let config = ProjectConfig::from_toml_str("spec_file = \"team.yaml\"\n", &root).unwrap();
+    let spec = || {
+        let options = config.apply(DiagramOptions::new().sink(MemorySink::new()));
+        render_with(&sample(), &options).unwrap().spec
+    };
+
+    assert!(spec().contains("hideDisconnected"));
+    fs::remove_file(root.join("team.yaml")).unwrap();
+    assert!(spec().contains("hideDisconnected"));
+}
+
+#[cfg(unix)]
+#[test]
+fn browser_command_template_is_run_with_the_path() {
+    let root = scratch_dir("browser");
+    let marker = root.join("opened");
+    let page = root.join("page.html");
+    let options = DiagramOptions::new()
+        .output_path(&page)
+        .browser(format!("sh -c 'echo \"$0\" > {}' {{}}", marker.display()));
+    diagram_with(&sample(), &options);
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !marker.exists() || fs::read_to_string(&marker).unwrap().is_empty() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "browser command never ran"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    assert_eq!(
+        fs::read_to_string(&marker).unwrap().trim(),
+        page.display().to_string()
+    );
+}
@@ -347,3 +347,72 @@ fn concurrent_dbg_calls_do_not_panic() {
         *panics
     );
 }
+
+// ──────────────────────────────────────────────
+// 9. dbg! falls back to the Debug frontend
+//
+// A type with only `Debug` (no `Serialize`, no `SpytialDecorators`) must
+// still compile under `caraspace::dbg!` and have its `{:#?}` text parsed
+// into the diagram.
+// ──────────────────────────────────────────────
+
+#[test]
+fn dbg_falls_back_to_debug_for_non_serialize_types() {
+    suppress_browser_open();
+
+    #[derive(Debug)]
+    struct DebugOnly {
+        debug_only_marker: &'static str,
+    }
+
+    let target = unique_output_path("debug-fallback");
+
+    let _guard = diagram_lock();
+    env::set_var("SPYTIAL_OUTPUT_PATH", &target);
+    let value = dbg!(DebugOnly {
+        debug_only_marker: "caraspace-debug-fallback-Q7",
+    });
+    let read_result = fs::read_to_string(&target);
+    env::remove_var("SPYTIAL_OUTPUT_PATH");
+    drop(_guard);
+
+    assert_eq!(value.debug_only_marker, "caraspace-debug-fallback-Q7");
+    let contents = read_result.expect("dbg! on a Debug-only type should write a diagram");
+    assert!(contents.contains("caraspace-debug-fallback-Q7"));
+    assert!(contents.contains("DebugOnly"));
+    let _ = fs::remove_file(&target);
+}
+
+// ──────────────────────────────────────────────
+// 10. SPYTIAL_MODE=terminal
+//
+// Terminal mode draws the diagram on stderr and must not write (or open)
+// an HTML file.
+// ──────────────────────────────────────────────
+
+#[test]
+fn terminal_mode_writes_no_html() {
+    suppress_browser_open();
+
+    #[derive(Debug, Serialize, SpytialDecorators)]
+    struct TerminalOnly {
+        n: u8,
+    }
+
+    let target = unique_output_path("terminal-mode");
+
+    let _guard = diagram_lock();
+    env::set_var("SPYTIAL_OUTPUT_PATH", &target);
+    env::set_var("SPYTIAL_MODE", "terminal");
+    let value = dbg!(TerminalOnly { n: 4 });
+    env::remove_var("SPYTIAL_MODE");
+    env::remove_var("SPYTIAL_OUTPUT_PATH");
+    drop(_guard);
+
+    assert_eq!(value.n, 4);
+    assert!(
+        !target.exists(),
+        "terminal mode should not write {}",
+        target.display()
+    );
+}
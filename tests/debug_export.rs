@@ -0,0 +1,210 @@
+//! Tests for the `Debug`-text frontend (`caraspace::debug_export`).
+//!
+//! The parser should recover the same relational shape the serde exporter
+//! produces wherever `{:#?}` carries enough information, and degrade to an
+//! opaque `debug` atom — never a panic — when it doesn't.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use caraspace::debug_export::{parse_debug_instance, try_export_debug_instance};
+use caraspace::export_debug_instance;
+use caraspace::jsondata::{IAtom, JsonDataInstance};
+
+fn target<'a>(inst: &'a JsonDataInstance, rel: &str, src: &str) -> &'a IAtom {
+    let tuple = inst
+        .relations
+        .iter()
+        .filter(|r| r.name == rel)
+        .flat_map(|r| &r.tuples)
+        .find(|t| t.atoms[0] == src)
+        .unwrap_or_else(|| panic!("no {rel} tuple from {src}"));
+    let id = tuple.atoms.last().unwrap();
+    inst.atoms.iter().find(|a| &a.id == id).unwrap()
+}
+
+fn relation_len(inst: &JsonDataInstance, rel: &str) -> usize {
+    inst.relations
+        .iter()
+        .find(|r| r.name == rel)
+        .map_or(0, |r| r.tuples.len())
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum Color {
+    Red,
+    Black,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Node {
+    key: i32,
+    color: Color,
+    name: String,
+    left: Option<Box<Node>>,
+}
+
+#[test]
+fn struct_fields_become_relations() {
+    let n = Node {
+        key: 5,
+        color: Color::Black,
+        name: "root \"quoted\"\n".into(),
+        left: Some(Box::new(Node {
+            key: -3,
+            color: Color::Red,
+            name: "l".into(),
+            left: None,
+        })),
+    };
+    let inst = export_debug_instance(&n);
+
+    let root = &inst.atoms[0];
+    assert_eq!(root.r#type, "Node");
+    assert_eq!(target(&inst, "key", &root.id).label, "5");
+    assert_eq!(target(&inst, "key", &root.id).r#type, "int");
+    assert_eq!(target(&inst, "name", &root.id).label, "root \"quoted\"\n");
+    assert_eq!(target(&inst, "color", &root.id).label, "Black");
+
+    // `Some(x)` unwraps straight to x, like serde export.
+    let left = target(&inst, "left", &root.id);
+    assert_eq!(left.r#type, "Node");
+    assert_eq!(target(&inst, "key", &left.id).label, "-3");
+    assert_eq!(target(&inst, "left", &left.id).r#type, "None");
+}
+
+#[test]
+fn collections() {
+    let v = vec![(1, 'a'), (2, ',')];
+    let inst = export_debug_instance(&v);
+    assert_eq!(inst.atoms[0].label, "seq[2]");
+    assert_eq!(relation_len(&inst, "idx"), 2 + 2 * 2);
+    assert!(inst
+        .atoms
+        .iter()
+        .any(|a| a.r#type == "char" && a.label == ","));
+
+    let mut m = BTreeMap::new();
+    m.insert("k1", vec![1.5]);
+    m.insert("k2", vec![]);
+    let inst = export_debug_instance(&m);
+    assert_eq!(inst.atoms[0].r#type, "map");
+    assert_eq!(relation_len(&inst, "map_entry"), 2);
+    assert!(inst.atoms.iter().any(|a| a.r#type == "float"));
+
+    let s: BTreeSet<u8> = [3, 1].into_iter().collect();
+    let inst = export_debug_instance(&s);
+    assert_eq!(inst.atoms[0].r#type, "sequence");
+    assert_eq!(relation_len(&inst, "idx"), 2);
+}
+
+#[test]
+fn tuple_structs_and_units() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Meters(f64);
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Pair(i32, i32);
+    #[derive(Debug)]
+    struct Marker;
+
+    let inst = export_debug_instance(&(Meters(2.5), Pair(1, 2), Marker, ()));
+    assert!(inst
+        .atoms
+        .iter()
+        .any(|a| a.r#type == "newtype_struct" && a.label == "Meters"));
+    assert!(inst
+        .atoms
+        .iter()
+        .any(|a| a.r#type == "tuple_struct" && a.label == "Pair"));
+    assert!(inst
+        .atoms
+        .iter()
+        .any(|a| a.r#type == "unit_struct" && a.label == "Marker"));
+    assert!(inst.atoms.iter().any(|a| a.r#type == "unit"));
+}
+
+#[test]
+fn nested_options_keep_some_wrapper() {
+    let inst = export_debug_instance(&Some(Option::<i32>::None));
+    assert!(inst.atoms.iter().any(|a| a.r#type == "Some"));
+    assert!(inst.atoms.iter().any(|a| a.r#type == "None"));
+}
+
+#[test]
+fn compact_and_pretty_forms_agree() {
+    let v = vec![Some((1, "x")), None];
+    let compact = parse_debug_instance(&format!("{v:?}")).unwrap();
+    let pretty = parse_debug_instance(&format!("{v:#?}")).unwrap();
+    assert_eq!(compact.atoms.len(), pretty.atoms.len());
+}
+
+#[test]
+fn non_exhaustive_and_opaque_values() {
+    struct Custom;
+    impl fmt::Debug for Custom {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Custom")
+                .field("elapsed", &std::time::Duration::from_millis(1500))
+                .finish_non_exhaustive()
+        }
+    }
+    let inst = try_export_debug_instance(&Custom).unwrap();
+    let elapsed = target(&inst, "elapsed", &inst.atoms[0].id);
+    assert_eq!(elapsed.r#type, "debug");
+    assert_eq!(elapsed.label, "1.5s");
+}
+
+#[test]
+fn unparseable_text_degrades_to_one_atom() {
+    struct Broken;
+    impl fmt::Debug for Broken {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("Broken { oops")
+        }
+    }
+    assert!(try_export_debug_instance(&Broken).is_err());
+    let inst = export_debug_instance(&Broken);
+    assert_eq!(inst.atoms.len(), 1);
+    assert_eq!(inst.atoms[0].r#type, "debug");
+    assert_eq!(inst.atoms[0].label, "Broken { oops");
+}
+
+#[test]
+fn malformed_char_literals_stay_whole() {
+    let inst = parse_debug_instance(r"Code('\u{zz}')").unwrap();
+    let inner = target(&inst, "value", &inst.atoms[0].id);
+    assert_eq!(inner.r#type, "debug");
+    assert_eq!(inner.label, r"'\u{zz}'");
+
+    let inst = parse_debug_instance(r"['a', '\n', 'x]").unwrap();
+    assert_eq!(inst.atoms[0].label, "seq[3]");
+    let last = inst.atoms.last().unwrap();
+    assert_eq!((last.r#type.as_str(), last.label.as_str()), ("debug", "'x"));
+}
+
+#[test]
+fn lengths_are_counted_while_parsing() {
+    let inst = parse_debug_instance("([1, [2, 3]], {4: (5,)}, Pair(6, 7,), Wrap(8,))").unwrap();
+    let labels: Vec<(&str, &str)> = inst
+        .atoms
+        .iter()
+        .filter(|a| a.r#type != "int")
+        .map(|a| (a.r#type.as_str(), a.label.as_str()))
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            ("tuple", "tuple[4]"),
+            ("sequence", "seq[2]"),
+            ("sequence", "seq[2]"),
+            ("map", "map[1]"),
+            ("tuple", "tuple[1]"),
+            ("tuple_struct", "Pair"),
+            ("newtype_struct", "Wrap"),
+        ]
+    );
+}
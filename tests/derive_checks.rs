@@ -0,0 +1,115 @@
+//! The derive's compile-time selector parser against `caraspace::selector`.
+//!
+//! `macros/src/selector.rs` mirrors the runtime grammar, so both run over
+//! one corpus: valid selectors must be accepted by the derive and by
+//! `Selector::parse`, and invalid ones must be rejected by both with the
+//! same message, pointing at the selector literal.
+
+use caraspace::selector::Selector;
+use caraspace::spytial_annotations::{Directive, HasSpytialDecorators};
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+/// Derives `Node` with one `hide_atom` per selector, so this file compiles
+/// only if the derive accepts every one of them.
+macro_rules! valid_corpus {
+    ($($selector:literal),* $(,)?) => {
+        #[derive(Serialize, SpytialDecorators)]
+        $(#[hide_atom(selector = $selector)])*
+        #[allow(dead_code)]
+        struct Node {
+            key: u32,
+            left: Option<Box<Node>>,
+            right: Option<Box<Node>>,
+        }
+
+        const VALID: &[&str] = &[$($selector),*];
+    };
+}
+
+valid_corpus![
+    "Node",
+    "Node + Missing - none",
+    "Node & univ",
+    "left.right",
+    "(left + right).key",
+    "^left",
+    "*(left + right)",
+    "~left.left",
+    "Node->Node & iden",
+    "std::string::String",
+    "{x : Node | some x.left}",
+    "{x, y : Node | x->y in left + right}",
+    "{x : Node, y : Node | x->y in ^left}",
+    "{x : Node | no x.left and not x in Missing}",
+    "{x : Node | x !in Missing}",
+    "{x : Node | x.left = x.right or some x.key}",
+    "{x : Node | !(some x.left) || no x.right && true}",
+    "{x : Node | @:(x.key) = 1}",
+    "{x : Node | @:(x.key) != \"a b\"}",
+    "{x : Node | @:x = Red}",
+];
+
+#[test]
+fn the_derive_and_the_runtime_parser_accept_the_same_selectors() {
+    for selector in VALID {
+        if let Err(err) = Selector::parse(selector) {
+            panic!("the derive accepted `{selector}` but the runtime parser did not: {err}");
+        }
+    }
+    let decorators = Node::decorators();
+    let derived: Vec<&str> = decorators
+        .directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::HideAtom(d) => Some(d.hide_atom.selector.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(derived, VALID);
+}
+
+/// The selectors passed as `selector = "..."` in a UI test source.
+fn selectors_in(source: &str) -> Vec<String> {
+    source
+        .split("selector = \"")
+        .skip(1)
+        .map(|rest| {
+            let end = rest
+                .find("\")]")
+                .expect("selector literal ends the attribute");
+            rest[..end].replace("\\\"", "\"")
+        })
+        .collect()
+}
+
+#[test]
+fn the_derive_and_the_runtime_parser_reject_with_the_same_message() {
+    let source = include_str!("ui/invalid_selectors.rs");
+    let stderr = include_str!("ui/invalid_selectors.stderr");
+    let selectors = selectors_in(source);
+    assert_eq!(selectors.len(), 11);
+    for selector in selectors {
+        let err = match Selector::parse(&selector) {
+            Ok(_) => panic!("the runtime parser accepted `{selector}`"),
+            Err(err) => err,
+        };
+        let at = err.position().expect("syntax errors have a position");
+        let column = selector[..at].chars().count() + 1;
+        let expected = format!(
+            "invalid selector for #[hide_atom(...)]: {} at column {column}",
+            err.message()
+        );
+        assert!(
+            stderr.contains(&expected),
+            "the derive rejects `{selector}` differently; expected `{expected}`"
+        );
+    }
+}
+
+/// Each compile error must name the problem and point at the literal, not
+/// just fail somewhere.
+#[test]
+fn compile_errors_point_at_the_literal() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
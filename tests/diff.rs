@@ -0,0 +1,193 @@
+//! Tests for `caraspace::diff`: matching, change sets and highlighting.
+
+use caraspace::diff::{diff_by_key, diff_instances, Matching, ADDED_COLOR, REMOVED_COLOR};
+use caraspace::jsondata::JsonDataInstance;
+use caraspace::{diff, export_json_instance, SpytialDecorators};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Serialize, SpytialDecorators)]
+#[attribute(field = "key")]
+#[hide_atom(selector = "u32 + None")]
+struct Node {
+    key: u32,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+fn node(key: u32, left: Option<Node>, right: Option<Node>) -> Node {
+    Node {
+        key,
+        left: left.map(Box::new),
+        right: right.map(Box::new),
+    }
+}
+
+/// Label of the atom with `id` in the diff's instance.
+fn label<'a>(d: &'a caraspace::Diff, id: &str) -> &'a str {
+    &d.instance.atoms.iter().find(|a| a.id == id).unwrap().label
+}
+
+#[test]
+fn identical_values_have_an_empty_diff() {
+    let tree = node(2, Some(node(1, None, None)), None);
+    let d = diff(&tree, &tree.clone());
+    assert!(d.is_empty(), "{d:?}");
+    assert_eq!(
+        d.instance.atoms.len(),
+        export_json_instance(&tree).atoms.len()
+    );
+}
+
+#[test]
+fn path_matching_reports_added_removed_and_changed() {
+    let before = node(2, Some(node(1, None, None)), None);
+    let after = node(2, None, Some(node(3, None, None)));
+    let d = diff(&before, &after);
+
+    // The new right child (and its key) are added; the old left child and
+    // its key are removed.
+    let added: Vec<&str> = d.added.iter().map(|id| label(&d, id)).collect();
+    assert!(added.contains(&"Node") && added.contains(&"3"), "{added:?}");
+    let removed: Vec<&str> = d.removed.iter().map(|id| label(&d, id)).collect();
+    assert!(
+        removed.contains(&"Node") && removed.contains(&"1"),
+        "{removed:?}"
+    );
+    assert!(d.removed.iter().all(|id| id.starts_with("before:")));
+
+    // The root's fields were re-pointed, so the root changed.
+    assert!(d.changed.contains(&d.instance.atoms[0].id));
+    assert!(d.added_tuples.iter().any(|(rel, _)| rel == "right"));
+    assert!(d.removed_tuples.iter().any(|(rel, _)| rel == "left"));
+
+    // Removed tuples are merged back in, pointing at the removed atoms.
+    let left = d
+        .instance
+        .relations
+        .iter()
+        .find(|r| r.name == "left")
+        .unwrap();
+    assert!(left
+        .tuples
+        .iter()
+        .any(|t| t.atoms[1].starts_with("before:")));
+}
+
+#[test]
+fn changed_leaves_mark_their_owner() {
+    let before = node(2, Some(node(1, None, None)), None);
+    let after = node(2, Some(node(5, None, None)), None);
+    let d = diff(&before, &after);
+
+    assert!(d.added.is_empty() && d.removed.is_empty());
+    let changed: Vec<&str> = d.changed.iter().map(|id| label(&d, id)).collect();
+    // The `u32` leaf changed value, and the (visible) node holding it too.
+    assert_eq!(changed, ["Node", "5"]);
+}
+
+#[test]
+fn key_matching_follows_nodes_that_moved() {
+    // A right rotation: 1 <- 2 becomes 1 -> 2.
+    let before = node(2, Some(node(1, None, None)), None);
+    let after = node(1, None, Some(node(2, None, None)));
+
+    let by_path = diff(&before, &after);
+    assert!(!by_path.changed.is_empty());
+    // By path, the root's key reads 1 instead of 2.
+    assert!(by_path.changed.iter().any(|id| label(&by_path, id) == "1"));
+
+    let by_key = diff_by_key(&before, &after, "key");
+    assert!(by_key.added.is_empty(), "{:?}", by_key.added);
+    assert!(by_key.removed.is_empty(), "{:?}", by_key.removed);
+    // Only pointers moved: both nodes changed, no key value did.
+    let changed: Vec<&str> = by_key.changed.iter().map(|id| label(&by_key, id)).collect();
+    assert_eq!(changed, ["Node", "Node"]);
+}
+
+#[test]
+fn maps_match_entries_by_key() {
+    let mut before = BTreeMap::new();
+    before.insert("a", 1);
+    before.insert("b", 2);
+    let mut after = before.clone();
+    after.remove("a");
+    after.insert("b", 20);
+    after.insert("c", 3);
+
+    let d = diff_instances(
+        &export_json_instance(&before),
+        &export_json_instance(&after),
+        &Matching::Path,
+    );
+    let added: Vec<&str> = d.added.iter().map(|id| label(&d, id)).collect();
+    let removed: Vec<&str> = d.removed.iter().map(|id| label(&d, id)).collect();
+    let changed: Vec<&str> = d.changed.iter().map(|id| label(&d, id)).collect();
+    assert!(added.contains(&"c") && added.contains(&"3"), "{added:?}");
+    assert!(
+        removed.contains(&"a") && removed.contains(&"1"),
+        "{removed:?}"
+    );
+    assert!(changed.contains(&"20"), "{changed:?}");
+}
+
+#[test]
+fn highlighting_colours_atoms_and_edges() {
+    let before = node(2, Some(node(1, None, None)), None);
+    let after = node(2, None, Some(node(3, None, None)));
+    let d = diff(&before, &after);
+
+    let svg = d.to_svg();
+    assert!(svg.contains(&format!(r#"fill="{ADDED_COLOR}""#)));
+    assert!(svg.contains(&format!(r#"fill="{REMOVED_COLOR}""#)));
+    assert!(svg.contains(&format!(r#"stroke="{ADDED_COLOR}" stroke-width="2.5""#)));
+    assert!(svg.contains(&format!(
+        r#"stroke="{REMOVED_COLOR}" stroke-width="1.2" stroke-dasharray="6 4""#
+    )));
+    // Markers and pair relations stay out of the picture.
+    assert!(!svg.contains("data-type=\"DiffStatus\""));
+    assert!(!svg.contains("data-relation=\"diffStatus\""));
+    assert!(!svg.contains("data-relation=\"diffAdded_right\""));
+    // Every marker has the type its relation declares.
+    let status = d
+        .instance
+        .relations
+        .iter()
+        .find(|r| r.name == "diffStatus")
+        .unwrap();
+    for tuple in &status.tuples {
+        let marker = d.instance.atoms.iter().find(|a| a.id == tuple.atoms[1]);
+        assert_eq!(marker.unwrap().r#type, status.types[1]);
+        assert_eq!(tuple.types[1], status.types[1]);
+    }
+    // The type's own decorators still apply.
+    assert!(svg.contains("key: 3"));
+    assert!(!svg.contains("data-type=\"u32\""));
+}
+
+#[test]
+fn unary_relations_are_not_paths() {
+    let instance = |label: &str| {
+        JsonDataInstance::from_json_str(&format!(
+            r#"{{
+                "atoms": [
+                    {{"id": "a", "type": "Task", "label": "{label}"}},
+                    {{"id": "b", "type": "Task", "label": "write"}}
+                ],
+                "relations": [
+                    {{"id": "done", "name": "done", "types": ["Task"],
+                      "tuples": [{{"atoms": ["a"], "types": ["Task"]}}]}},
+                    {{"id": "next", "name": "next", "types": ["Task", "Task"],
+                      "tuples": [{{"atoms": ["a", "b"], "types": ["Task", "Task"]}}]}}
+                ]
+            }}"#
+        ))
+        .unwrap()
+    };
+    let (before, after) = (instance("plan"), instance("review"));
+    assert!(caraspace::jsondata::validate(&before).is_empty());
+
+    let d = diff_instances(&before, &after, &Matching::Path);
+    let changed: Vec<&str> = d.changed.iter().map(|id| label(&d, id)).collect();
+    assert_eq!(changed, ["review"]);
+}
@@ -0,0 +1,95 @@
+//! Tests for Graphviz DOT output (`caraspace::export::to_dot`).
+
+use caraspace::export::{export_json_instance, to_dot};
+use caraspace::spytial_annotations::{HasSpytialDecorators, SpytialDecoratorsBuilder};
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "name")]
+#[atom_color(selector = "Team", value = "#ccddff")]
+#[edge_style(field = "lead", value = "red", style = "dashed", weight = 2.0)]
+#[hide_field(field = "budget")]
+#[hide_atom(selector = "None")]
+#[group(selector = "Person", name = "people")]
+struct Team {
+    name: String,
+    lead: Person,
+    members: Vec<Person>,
+    budget: u64,
+    mascot: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Person {
+    handle: String,
+}
+
+fn team() -> Team {
+    Team {
+        name: "core \"infra\"".into(),
+        lead: Person {
+            handle: "ada".into(),
+        },
+        members: vec![Person {
+            handle: "bob".into(),
+        }],
+        budget: 10,
+        mascot: None,
+    }
+}
+
+#[test]
+fn decorators_translate_to_dot_attributes() {
+    let dot = to_dot(&export_json_instance(&team()), &Team::decorators());
+
+    assert!(dot.starts_with("digraph caraspace {\n"));
+    assert!(dot.ends_with("}\n"));
+    // attribute + escaping: the name is folded into the Team label.
+    assert!(dot.contains(r#"label="Team\nname: core \"infra\"""#));
+    assert!(dot.contains(r##"style="rounded,filled", fillcolor="#ccddff""##));
+    assert!(
+        dot.contains(r#"[label="lead", color="red", fontcolor="red", style=dashed, penwidth=2]"#)
+    );
+    // hideField and hideAtom omit edges and nodes.
+    assert!(!dot.contains("budget"));
+    assert!(!dot.contains(r#"label="None""#));
+    assert!(!dot.contains("mascot"));
+    // Both people land in one cluster.
+    assert!(dot.contains("subgraph cluster_0 {\n    label=\"people\";"));
+    assert_eq!(dot.matches("subgraph cluster_").count(), 1);
+}
+
+#[test]
+fn ternaries_become_labelled_edges() {
+    let mut scores = BTreeMap::new();
+    scores.insert("alice", 3);
+    let inst = export_json_instance(&(vec![7], scores));
+    let dot = to_dot(&inst, &Default::default());
+
+    assert!(dot.contains(r#"[label="idx[0]"]"#));
+    assert!(dot.contains(r#"[label="map_entry[alice]"]"#));
+    // The map key lives on the edge, not as a free-floating node.
+    assert!(!dot.contains(r#"[label="alice"]"#));
+}
+
+#[test]
+fn horizontal_align_becomes_same_rank() {
+    #[derive(Serialize)]
+    struct Pair {
+        a: u8,
+        b: u8,
+    }
+    let decorators = SpytialDecoratorsBuilder::new()
+        .align("a", "horizontal", false)
+        .edge_style("b", "blue", None, None, None, None, Some(false), Some(true))
+        .build();
+    let dot = to_dot(&export_json_instance(&Pair { a: 1, b: 2 }), &decorators);
+
+    assert!(dot.contains(r#"{ rank=same; "atom0"; "atom1"; }"#));
+    assert!(
+        !dot.contains(r#"label="b""#),
+        "hidden edge should be omitted"
+    );
+}
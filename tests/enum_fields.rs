@@ -0,0 +1,161 @@
+mod common;
+
+use caraspace::export::export_json_instance;
+use caraspace::spytial_annotations::{Constraint, HasSpytialDecorators};
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+use common::{atom_colors, colors};
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Payload", value = "orange")]
+struct Payload {
+    value: u32,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[orientation(selector = "left", directions = ["left", "below"])]
+enum Tree {
+    Leaf,
+    Node {
+        left: Box<Tree>,
+        val: Payload,
+        right: Box<Tree>,
+    },
+}
+
+fn has_atom_of_type<T: Serialize>(value: &T, ty: &str) -> bool {
+    export_json_instance(value)
+        .atoms
+        .iter()
+        .any(|a| a.r#type == ty)
+}
+
+#[test]
+fn named_variant_fields_contribute_their_decorators() {
+    assert_eq!(atom_colors::<Tree>(), colors(&[("Payload", "orange")]));
+    assert!(Tree::decorators()
+        .constraints
+        .iter()
+        .any(|c| matches!(c, Constraint::Orientation(o) if o.orientation.selector == "left")));
+
+    let tree = Tree::Node {
+        left: Box::new(Tree::Leaf),
+        val: Payload { value: 1 },
+        right: Box::new(Tree::Leaf),
+    };
+    assert!(has_atom_of_type(&tree, "Payload"));
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Literal", value = "green")]
+struct Literal {
+    value: i64,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Ident", value = "purple")]
+struct Ident {
+    name: String,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+enum Expr {
+    Lit(Literal),
+    Var(Ident),
+    Add(Box<Expr>, Box<Expr>),
+    Let {
+        name: Ident,
+        value: Box<Expr>,
+        body: Box<Expr>,
+    },
+}
+
+#[test]
+fn tuple_variant_fields_contribute_their_decorators_once() {
+    assert_eq!(
+        atom_colors::<Expr>(),
+        colors(&[("Literal", "green"), ("Ident", "purple"),])
+    );
+
+    let expr = Expr::Let {
+        name: Ident {
+            name: "x".to_string(),
+        },
+        value: Box::new(Expr::Lit(Literal { value: 2 })),
+        body: Box::new(Expr::Add(
+            Box::new(Expr::Var(Ident {
+                name: "x".to_string(),
+            })),
+            Box::new(Expr::Lit(Literal { value: 3 })),
+        )),
+    };
+    assert!(has_atom_of_type(&expr, "Literal"));
+    assert!(has_atom_of_type(&expr, "Ident"));
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Timer", value = "gray")]
+struct Timer {
+    remaining_ms: u64,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Session", value = "blue")]
+struct Session {
+    user: String,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+enum Connection {
+    Idle,
+    Connecting(Option<Timer>),
+    Connected {
+        session: Session,
+        retries: Vec<Timer>,
+    },
+    Closed(String),
+}
+
+#[test]
+fn state_machine_variants_are_walked_through_containers() {
+    assert_eq!(
+        atom_colors::<Connection>(),
+        colors(&[("Timer", "gray"), ("Session", "blue"),])
+    );
+
+    let states = [
+        Connection::Idle,
+        Connection::Connecting(Some(Timer { remaining_ms: 500 })),
+        Connection::Connected {
+            session: Session {
+                user: "ada".to_string(),
+            },
+            retries: Vec::new(),
+        },
+        Connection::Closed("bye".to_string()),
+    ];
+    assert!(has_atom_of_type(&states, "Timer"));
+    assert!(has_atom_of_type(&states, "Session"));
+}
+
+#[derive(Serialize, SpytialDecorators)]
+enum Undecorated {
+    Empty,
+    Count(u32),
+    Named { label: String },
+}
+
+#[test]
+fn enums_of_primitives_have_no_decorators() {
+    let _ = [
+        Undecorated::Empty,
+        Undecorated::Count(1),
+        Undecorated::Named {
+            label: "a".to_string(),
+        },
+    ];
+    let decorators = Undecorated::decorators();
+    assert!(decorators.directives.is_empty());
+    assert!(decorators.constraints.is_empty());
+}
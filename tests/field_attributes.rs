@@ -0,0 +1,154 @@
+use caraspace::export::export_json_instance;
+use caraspace::spytial_annotations::{
+    Directive, HasSpytialDecorators, SpytialDecorators as Decorators,
+};
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+#[derive(Serialize, SpytialDecorators)]
+struct Node {
+    #[spytial(attribute)]
+    key: u32,
+    #[spytial(hide)]
+    cache: Vec<u8>,
+    #[spytial(edge(color = "red", style = "dashed", weight = 2, show_label = false))]
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+/// Field, colour, style, weight, show_label, hidden and selector of every
+/// edge style in `decorators`.
+type EdgeStyle = (
+    String,
+    String,
+    Option<String>,
+    Option<f64>,
+    Option<bool>,
+    Option<bool>,
+    Option<String>,
+);
+
+fn edge_styles(decorators: &Decorators) -> Vec<EdgeStyle> {
+    decorators
+        .directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::EdgeStyle(e) => Some((
+                e.edge_style.field.clone(),
+                e.edge_style.value.clone(),
+                e.edge_style.style.clone(),
+                e.edge_style.weight,
+                e.edge_style.show_label,
+                e.edge_style.hidden,
+                e.edge_style.selector.clone(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn field_attributes_decorate_their_own_field() {
+    let decorators = Node::decorators();
+
+    assert!(decorators.directives.iter().any(|d| {
+        matches!(d, Directive::Attribute(a)
+            if a.attribute.field == "key" && a.attribute.selector.is_none())
+    }));
+    assert!(decorators.directives.iter().any(|d| {
+        matches!(d, Directive::HideField(h)
+            if h.hide_field.field == "cache" && h.hide_field.selector.is_none())
+    }));
+
+    assert_eq!(
+        edge_styles(&decorators),
+        [(
+            "left".to_string(),
+            "red".to_string(),
+            Some("dashed".to_string()),
+            Some(2.0),
+            Some(false),
+            None,
+            None,
+        )]
+    );
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[serde(rename_all = "camelCase")]
+struct Account {
+    #[spytial(attribute)]
+    account_id: u32,
+    #[serde(rename = "holder")]
+    #[spytial(edge(color = "green"))]
+    owner_name: String,
+    #[spytial(hide(selector = "Account"))]
+    r#type: String,
+}
+
+#[test]
+fn field_attributes_follow_serde_renames() {
+    let decorators = Account::decorators();
+    let value = Account {
+        account_id: 7,
+        owner_name: "ada".to_string(),
+        r#type: "checking".to_string(),
+    };
+    let instance = export_json_instance(&value);
+    let relations: Vec<&str> = instance.relations.iter().map(|r| r.name.as_str()).collect();
+
+    let attribute = decorators
+        .directives
+        .iter()
+        .find_map(|d| match d {
+            Directive::Attribute(a) => Some(&a.attribute),
+            _ => None,
+        })
+        .expect("expected an Attribute directive");
+    assert_eq!(attribute.field, "accountId");
+    assert!(relations.contains(&"accountId"));
+
+    let edges = edge_styles(&decorators);
+    assert_eq!(
+        (edges[0].0.as_str(), edges[0].1.as_str()),
+        ("holder", "green")
+    );
+    assert!(relations.contains(&"holder"));
+
+    let hide = decorators
+        .directives
+        .iter()
+        .find_map(|d| match d {
+            Directive::HideField(h) => Some(&h.hide_field),
+            _ => None,
+        })
+        .expect("expected a HideField directive");
+    assert_eq!(hide.field, "type");
+    assert_eq!(hide.selector.as_deref(), Some("Account"));
+    assert!(relations.contains(&"type"));
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "name")]
+struct Mixed {
+    name: String,
+    #[spytial(attribute, edge(color = "blue", hidden = true))]
+    parent: Option<Box<Mixed>>,
+}
+
+#[test]
+fn field_and_container_attributes_combine() {
+    let decorators = Mixed::decorators();
+    let attributes: Vec<&str> = decorators
+        .directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Attribute(a) => Some(a.attribute.field.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(attributes, ["name", "parent"]);
+
+    let edges = edge_styles(&decorators);
+    assert_eq!((edges[0].0.as_str(), edges[0].5), ("parent", Some(true)));
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use std::any::type_name;
+
+use caraspace::sink::MemorySink;
+use caraspace::spytial_annotations::{get_type_decorators, HasSpytialDecorators};
+use caraspace::{render_with, DiagramOptions, SpytialDecorators};
+use serde::Serialize;
+
+use common::{atom_colors, atom_colors_in, colors};
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Person", value = "blue")]
+struct Person {
+    name: String,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Order", value = "green")]
+struct Order {
+    id: u32,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "value")]
+struct Wrapper<T> {
+    value: T,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Bounded<T: HasSpytialDecorators> {
+    items: Vec<T>,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Fresh", value = "pink")]
+struct Fresh {
+    n: u32,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Unregistered", value = "gold")]
+struct Unregistered {
+    n: u32,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Team {
+    lead: Wrapper<Unregistered>,
+}
+
+#[test]
+fn each_instantiation_collects_its_own_type_argument() {
+    assert_eq!(
+        atom_colors::<Wrapper<Person>>(),
+        colors(&[("Person", "blue")])
+    );
+    assert_eq!(
+        atom_colors::<Wrapper<Order>>(),
+        colors(&[("Order", "green")])
+    );
+    assert!(atom_colors::<Wrapper<u32>>().is_empty());
+}
+
+#[test]
+fn each_instantiation_registers_under_its_own_name() {
+    Wrapper::<Person>::decorators();
+    Wrapper::<Order>::decorators();
+
+    let person = get_type_decorators(type_name::<Wrapper<Person>>()).unwrap();
+    let order = get_type_decorators(type_name::<Wrapper<Order>>()).unwrap();
+    assert_eq!(atom_colors_in(&person), colors(&[("Person", "blue")]));
+    assert_eq!(atom_colors_in(&order), colors(&[("Order", "green")]));
+    // serde only knows the bare name, which two instantiations share
+    assert!(get_type_decorators("Wrapper").is_none());
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Lonely", value = "teal")]
+struct Lonely {
+    n: u32,
+}
+
+/// `Lonely` appears nowhere else, so nothing has collected its decorators
+/// before `Wrapper<Lonely>` looks them up.
+#[test]
+fn unbounded_arguments_are_found_before_their_first_use() {
+    let options = DiagramOptions::new().sink(MemorySink::new());
+    let rendered = render_with(
+        &Wrapper {
+            value: Lonely { n: 1 },
+        },
+        &options,
+    )
+    .unwrap();
+    assert!(rendered.spec.contains("teal"), "{}", rendered.spec);
+}
+
+#[test]
+fn bounded_parameters_are_probed_directly() {
+    assert_eq!(
+        atom_colors::<Bounded<Fresh>>(),
+        colors(&[("Fresh", "pink")])
+    );
+}
+
+#[test]
+fn arguments_of_generic_field_types_are_walked() {
+    assert_eq!(atom_colors::<Team>(), colors(&[("Unregistered", "gold")]));
+}
+
+mod left {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[atom_color(selector = "Node", value = "red")]
+    pub struct Node {
+        pub key: u32,
+    }
+}
+
+mod right {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[atom_color(selector = "Node", value = "black")]
+    pub struct Node {
+        pub key: u32,
+    }
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Forest {
+    red: left::Node,
+    black: right::Node,
+}
+
+#[test]
+fn same_named_types_from_different_modules_do_not_clash() {
+    assert_eq!(
+        atom_colors::<Forest>(),
+        colors(&[("Node", "red"), ("Node", "black")])
+    );
+
+    let red = get_type_decorators(type_name::<left::Node>()).unwrap();
+    let black = get_type_decorators(type_name::<right::Node>()).unwrap();
+    assert_eq!(atom_colors_in(&red), colors(&[("Node", "red")]));
+    assert_eq!(atom_colors_in(&black), colors(&[("Node", "black")]));
+    assert!(get_type_decorators("Node").is_none());
+}
+
+#[test]
+fn a_unique_bare_name_still_finds_its_type() {
+    #[derive(Serialize, SpytialDecorators)]
+    #[atom_color(selector = "Solo", value = "white")]
+    struct Solo {
+        n: u32,
+    }
+
+    Solo::decorators();
+    let solo = get_type_decorators("Solo").unwrap();
+    assert_eq!(atom_colors_in(&solo), colors(&[("Solo", "white")]));
+}
@@ -0,0 +1,135 @@
+//! The HTML page must carry arbitrary field values and specs verbatim.
+//!
+//! Data and spec are embedded in `<script type="application/json">` blocks;
+//! these tests feed values that would break out of a JS template literal or
+//! a `<script>` element and check that the page is intact and the payload
+//! decodes back to exactly what went in.
+
+use caraspace::{diagram, diagram_with_spec};
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+const NASTY: &[&str] = &[
+    "`",
+    "${alert(1)}",
+    "`; alert(1); `",
+    "</script><script>alert(1)</script>",
+    "</SCRIPT >",
+    "<!-- <script>",
+    "]]>",
+    "\\`\\${x}",
+    "{{ spytial_spec }}",
+    "{{ json_data }}",
+    "/*__SPYTIAL_CORE_JS__*/",
+    "line\u{2028}separator\u{2029}",
+    "quote \" and 'single' & amp",
+];
+
+/// Run `render` with `SPYTIAL_OUTPUT_PATH` pointed at a fresh file and
+/// return what it wrote.
+fn capture(render: impl FnOnce()) -> String {
+    static LOCK: Mutex<()> = Mutex::new(());
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let target = env::temp_dir().join(format!(
+        "caraspace-embed-{}-{}.html",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let _guard = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    env::set_var("SPYTIAL_NO_OPEN", "1");
+    env::set_var("SPYTIAL_OUTPUT_PATH", &target);
+    render();
+    env::remove_var("SPYTIAL_OUTPUT_PATH");
+
+    let html = fs::read_to_string(&target).expect("diagram should write HTML");
+    let _ = fs::remove_file(&target);
+    html
+}
+
+/// Text content of the `<script>` element with the given id.
+fn script_block<'a>(html: &'a str, id: &str) -> &'a str {
+    let open = format!(r#"<script type="application/json" id="{id}">"#);
+    let start = html.find(&open).unwrap_or_else(|| panic!("no #{id}")) + open.len();
+    let len = html[start..].find("</script>").unwrap();
+    &html[start..start + len]
+}
+
+/// Everything the page runs as script, outside the vendored bundles.
+fn inline_script(html: &str) -> &str {
+    let start = html.rfind("<script>").unwrap();
+    &html[start..]
+}
+
+#[derive(Serialize)]
+struct Payload {
+    texts: Vec<String>,
+}
+
+#[test]
+fn adversarial_field_values_round_trip() {
+    let payload = Payload {
+        texts: NASTY.iter().map(|s| s.to_string()).collect(),
+    };
+    let html = capture(|| diagram_with_spec(&payload, ""));
+
+    let block = script_block(&html, "spytial-data");
+    for bad in ["<", ">", "\u{2028}", "\u{2029}"] {
+        assert!(!block.contains(bad), "{bad:?} left unescaped in data");
+    }
+    let data: Value = serde_json::from_str(block).expect("data block is valid JSON");
+    let labels: Vec<&str> = data["atoms"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|a| a["type"] == "string")
+        .map(|a| a["label"].as_str().unwrap())
+        .collect();
+    for nasty in NASTY {
+        assert!(labels.contains(nasty), "{nasty:?} did not survive");
+    }
+
+    // The page still ends the way the template does, and no placeholder
+    // was filled twice.
+    assert_eq!(html.matches("</script>").count(), 7);
+    assert!(html.trim_end().ends_with("</html>"));
+    assert!(!inline_script(&html).contains("{{"));
+}
+
+#[test]
+fn adversarial_specs_round_trip() {
+    let spec = "directives:\n  - atomColor:\n      selector: \"`${x}` </script><script>\"\n      value: red\n";
+    let html = capture(|| diagram_with_spec(&Payload { texts: vec![] }, spec));
+
+    let block = script_block(&html, "spytial-spec");
+    assert!(!block.contains('<'));
+    let decoded: String = serde_json::from_str(block).expect("spec block is a JSON string");
+    assert_eq!(decoded, spec);
+}
+
+#[test]
+fn derived_specs_are_embedded() {
+    #[derive(Serialize, caraspace::SpytialDecorators)]
+    #[atom_color(selector = "Node", value = "red")]
+    struct Node {
+        name: String,
+    }
+    let html = capture(|| {
+        diagram(&Node {
+            name: "</script>".into(),
+        })
+    });
+
+    let spec: String = serde_json::from_str(script_block(&html, "spytial-spec")).unwrap();
+    assert!(spec.contains("atomColor"));
+    let data: Value = serde_json::from_str(script_block(&html, "spytial-data")).unwrap();
+    assert!(data["atoms"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|a| a["label"] == "</script>"));
+}
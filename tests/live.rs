@@ -0,0 +1,114 @@
+//! Tests for the live diagram server (`caraspace::live`).
+//!
+//! Once a server is running every diagram in the process goes to it, so
+//! this file has its own test binary and a single server on an OS-picked
+//! port.
+
+use caraspace::{diagram_with_spec, live};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+fn server() -> SocketAddr {
+    static ADDR: OnceLock<SocketAddr> = OnceLock::new();
+    *ADDR.get_or_init(|| live::serve("127.0.0.1:0").expect("bind a free port"))
+}
+
+/// Tests publish into one shared stream; keep them from interleaving.
+fn lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn request(path: &str) -> TcpStream {
+    let mut stream = TcpStream::connect(server()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    stream
+}
+
+/// Read from `stream` until `needle` shows up; panics after five seconds.
+fn read_until(stream: &mut TcpStream, needle: &str) -> String {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut seen = Vec::new();
+    let mut buf = [0_u8; 8192];
+    let mut from = 0;
+    while !seen[from..]
+        .windows(needle.len())
+        .any(|w| w == needle.as_bytes())
+    {
+        assert!(Instant::now() < deadline, "never saw {needle:?}");
+        let n = stream.read(&mut buf).expect("read from live server");
+        assert!(n > 0, "connection closed before {needle:?}");
+        from = seen.len().saturating_sub(needle.len());
+        seen.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8_lossy(&seen).into_owned()
+}
+
+#[derive(Serialize)]
+struct Counter {
+    label: String,
+}
+
+#[test]
+fn diagrams_stream_to_open_event_connections() {
+    let _guard = lock();
+    let mut events = request("/events");
+    read_until(&mut events, "text/event-stream");
+
+    for i in 0..3 {
+        diagram_with_spec(
+            &Counter {
+                label: format!("live-marker-{i}"),
+            },
+            "",
+        );
+    }
+    let stream = read_until(&mut events, "live-marker-2");
+    let first = stream.find("live-marker-0").expect("every update is sent");
+    assert!(first < stream.find("live-marker-1").unwrap());
+    for line in stream.lines().filter(|l| l.starts_with("data: ")) {
+        let payload: serde_json::Value = serde_json::from_str(&line["data: ".len()..]).unwrap();
+        assert!(payload["version"].is_u64());
+        assert!(payload["data"]["atoms"].is_array());
+        assert_eq!(payload["spec"], "");
+    }
+}
+
+#[test]
+fn page_shows_the_latest_diagram_and_subscribes() {
+    let _guard = lock();
+    diagram_with_spec(
+        &Counter {
+            label: "latest-</script>-marker".into(),
+        },
+        "",
+    );
+
+    let mut page = request("/");
+    let mut html = String::new();
+    page.read_to_string(&mut html).unwrap();
+    assert!(html.starts_with("HTTP/1.1 200 OK"));
+    assert!(html.contains(r"latest-\u003c/script\u003e-marker"));
+    assert!(html.contains(
+        r#"<script type="application/json" id="spytial-live">{"events":"/events","version":"#
+    ));
+
+    // A reconnecting tab is sent the latest diagram straight away.
+    let mut events = request("/events");
+    read_until(&mut events, r"latest-</script>-marker");
+}
+
+#[test]
+fn serve_is_idempotent() {
+    assert_eq!(live::serve("127.0.0.1:0").unwrap(), server());
+    assert_eq!(live::address(), Some(server()));
+
+    let mut health = request("/health");
+    read_until(&mut health, "ok");
+}
@@ -0,0 +1,78 @@
+//! Reading instances back: `JsonDataInstance::from_json_str` / `from_reader`
+//! and `jsondata::from_html` / `load_html` on pages caraspace wrote, feeding
+//! `from_datum`.
+
+use caraspace::jsondata::{from_html, load_html, JsonDataInstance};
+use caraspace::spytial_annotations::{to_yaml, HasSpytialDecorators};
+use caraspace::{diagram, export_json_instance, from_datum, SpytialDecorators};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SpytialDecorators)]
+#[attribute(field = "name")]
+struct Inventory {
+    name: String,
+    counts: BTreeMap<String, u32>,
+    tags: Vec<Option<String>>,
+}
+
+fn sample() -> Inventory {
+    Inventory {
+        name: "`${warehouse}` </script>".into(),
+        counts: [("bolts".to_string(), 3), ("nuts".to_string(), 0)].into(),
+        tags: vec![Some("a".into()), None],
+    }
+}
+
+#[test]
+fn json_round_trips_through_from_json_str_and_from_reader() {
+    let value = sample();
+    let json = serde_json::to_string_pretty(&export_json_instance(&value)).unwrap();
+
+    let parsed = JsonDataInstance::from_json_str(&json).unwrap();
+    assert_eq!(serde_json::to_string_pretty(&parsed).unwrap(), json);
+    assert_eq!(from_datum::<Inventory>(&parsed).unwrap(), value);
+
+    let read = JsonDataInstance::from_reader(json.as_bytes()).unwrap();
+    assert_eq!(from_datum::<Inventory>(&read).unwrap(), value);
+}
+
+#[test]
+fn saved_pages_give_back_the_value_and_spec() {
+    let value = sample();
+    let path = env::temp_dir().join(format!("caraspace-load-{}.html", std::process::id()));
+    env::set_var("SPYTIAL_NO_OPEN", "1");
+    env::set_var("SPYTIAL_OUTPUT_PATH", &path);
+    diagram(&value);
+    env::remove_var("SPYTIAL_OUTPUT_PATH");
+
+    let saved = load_html(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(from_datum::<Inventory>(&saved.instance).unwrap(), value);
+    assert_eq!(saved.spec, to_yaml(&Inventory::decorators()).unwrap());
+}
+
+#[test]
+fn pages_from_the_template_literal_era_still_load() {
+    let json = serde_json::to_string_pretty(&export_json_instance(&vec![1u8, 2])).unwrap();
+    let html = format!(
+        "<script>\n        const spytialSpec = `directives: []\n`;\n        \n        const jsonData = `{json}`;\n</script>"
+    );
+    let saved = from_html(&html).unwrap();
+    assert_eq!(saved.spec, "directives: []\n");
+    assert_eq!(from_datum::<Vec<u8>>(&saved.instance).unwrap(), [1, 2]);
+}
+
+#[test]
+fn errors_say_what_went_wrong() {
+    let err = JsonDataInstance::from_json_str("{\"atoms\": 3}").unwrap_err();
+    assert!(err.message().starts_with("invalid instance JSON"), "{err}");
+
+    let err = from_html("<html>nothing here</html>").unwrap_err();
+    assert_eq!(err.message(), "no embedded caraspace diagram found");
+
+    let err = load_html("/definitely/not/here.html").unwrap_err();
+    assert!(err.to_string().starts_with("load error: could not read"));
+}
@@ -0,0 +1,127 @@
+//! Tests for the Markdown-friendly text exporters
+//! (`caraspace::export::{to_mermaid, to_plantuml}`).
+
+use caraspace::export::{export_json_instance, to_mermaid, to_plantuml};
+use caraspace::jsondata::JsonDataInstance;
+use caraspace::spytial_annotations::HasSpytialDecorators;
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "key")]
+#[atom_color(selector = "{x : Node | @:(x.key) = 1}", value = "black")]
+#[edge_style(field = "next", value = "#ff0000", style = "dashed")]
+#[hide_atom(selector = "None")]
+#[group(selector = "Node", name = "list")]
+struct Node {
+    key: u32,
+    note: String,
+    next: Option<Box<Node>>,
+}
+
+fn list() -> Node {
+    Node {
+        key: 1,
+        note: "a \"quoted\" | piped <b>".into(),
+        next: Some(Box::new(Node {
+            key: 2,
+            note: "#2".into(),
+            next: None,
+        })),
+    }
+}
+
+#[test]
+fn mermaid_flowchart() {
+    let chart = to_mermaid(&export_json_instance(&list()), &Node::decorators());
+
+    assert!(chart.starts_with("flowchart TD\n"));
+    assert!(chart.contains(r#"n0["Node<br/>key: 1"]"#));
+    // Labels can't break out of their quotes or edge pipes.
+    assert!(chart.contains("a #34;quoted#34; #124; piped #60;b#62;"));
+    assert!(chart.contains(r##"["#35;2"]"##));
+    assert!(chart.contains("subgraph group0[\"list\"]"));
+    assert!(chart.contains("-.->|next|"));
+    assert!(chart.contains("linkStyle "));
+    assert!(chart.contains("stroke:#ff0000"));
+    assert!(chart.contains("style n0 fill:black,color:#fff"));
+    assert!(!chart.contains("None"));
+}
+
+#[test]
+fn plantuml_object_diagram() {
+    let uml = to_plantuml(&export_json_instance(&list()), &Node::decorators());
+
+    assert!(uml.starts_with("@startuml\n"));
+    assert!(uml.ends_with("@enduml\n"));
+    assert!(uml.contains("package \"list\" {\n  object \"Node\" as n0 #black {\n    key = 1\n"));
+    assert!(uml.contains("-[#ff0000,dashed]->"));
+    assert!(uml.contains(" : next\n"));
+    assert!(uml.contains(r#"object "a &#34;quoted&#34; | piped <b>" as "#));
+    assert!(!uml.contains("None"));
+}
+
+#[test]
+fn multi_line_labels_stay_on_one_line() {
+    let inst = export_json_instance(&vec!["line one\nline two".to_string()]);
+    let chart = to_mermaid(&inst, &Default::default());
+    let uml = to_plantuml(&inst, &Default::default());
+    assert!(chart.contains("line one<br/>line two"));
+    assert!(uml.contains(r"line one\nline two"));
+}
+
+#[test]
+fn ids_that_are_not_identifiers_stay_out_of_the_source() {
+    // A hand-written instance, as loaded with `from_json_str`.
+    let inst = JsonDataInstance::from_json_str(
+        r#"{
+            "atoms": [
+                {"id": "end", "type": "Step", "label": "first"},
+                {"id": "a b; c-->d", "type": "Step", "label": "second"}
+            ],
+            "relations": [{
+                "id": "next", "name": "next", "types": ["Step", "Step"],
+                "tuples": [{"atoms": ["end", "a b; c-->d"], "types": ["Step", "Step"]}]
+            }]
+        }"#,
+    )
+    .unwrap();
+
+    let chart = to_mermaid(&inst, &Default::default());
+    assert!(chart.contains(r#"n0["first"]"#), "{chart}");
+    assert!(chart.contains(r#"n1["second"]"#), "{chart}");
+    assert!(chart.contains("n0 -->|next| n1"), "{chart}");
+    assert!(
+        !chart.contains("end[") && !chart.contains("c-->d"),
+        "{chart}"
+    );
+
+    let uml = to_plantuml(&inst, &Default::default());
+    assert!(uml.contains("object \"first\" as n0\n"), "{uml}");
+    assert!(uml.contains("n0 --> n1 : next\n"), "{uml}");
+    assert!(!uml.contains("a b;"), "{uml}");
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Pin", value = "red;\nclick n0 call alert()")]
+#[edge_style(field = "next", value = "rgb(1,2,3)")]
+struct Pin {
+    next: Option<Box<Pin>>,
+}
+
+#[test]
+fn colours_cannot_break_style_lines() {
+    let pins = Pin {
+        next: Some(Box::new(Pin { next: None })),
+    };
+    let instance = export_json_instance(&pins);
+
+    let chart = to_mermaid(&instance, &Pin::decorators());
+    assert!(!chart.contains("click"), "{chart}");
+    assert!(!chart.contains("style n"), "{chart}");
+    assert!(!chart.contains("linkStyle"), "{chart}");
+
+    let uml = to_plantuml(&instance, &Pin::decorators());
+    assert!(!uml.contains("click"), "{uml}");
+    assert!(!uml.contains("rgb"), "{uml}");
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+use common::{atom_colors, colors};
+
+mod model {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    #[atom_color(selector = "Employee", value = "teal")]
+    pub struct Employee {
+        pub name: String,
+    }
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[atom_color(selector = "Failure", value = "red")]
+    pub struct Failure {
+        pub reason: String,
+    }
+
+    #[derive(Serialize, SpytialDecorators, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    #[atom_color(selector = "Id", value = "gray")]
+    pub struct Id(pub u32);
+}
+
+use model::{Employee, Failure, Id};
+
+#[derive(Serialize, SpytialDecorators)]
+struct Maps {
+    by_id: HashMap<Id, Employee>,
+    sorted: BTreeMap<String, Vec<Employee>>,
+}
+
+#[test]
+fn map_keys_and_values_are_walked() {
+    assert_eq!(
+        atom_colors::<Maps>(),
+        colors(&[("Id", "gray"), ("Employee", "teal")])
+    );
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Sets {
+    members: HashSet<Employee>,
+    ordered: BTreeSet<Id>,
+}
+
+#[test]
+fn set_elements_are_walked() {
+    assert_eq!(
+        atom_colors::<Sets>(),
+        colors(&[("Employee", "teal"), ("Id", "gray")])
+    );
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Outcome {
+    last: Result<Employee, Failure>,
+}
+
+#[test]
+fn both_sides_of_a_result_are_walked() {
+    assert_eq!(
+        atom_colors::<Outcome>(),
+        colors(&[("Employee", "teal"), ("Failure", "red")])
+    );
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Shapes<'a> {
+    pair: (u32, Option<Id>),
+    team: [Employee; 2],
+    borrowed: &'a [Failure],
+}
+
+#[test]
+fn tuples_arrays_slices_and_references_are_walked() {
+    assert_eq!(
+        atom_colors::<Shapes>(),
+        colors(&[("Id", "gray"), ("Employee", "teal"), ("Failure", "red")])
+    );
+}
+
+/// A container the macro cannot see into.
+struct Pool {
+    items: Vec<Employee>,
+}
+
+impl Serialize for Pool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+type Staff = Vec<Failure>;
+
+#[derive(Serialize, SpytialDecorators)]
+#[spytial(include = "model::Id")]
+struct Department {
+    #[spytial(include = "model::Employee")]
+    pool: Pool,
+    #[spytial(include = "Failure")]
+    staff: Staff,
+    #[spytial(include = "Employee")]
+    lead: Employee,
+}
+
+#[test]
+fn include_probes_types_behind_aliases_and_custom_containers() {
+    assert_eq!(
+        atom_colors::<Department>(),
+        colors(&[("Employee", "teal"), ("Failure", "red"), ("Id", "gray")])
+    );
+
+    let department = Department {
+        pool: Pool { items: Vec::new() },
+        staff: Vec::new(),
+        lead: Employee {
+            name: "ada".to_string(),
+        },
+    };
+    assert!(caraspace::export::export_json_instance(&department)
+        .atoms
+        .iter()
+        .any(|a| a.r#type == "Employee"));
+}
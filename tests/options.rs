@@ -0,0 +1,133 @@
+//! `DiagramOptions`, `diagram_with` and `set_default_options`: per-call
+//! settings that never touch the environment, so these tests run in
+//! parallel without a lock.
+
+mod common;
+
+use caraspace::jsondata::load_html;
+use caraspace::{diagram, diagram_with, set_default_options, OutputFormat, SpytialDecorators};
+use common::{quiet, scratch_dir};
+use serde::Serialize;
+use std::fs;
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "name")]
+struct Team {
+    name: String,
+    lead: Person,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Person {
+    name: String,
+    manager: Option<Box<Person>>,
+}
+
+fn sample() -> Team {
+    Team {
+        name: "core".into(),
+        lead: Person {
+            name: "Ada".into(),
+            manager: Some(Box::new(Person {
+                name: "Grace".into(),
+                manager: None,
+            })),
+        },
+    }
+}
+
+#[test]
+fn output_path_and_title_are_used() {
+    let path = scratch_dir("path").join("team.html");
+    diagram_with(&sample(), &quiet().output_path(&path).title("Team <core>"));
+
+    let html = fs::read_to_string(&path).unwrap();
+    assert!(html.contains("<title>Team &lt;core&gt;</title>"));
+    let saved = load_html(&path).unwrap();
+    assert!(saved.instance.atoms.iter().any(|a| a.label == "Grace"));
+    assert!(
+        saved.spec.contains("name"),
+        "derived spec kept: {}",
+        saved.spec
+    );
+}
+
+#[test]
+fn output_dir_and_file_name_pattern_name_the_file() {
+    let dir = scratch_dir("pattern");
+    let options = quiet().output_dir(&dir).file_name("team-{n}.{ext}");
+    diagram_with(&sample(), &options);
+    diagram_with(&sample(), &options.clone().format(OutputFormat::Svg));
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 2, "{names:?}");
+    assert!(names[0].starts_with("team-") && names[0].ends_with(".html"));
+    assert!(names[1].starts_with("team-") && names[1].ends_with(".svg"));
+}
+
+#[test]
+fn svg_format_writes_an_image_with_its_title() {
+    let path = scratch_dir("svg").join("team.svg");
+    diagram_with(
+        &sample(),
+        &quiet()
+            .output_path(&path)
+            .format(OutputFormat::Svg)
+            .title("Core team"),
+    );
+
+    let image = fs::read_to_string(&path).unwrap();
+    assert!(image.contains("<svg"));
+    assert!(image.contains("<title>Core team</title>"));
+    assert!(image.contains("Ada"));
+}
+
+#[test]
+fn extra_spec_is_merged_into_the_derived_one() {
+    let path = scratch_dir("spec").join("team.html");
+    let extra = "directives:\n  - flag: hideDisconnected\n";
+    diagram_with(
+        &sample(),
+        &quiet()
+            .output_path(&path)
+            .extra_spec(extra)
+            .extra_spec(extra),
+    );
+
+    let spec = load_html(&path).unwrap().spec;
+    assert!(spec.contains("hideDisconnected"), "{spec}");
+    assert_eq!(spec.matches("hideDisconnected").count(), 1, "{spec}");
+    assert!(spec.contains("attribute"), "derived spec kept: {spec}");
+}
+
+#[test]
+fn max_depth_drops_atoms_past_the_limit() {
+    let path = scratch_dir("depth").join("team.html");
+    diagram_with(&sample(), &quiet().output_path(&path).max_depth(2));
+
+    let instance = load_html(&path).unwrap().instance;
+    let labels: Vec<&str> = instance.atoms.iter().map(|a| a.label.as_str()).collect();
+    assert!(labels.contains(&"core"), "{labels:?}");
+    assert!(labels.contains(&"Ada"), "{labels:?}");
+    assert!(!labels.contains(&"Grace"), "{labels:?}");
+    let ids: Vec<&str> = instance.atoms.iter().map(|a| a.id.as_str()).collect();
+    for tuple in instance.relations.iter().flat_map(|r| &r.tuples) {
+        assert!(tuple.atoms.iter().all(|id| ids.contains(&id.as_str())));
+    }
+}
+
+#[test]
+fn set_default_options_applies_to_diagram() {
+    // The only test here that changes the process-wide defaults; the others
+    // pass explicit options and are unaffected.
+    let path = scratch_dir("defaults").join("team.html");
+    set_default_options(quiet().output_path(&path).title("Defaults"));
+
+    diagram(&sample());
+    let html = fs::read_to_string(&path).unwrap();
+    assert!(html.contains("<title>Defaults</title>"));
+}
@@ -0,0 +1,132 @@
+//! `render` and `render_with`: the rendered page, spec, instance and path
+//! returned as values, and each kind of `DiagramError`.
+
+mod common;
+
+use caraspace::jsondata::from_html;
+use caraspace::sink::MemorySink;
+use caraspace::{render_with, DiagramError, DiagramOptions, OutputFormat, SpytialDecorators};
+use common::{quiet, scratch_dir};
+use serde::{Serialize, Serializer};
+use std::fs;
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "name")]
+struct Crew {
+    name: String,
+    badge: u32,
+}
+
+fn sample() -> Crew {
+    Crew {
+        name: "Ada".into(),
+        badge: 7,
+    }
+}
+
+#[test]
+fn render_returns_what_it_wrote() {
+    let path = scratch_dir("html").join("crew.html");
+    let rendered = render_with(&sample(), &quiet().output_path(&path)).unwrap();
+
+    assert_eq!(rendered.path.as_deref(), Some(path.as_path()));
+    let html = rendered.html.unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), html);
+    assert!(rendered.spec.contains("attribute"), "{}", rendered.spec);
+    assert!(rendered.instance.atoms.iter().any(|a| a.label == "Ada"));
+
+    let saved = from_html(&html).unwrap();
+    assert_eq!(saved.spec, rendered.spec);
+    assert_eq!(saved.instance.atoms.len(), rendered.instance.atoms.len());
+}
+
+#[test]
+fn svg_path_is_the_image_and_no_page_is_built() {
+    let dir = scratch_dir("svg");
+    let rendered = render_with(
+        &sample(),
+        &quiet().output_dir(&dir).format(OutputFormat::Svg),
+    )
+    .unwrap();
+
+    let path = rendered.path.unwrap();
+    assert_eq!(path.extension().unwrap(), "svg");
+    assert!(fs::read_to_string(path).unwrap().contains("<svg"));
+    assert_eq!(rendered.html, None);
+}
+
+#[test]
+fn a_sink_means_no_path() {
+    let captured = MemorySink::new();
+    let rendered = render_with(
+        &sample(),
+        &DiagramOptions::new()
+            .sink(captured.clone())
+            .redact_field("name"),
+    )
+    .unwrap();
+
+    assert_eq!(rendered.path, None);
+    assert_eq!(Some(captured.last().unwrap().content), rendered.html);
+    assert!(rendered.instance.atoms.iter().all(|a| a.label != "Ada"));
+}
+
+#[derive(SpytialDecorators)]
+struct Unserializable;
+
+impl Serialize for Unserializable {
+    fn serialize<S: Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("no wire form"))
+    }
+}
+
+#[test]
+fn serialization_failures_are_errors() {
+    let dir = scratch_dir("serialize");
+    let err = render_with(&Unserializable, &quiet().output_dir(&dir)).unwrap_err();
+
+    assert!(matches!(err, DiagramError::Serialization(_)), "{err:?}");
+    assert!(err.to_string().contains("no wire form"), "{err}");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0, "nothing written");
+}
+
+#[test]
+fn write_failures_are_io_errors() {
+    let path = scratch_dir("io").join("missing").join("crew.html");
+    let err = render_with(&sample(), &quiet().output_path(&path)).unwrap_err();
+
+    assert!(matches!(err, DiagramError::Io(_)), "{err:?}");
+    assert!(err.to_string().contains("crew.html"), "{err}");
+}
+
+#[test]
+fn sink_failures_are_io_errors() {
+    let options = DiagramOptions::new()
+        .sink(|_: &caraspace::sink::Artifact<'_>| Err(std::io::Error::other("share is read-only")));
+    let err = render_with(&sample(), &options).unwrap_err();
+
+    assert!(matches!(err, DiagramError::Io(_)), "{err:?}");
+    assert!(err.to_string().contains("share is read-only"), "{err}");
+}
+
+#[test]
+fn browser_failures_are_launch_errors_after_the_write() {
+    let path = scratch_dir("launch").join("crew.html");
+    let options = DiagramOptions::new()
+        .output_path(&path)
+        .open(true)
+        .browser("caraspace-no-such-browser {}");
+    let err = render_with(&sample(), &options).unwrap_err();
+
+    match &err {
+        DiagramError::Launch { path: written, .. } => {
+            assert_eq!(written.as_deref(), Some(path.as_path()))
+        }
+        other => panic!("{other:?}"),
+    }
+    assert!(
+        err.to_string().contains("caraspace-no-such-browser"),
+        "{err}"
+    );
+    assert!(path.is_file(), "the diagram is still written");
+}
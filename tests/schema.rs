@@ -0,0 +1,231 @@
+//! The instance wire format: version marker, published JSON Schema and
+//! `jsondata::validate`.
+
+use caraspace::jsondata::{
+    json_schema, validate, IAtom, IRelation, ITuple, JsonDataInstance, ValidationIssue,
+    FORMAT_VERSION,
+};
+use caraspace::{diff, export_debug_instance, export_json_instance, SpytialDecorators};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::{env, fs};
+
+const PUBLISHED: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/docs/src/internals/instance.schema.json"
+);
+
+#[derive(Clone, Serialize, SpytialDecorators)]
+enum Shape {
+    Circle { r: f64 },
+    Pair(u8, char),
+    Empty,
+}
+
+#[derive(Clone, Serialize, SpytialDecorators)]
+struct Scene {
+    shapes: Vec<Shape>,
+    names: BTreeMap<String, (u8, Option<bool>)>,
+}
+
+fn scene() -> Scene {
+    Scene {
+        shapes: vec![Shape::Circle { r: 1.5 }, Shape::Pair(2, 'x'), Shape::Empty],
+        names: [
+            ("a".to_string(), (1, None)),
+            ("b".to_string(), (2, Some(true))),
+        ]
+        .into(),
+    }
+}
+
+fn atom(id: &str) -> IAtom {
+    IAtom {
+        id: id.into(),
+        r#type: "T".into(),
+        label: id.into(),
+    }
+}
+
+fn relation(name: &str, types: &[&str], tuples: &[&[&str]]) -> IRelation {
+    IRelation {
+        id: name.into(),
+        name: name.into(),
+        types: types.iter().map(|t| t.to_string()).collect(),
+        tuples: tuples
+            .iter()
+            .map(|atoms| ITuple {
+                atoms: atoms.iter().map(|a| a.to_string()).collect(),
+                types: atoms.iter().map(|_| "T".to_string()).collect(),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn published_schema_is_current() {
+    let generated = serde_json::to_string_pretty(&json_schema()).unwrap() + "\n";
+    if env::var_os("CARASPACE_BLESS").is_some() {
+        fs::write(PUBLISHED, &generated).unwrap();
+    }
+    let published = fs::read_to_string(PUBLISHED).unwrap();
+    assert!(
+        published == generated,
+        "{PUBLISHED} is stale; rerun with CARASPACE_BLESS=1 to regenerate it"
+    );
+}
+
+#[test]
+fn schema_properties_match_the_serialized_types() {
+    fn keys(value: &Value) -> BTreeSet<&str> {
+        value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect()
+    }
+
+    let schema = json_schema();
+    let instance = serde_json::to_value(export_json_instance(&scene())).unwrap();
+    assert_eq!(keys(&instance), keys(&schema["properties"]));
+    assert_eq!(
+        keys(&instance["atoms"][0]),
+        keys(&schema["$defs"]["atom"]["properties"])
+    );
+    let relation = &instance["relations"][0];
+    assert_eq!(
+        keys(relation),
+        keys(&schema["$defs"]["relation"]["properties"])
+    );
+    assert_eq!(
+        keys(&relation["tuples"][0]),
+        keys(&schema["$defs"]["tuple"]["properties"])
+    );
+}
+
+#[test]
+fn instances_carry_the_format_version() {
+    let instance = export_json_instance(&scene());
+    assert_eq!(instance.version, FORMAT_VERSION);
+    let json = serde_json::to_value(&instance).unwrap();
+    assert_eq!(json["version"], FORMAT_VERSION);
+
+    // JSON from before the marker existed is version 1.
+    let legacy = JsonDataInstance::from_json_str(r#"{"atoms":[],"relations":[]}"#).unwrap();
+    assert_eq!(legacy.version, 1);
+
+    let newer = format!(
+        r#"{{"atoms":[],"relations":[],"version":{}}}"#,
+        FORMAT_VERSION + 1
+    );
+    let err = JsonDataInstance::from_json_str(&newer).unwrap_err();
+    assert!(
+        err.message().contains("newer than this caraspace supports"),
+        "{err}"
+    );
+}
+
+#[test]
+fn caraspace_output_validates() {
+    let value = scene();
+    assert_eq!(validate(&export_json_instance(&value)), []);
+    assert_eq!(validate(&export_debug_instance(&vec![(1, "a")])), []);
+    assert_eq!(validate(&JsonDataInstance::default()), []);
+
+    let mut after = value.clone();
+    after.shapes.pop();
+    after.names.insert("c".into(), (3, None));
+    assert_eq!(validate(&diff(&value, &after).instance), []);
+}
+
+#[test]
+fn validate_reports_each_problem() {
+    let mut instance = JsonDataInstance::new(
+        vec![atom("a"), atom("b"), atom("a")],
+        vec![
+            relation("next", &["T", "T"], &[&["a", "b"], &["b", "ghost"]]),
+            relation("next", &["T", "T"], &[&["a"]]),
+        ],
+    );
+    instance.relations[0].tuples[0].types.pop();
+    instance.version = 0;
+
+    let issues = validate(&instance);
+    assert_eq!(
+        issues,
+        [
+            ValidationIssue::UnsupportedVersion { found: 0 },
+            ValidationIssue::DuplicateAtomId { id: "a".into() },
+            ValidationIssue::TupleTypesMismatch {
+                relation: "next".into(),
+                tuple: 0,
+                atoms: 2,
+                types: 1,
+            },
+            ValidationIssue::DanglingAtom {
+                relation: "next".into(),
+                tuple: 1,
+                position: 1,
+                id: "ghost".into(),
+            },
+            ValidationIssue::DuplicateRelationId { id: "next".into() },
+            ValidationIssue::ArityMismatch {
+                relation: "next".into(),
+                tuple: 0,
+                expected: 2,
+                found: 1,
+            },
+        ]
+    );
+    assert_eq!(
+        issues[3].to_string(),
+        "next[1] position 1 names unknown atom `ghost`"
+    );
+}
+
+#[test]
+fn index_positions_hold_raw_values() {
+    let mut idx = relation("idx", &["sequence", "index", "T"], &[&["s", "0", "a"]]);
+    idx.tuples[0].types[1] = "index".into();
+    let instance = JsonDataInstance::new(vec![atom("s"), atom("a")], vec![idx]);
+    assert_eq!(validate(&instance), []);
+}
+
+#[test]
+fn empty_tuples_and_nullary_relations_are_reported() {
+    let instance = JsonDataInstance::new(
+        vec![atom("a")],
+        vec![
+            relation("e", &[], &[&[]]),
+            relation("t", &["T"], &[&["a"], &[]]),
+        ],
+    );
+    assert_eq!(
+        validate(&instance),
+        [
+            ValidationIssue::NullaryRelation {
+                relation: "e".into()
+            },
+            ValidationIssue::EmptyTuple {
+                relation: "e".into(),
+                tuple: 0,
+            },
+            ValidationIssue::EmptyTuple {
+                relation: "t".into(),
+                tuple: 1,
+            },
+            ValidationIssue::ArityMismatch {
+                relation: "t".into(),
+                tuple: 1,
+                expected: 1,
+                found: 0,
+            },
+        ]
+    );
+    assert_eq!(
+        validate(&instance)[0].to_string(),
+        "e has no types: relations need arity 1 or more"
+    );
+}
@@ -0,0 +1,213 @@
+//! Tests for `caraspace::selector`: parsing, evaluation, error positions
+//! and `check`.
+
+use caraspace::jsondata::{IRelation, ITuple, JsonDataInstance};
+use caraspace::selector::{self, check, Selector, SelectorIssue};
+use caraspace::spytial_annotations::{HasSpytialDecorators, SpytialDecoratorsBuilder};
+use caraspace::{export_json_instance, SpytialDecorators};
+use serde::Serialize;
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "{x : Node | @:(x.key) = 1}", value = "red")]
+#[hide_atom(selector = "Missing")]
+struct Node {
+    key: u32,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+fn leaf(key: u32) -> Node {
+    Node {
+        key,
+        left: None,
+        right: None,
+    }
+}
+
+/// 2 with children 1 and 3.
+fn tree() -> JsonDataInstance {
+    export_json_instance(&Node {
+        key: 2,
+        left: Some(Box::new(leaf(1))),
+        right: Some(Box::new(leaf(3))),
+    })
+}
+
+/// Labels of the atoms a selection's first column names.
+fn labels(instance: &JsonDataInstance, selector: &str) -> Vec<String> {
+    let sel = selector::evaluate(selector, instance).unwrap();
+    let mut out: Vec<String> = sel
+        .atoms()
+        .into_iter()
+        .map(|id| {
+            let atom = instance.atoms.iter().find(|a| a.id == id).unwrap();
+            atom.label.clone()
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+#[test]
+fn types_relations_and_set_operators() {
+    let instance = tree();
+    assert_eq!(selector::evaluate("Node", &instance).unwrap().len(), 3);
+    // Every node has a `left`; the leaves' point at `None`.
+    let left = selector::evaluate("left", &instance).unwrap();
+    assert_eq!((left.arity, left.len()), (2, 3));
+    assert_eq!(selector::evaluate("left.None", &instance).unwrap().len(), 2);
+    assert_eq!(labels(&instance, "u32 - u32"), Vec::<String>::new());
+    assert_eq!(labels(&instance, "Node.key"), ["1", "2", "3"]);
+    assert_eq!(labels(&instance, "Node.(left + right).key"), ["1", "3"]);
+    assert!(selector::evaluate("NoSuchType", &instance)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn comprehensions_labels_and_closures() {
+    let instance = tree();
+    let pairs = selector::evaluate("{x, y : Node | x->y in left + right}", &instance).unwrap();
+    assert_eq!(pairs.arity, 2);
+    assert_eq!(pairs.pairs().len(), 2);
+
+    let big = selector::evaluate("{x : Node | @:(x.key) = 3}", &instance).unwrap();
+    assert_eq!(big.len(), 1);
+    let reachable = selector::evaluate("Node.^(left + right) & Node", &instance).unwrap();
+    assert_eq!(reachable.len(), 2);
+    let roots = selector::evaluate("{x : Node | no (left + right).x}", &instance).unwrap();
+    assert_eq!(roots.atoms().into_iter().collect::<Vec<_>>(), ["atom0"]);
+
+    // `true` / `false` stand alone as formulas; next to `=` they are labels.
+    let all = selector::evaluate("{x : Node | true}", &instance).unwrap();
+    assert_eq!(all.len(), 3);
+    let none = selector::evaluate("{x : Node | false or some x.left}", &instance).unwrap();
+    assert_eq!(none.len(), 3);
+    let flags = export_json_instance(&vec![true, false]);
+    let set = selector::evaluate("{x : bool | @:(x) = true}", &flags).unwrap();
+    assert_eq!(set.len(), 1);
+}
+
+/// A balanced tree holding `lo..hi`.
+fn balanced(lo: u32, hi: u32) -> Option<Box<Node>> {
+    (lo < hi).then(|| {
+        let mid = lo + (hi - lo) / 2;
+        Box::new(Node {
+            key: mid,
+            left: balanced(lo, mid),
+            right: balanced(mid + 1, hi),
+        })
+    })
+}
+
+#[test]
+fn comprehensions_scale_to_hundreds_of_atoms() {
+    let instance = export_json_instance(&balanced(0, 300).unwrap());
+    let started = std::time::Instant::now();
+
+    // `x->y in R` filters R; the other two bodies enumerate every binding.
+    let by_membership = selector::evaluate("{x, y : Node | x->y in left}", &instance).unwrap();
+    let by_join = selector::evaluate("{x, y : Node | x.left = y}", &instance).unwrap();
+    let by_transpose = selector::evaluate("{y, x : Node | y->x in ~left}", &instance).unwrap();
+    assert!(by_membership.len() > 100);
+    assert_eq!(by_membership, by_join);
+    let mut flipped: Vec<(&str, &str)> = by_transpose
+        .pairs()
+        .into_iter()
+        .map(|(y, x)| (x, y))
+        .collect();
+    let mut expected = by_membership.pairs();
+    flipped.sort();
+    expected.sort();
+    assert_eq!(flipped, expected, "columns follow the variable order");
+    let reachable = selector::evaluate("{x, y : Node | x->y in ^(left + right)}", &instance);
+    assert!(reachable.unwrap().len() > 1500);
+
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(10),
+        "took {:?}",
+        started.elapsed()
+    );
+}
+
+#[test]
+fn a_parsed_selector_evaluates_against_many_instances() {
+    let sel = Selector::parse("Node.key").unwrap();
+    assert_eq!(sel.source(), "Node.key");
+    assert_eq!(sel.evaluate(&tree()).unwrap().len(), 3);
+    assert_eq!(
+        sel.evaluate(&export_json_instance(&leaf(7))).unwrap().len(),
+        1
+    );
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    let err = Selector::parse("Node +").unwrap_err();
+    assert_eq!(err.position(), Some(6));
+    assert_eq!(err.message(), "expected a name, found end of selector");
+    assert_eq!(
+        err.to_string(),
+        "expected a name, found end of selector at column 7 in selector `Node +`"
+    );
+
+    let err = Selector::parse("{x : Node | x.key # 3}").unwrap_err();
+    assert_eq!(err.position(), Some(18));
+    assert_eq!(err.message(), "unexpected character `#`");
+
+    let err = Selector::parse("Node key").unwrap_err();
+    assert_eq!(err.position(), Some(5));
+
+    // Evaluation errors point at the offending operator.
+    let err = selector::evaluate("Node + left", &tree()).unwrap_err();
+    assert_eq!(err.position(), Some(5));
+    assert!(err
+        .message()
+        .starts_with("cannot combine arity 1 and arity 2"));
+    let err = selector::evaluate("Node.Node", &tree()).unwrap_err();
+    assert_eq!(err.position(), Some(4));
+    let err = selector::evaluate("{x : left | some x}", &tree()).unwrap_err();
+    assert_eq!(err.position(), Some(5));
+    assert_eq!(err.selector(), "{x : left | some x}");
+}
+
+#[test]
+fn empty_tuples_are_skipped() {
+    let mut instance = tree();
+    instance.relations.push(IRelation {
+        id: "e".into(),
+        name: "e".into(),
+        types: vec![],
+        tuples: vec![ITuple {
+            atoms: vec![],
+            types: vec![],
+        }],
+    });
+    let sel = selector::evaluate("e.(Node->Node->Node)", &instance).unwrap();
+    assert!(sel.is_empty());
+    assert_eq!(selector::evaluate("Node - e", &instance).unwrap().len(), 3);
+}
+
+#[test]
+fn check_reports_invalid_and_empty_selectors() {
+    let instance = tree();
+    assert_eq!(
+        check(&Node::decorators(), &instance),
+        [SelectorIssue::Empty {
+            decorator: "hideAtom",
+            selector: "Missing".into(),
+        }]
+    );
+
+    let decorators = SpytialDecoratorsBuilder::new()
+        .atom_color("Node +", "red")
+        .orientation("left", vec!["below"], false)
+        .orientation("left", vec!["left"], false)
+        .build();
+    let issues = check(&decorators, &instance);
+    assert_eq!(issues.len(), 1, "{issues:?}");
+    assert_eq!(
+        issues[0].to_string(),
+        "atomColor: expected a name, found end of selector at column 7 in selector `Node +`"
+    );
+}
@@ -0,0 +1,283 @@
+//! Tests for opt-in `Rc`/`Arc` sharing (`caraspace::shared`).
+//!
+//! A shared pointer must export as a single atom no matter how many owners
+//! reach it, and `from_datum` must hand every owner the same allocation back.
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use caraspace::jsondata::JsonDataInstance;
+use caraspace::spytial_annotations::HasSpytialDecorators;
+use caraspace::{export_json_instance, from_datum, SharedRef, SpytialDecorators};
+use serde::{Deserialize, Serialize};
+
+fn count_type(instance: &JsonDataInstance, ty: &str) -> usize {
+    instance.atoms.iter().filter(|a| a.r#type == ty).count()
+}
+
+fn targets_of<'a>(instance: &'a JsonDataInstance, rel: &str) -> Vec<&'a str> {
+    instance
+        .relations
+        .iter()
+        .filter(|r| r.name == rel)
+        .flat_map(|r| r.tuples.iter().map(|t| t.atoms[1].as_str()))
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Leaf {
+    v: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Diamond {
+    left: SharedRef<Rc<Leaf>>,
+    right: SharedRef<Rc<Leaf>>,
+}
+
+#[test]
+fn shared_allocation_exports_one_atom() {
+    let leaf = Rc::new(Leaf { v: 7 });
+    let d = Diamond {
+        left: SharedRef(leaf.clone()),
+        right: SharedRef(leaf),
+    };
+    let inst = export_json_instance(&d);
+
+    assert_eq!(count_type(&inst, "Leaf"), 1);
+    assert_eq!(targets_of(&inst, "left"), targets_of(&inst, "right"));
+}
+
+#[test]
+fn distinct_allocations_stay_distinct() {
+    let d = Diamond {
+        left: SharedRef(Rc::new(Leaf { v: 1 })),
+        right: SharedRef(Rc::new(Leaf { v: 1 })),
+    };
+    let inst = export_json_instance(&d);
+    assert_eq!(count_type(&inst, "Leaf"), 2);
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct WithAttr {
+    #[serde(with = "caraspace::shared")]
+    a: Arc<Leaf>,
+    #[serde(with = "caraspace::shared")]
+    b: Arc<Leaf>,
+    others: Vec<SharedRef<Arc<Leaf>>>,
+}
+
+#[test]
+fn serde_with_attribute_and_collections_share() {
+    let leaf = Arc::new(Leaf { v: 3 });
+    let w = WithAttr {
+        a: leaf.clone(),
+        b: leaf.clone(),
+        others: vec![SharedRef(leaf.clone()), SharedRef(leaf)],
+    };
+    let inst = export_json_instance(&w);
+    assert_eq!(count_type(&inst, "Leaf"), 1);
+}
+
+#[test]
+fn reify_rebuilds_sharing() {
+    let leaf = Rc::new(Leaf { v: 9 });
+    let d = Diamond {
+        left: SharedRef(leaf.clone()),
+        right: SharedRef(leaf),
+    };
+    let back: Diamond = from_datum(&export_json_instance(&d)).unwrap();
+    assert_eq!(back.left.v, 9);
+    assert!(Rc::ptr_eq(&back.left, &back.right));
+
+    let split = Diamond {
+        left: SharedRef(Rc::new(Leaf { v: 1 })),
+        right: SharedRef(Rc::new(Leaf { v: 2 })),
+    };
+    let back: Diamond = from_datum(&export_json_instance(&split)).unwrap();
+    assert!(!Rc::ptr_eq(&back.left, &back.right));
+    assert_eq!((back.left.v, back.right.v), (1, 2));
+}
+
+#[derive(Serialize, Deserialize)]
+struct TwoNones {
+    a: SharedRef<Rc<Option<i32>>>,
+    b: SharedRef<Rc<Option<i32>>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TwoFlags {
+    a: SharedRef<Rc<bool>>,
+    b: SharedRef<Rc<bool>>,
+}
+
+#[test]
+fn singleton_pointees_keep_their_own_allocation() {
+    let nones = TwoNones {
+        a: SharedRef(Rc::new(None::<i32>)),
+        b: SharedRef(Rc::new(None::<i32>)),
+    };
+    let back: TwoNones = from_datum(&export_json_instance(&nones)).unwrap();
+    assert!(!Rc::ptr_eq(&back.a, &back.b));
+
+    let flags = TwoFlags {
+        a: SharedRef(Rc::new(true)),
+        b: SharedRef(Rc::new(true)),
+    };
+    let back: TwoFlags = from_datum(&export_json_instance(&flags)).unwrap();
+    assert!(!Rc::ptr_eq(&back.a, &back.b));
+    assert!(**back.a && **back.b);
+
+    let shared = Rc::new(true);
+    let same = TwoFlags {
+        a: SharedRef(shared.clone()),
+        b: SharedRef(shared),
+    };
+    let back: TwoFlags = from_datum(&export_json_instance(&same)).unwrap();
+    assert!(Rc::ptr_eq(&back.a, &back.b));
+}
+
+#[test]
+fn reify_rebuilds_sharing_through_serde_with() {
+    let leaf = Arc::new(Leaf { v: 4 });
+    let w = WithAttr {
+        a: leaf.clone(),
+        b: leaf.clone(),
+        others: vec![SharedRef(leaf)],
+    };
+    let back: WithAttr = from_datum(&export_json_instance(&w)).unwrap();
+    assert!(Arc::ptr_eq(&back.a, &back.b));
+    assert!(Arc::ptr_eq(&back.a, &back.others[0]));
+}
+
+#[test]
+fn other_serializers_see_the_pointee() {
+    let leaf = Rc::new(Leaf { v: 5 });
+    let d = Diamond {
+        left: SharedRef(leaf.clone()),
+        right: SharedRef(leaf),
+    };
+    let json = serde_json::to_string(&d).unwrap();
+    assert_eq!(json, r#"{"left":{"v":5},"right":{"v":5}}"#);
+
+    let back: Diamond = serde_json::from_str(&json).unwrap();
+    assert_eq!(*back.left.0, Leaf { v: 5 });
+}
+
+// ──────────────────────────────────────────────
+// Reference cycles
+// ──────────────────────────────────────────────
+
+use std::cell::RefCell;
+use std::rc::Weak;
+
+#[derive(Serialize, Deserialize, Debug, SpytialDecorators)]
+#[attribute(field = "val")]
+struct ListNode {
+    val: i32,
+    next: Option<SharedRef<Rc<RefCell<ListNode>>>>,
+    prev: SharedRef<Weak<RefCell<ListNode>>>,
+}
+
+fn list_node(val: i32) -> Rc<RefCell<ListNode>> {
+    Rc::new(RefCell::new(ListNode {
+        val,
+        next: None,
+        prev: SharedRef(Weak::new()),
+    }))
+}
+
+/// a <-> b <-> c, with `next` strong and `prev` weak.
+fn doubly_linked() -> Rc<RefCell<ListNode>> {
+    let (a, b, c) = (list_node(1), list_node(2), list_node(3));
+    a.borrow_mut().next = Some(SharedRef(b.clone()));
+    b.borrow_mut().prev = SharedRef(Rc::downgrade(&a));
+    b.borrow_mut().next = Some(SharedRef(c.clone()));
+    c.borrow_mut().prev = SharedRef(Rc::downgrade(&b));
+    a
+}
+
+#[test]
+fn doubly_linked_list_exports_as_cycle() {
+    let head = doubly_linked();
+    let inst = export_json_instance(&SharedRef(head));
+
+    assert_eq!(count_type(&inst, "ListNode"), 3);
+    let nodes: Vec<&str> = inst
+        .atoms
+        .iter()
+        .filter(|a| a.r#type == "ListNode")
+        .map(|a| a.id.as_str())
+        .collect();
+    // Every back-pointer lands on an existing node atom.
+    let prevs = targets_of(&inst, "prev");
+    assert!(prevs.contains(&nodes[0]));
+    assert!(prevs.contains(&nodes[1]));
+    // head.prev (a dangling Weak) and tail.next both point at the `None` singleton.
+    let none_id = inst
+        .atoms
+        .iter()
+        .find(|a| a.r#type == "None")
+        .unwrap()
+        .id
+        .as_str();
+    assert!(prevs.contains(&none_id));
+    assert!(targets_of(&inst, "next").contains(&none_id));
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Ring {
+    label: String,
+    next: Option<SharedRef<Rc<RefCell<Ring>>>>,
+}
+
+#[test]
+fn strong_self_cycle_exports_without_overflow() {
+    let r = Rc::new(RefCell::new(Ring {
+        label: "loop".into(),
+        next: None,
+    }));
+    r.borrow_mut().next = Some(SharedRef(r.clone()));
+
+    let inst = export_json_instance(&SharedRef(r.clone()));
+    assert_eq!(count_type(&inst, "Ring"), 1);
+    let ring_id = &inst.atoms[0].id;
+    assert_eq!(targets_of(&inst, "next"), vec![ring_id.as_str()]);
+
+    // Break the cycle so the test doesn't leak.
+    r.borrow_mut().next = None;
+}
+
+#[test]
+fn derive_sees_through_shared_and_weak_pointers() {
+    // Self-referential through SharedRef/Weak/RefCell: must expand and not
+    // recurse into itself.
+    assert_eq!(ListNode::decorators().directives.len(), 1);
+}
+
+#[test]
+fn reify_reports_cycles_instead_of_overflowing() {
+    let head = doubly_linked();
+    let inst = export_json_instance(&SharedRef(head));
+    let err = from_datum::<SharedRef<Rc<RefCell<ListNode>>>>(&inst).unwrap_err();
+    assert!(err.message().contains("cycle"), "{}", err.message());
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Owner {
+    strong: SharedRef<Rc<Leaf>>,
+    #[serde(with = "caraspace::shared::weak")]
+    weak: Weak<Leaf>,
+}
+
+#[test]
+fn reify_points_weak_at_rebuilt_owner() {
+    let leaf = Rc::new(Leaf { v: 11 });
+    let o = Owner {
+        weak: Rc::downgrade(&leaf),
+        strong: SharedRef(leaf),
+    };
+    let back: Owner = from_datum(&export_json_instance(&o)).unwrap();
+    let upgraded = back.weak.upgrade().expect("weak should point at the owner");
+    assert!(Rc::ptr_eq(&upgraded, &back.strong));
+}
@@ -0,0 +1,350 @@
+//! `DiagramSink`: rendered diagrams handed to custom code or to the
+//! built-in sinks instead of being written and opened, and
+//! `SPYTIAL_BROWSER` choosing the open command.
+
+mod common;
+
+use caraspace::jsondata::from_html;
+use caraspace::sink::{set_default_sink, Artifact, DiagramSink, FileSink, HttpSink, MemorySink};
+use caraspace::{diagram, diagram_with, DiagramOptions, OutputFormat, SpytialDecorators};
+use common::scratch_dir;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, SpytialDecorators)]
+struct Pair {
+    left: u32,
+    right: u32,
+}
+
+#[derive(Default)]
+struct Collect(Mutex<Vec<(OutputFormat, String, Option<String>)>>);
+
+impl DiagramSink for Collect {
+    fn deliver(&self, artifact: &Artifact<'_>) -> io::Result<()> {
+        self.0.lock().unwrap().push((
+            artifact.format,
+            artifact.content.to_string(),
+            artifact.title.map(str::to_string),
+        ));
+        Ok(())
+    }
+}
+
+#[test]
+fn sink_replaces_the_file_and_browser() {
+    let dir = scratch_dir("html");
+    let collect = Arc::new(Collect::default());
+    let options = DiagramOptions::new()
+        .output_dir(&dir)
+        .title("pair")
+        .sink(collect.clone());
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+
+    let delivered = collect.0.lock().unwrap();
+    assert_eq!(delivered.len(), 1);
+    let (format, content, title) = &delivered[0];
+    assert_eq!(*format, OutputFormat::Html);
+    assert!(content.contains("<title>pair</title>"));
+    assert_eq!(title.as_deref(), Some("pair"));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0, "nothing written");
+}
+
+#[test]
+fn closures_are_sinks_for_every_format() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let seen = seen.clone();
+        move |artifact: &Artifact<'_>| {
+            seen.lock()
+                .unwrap()
+                .push((artifact.extension(), artifact.content.to_string()));
+            Ok(())
+        }
+    };
+    let options = DiagramOptions::new().title("pair").sink(sink);
+    for format in [OutputFormat::Svg, OutputFormat::Terminal] {
+        diagram_with(&Pair { left: 1, right: 2 }, &options.clone().format(format));
+    }
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen[0].0, "svg");
+    assert!(seen[0].1.contains("<svg") && seen[0].1.contains("<title>pair</title>"));
+    assert_eq!(seen[1].0, "txt");
+    assert!(seen[1].1.starts_with("pair\n"), "{}", seen[1].1);
+    assert!(seen[1].1.contains("Pair"), "{}", seen[1].1);
+}
+
+#[test]
+fn sink_errors_do_not_panic() {
+    let options =
+        DiagramOptions::new().sink(|_: &Artifact<'_>| Err(io::Error::other("unreachable share")));
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+}
+
+#[cfg(unix)]
+#[test]
+fn spytial_browser_is_the_open_command() {
+    // The only test in this binary that reads the environment.
+    let dir = scratch_dir("browser");
+    let marker = dir.join("opened");
+    let page = dir.join("pair.html");
+    env::set_var(
+        "SPYTIAL_BROWSER",
+        format!("sh -c 'echo \"$0\" > \"{}\"'", marker.display()),
+    );
+    let options = DiagramOptions::from_env().output_path(&page).open(true);
+    env::remove_var("SPYTIAL_BROWSER");
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while fs::read_to_string(&marker).map_or(true, |text| text.is_empty()) {
+        assert!(Instant::now() < deadline, "browser command never ran");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(
+        fs::read_to_string(&marker).unwrap().trim(),
+        page.display().to_string()
+    );
+}
+
+#[test]
+fn memory_sink_keeps_diagrams_for_assertions() {
+    let captured = MemorySink::new();
+    let options = DiagramOptions::new().sink(captured.clone());
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+    diagram_with(
+        &Pair { left: 3, right: 4 },
+        &options.clone().format(OutputFormat::Svg),
+    );
+
+    assert_eq!(captured.len(), 2);
+    let page = &captured.diagrams()[0];
+    assert_eq!(page.format, OutputFormat::Html);
+    let saved = from_html(&page.content).unwrap();
+    assert!(saved.instance.atoms.iter().any(|a| a.label == "2"));
+    assert_eq!(captured.last().unwrap().format, OutputFormat::Svg);
+
+    assert_eq!(captured.take().len(), 2);
+    assert!(captured.is_empty());
+}
+
+#[test]
+fn set_default_sink_routes_diagram() {
+    // The only test here that changes the process-wide defaults.
+    let captured = MemorySink::new();
+    set_default_sink(captured.clone());
+    diagram(&Pair { left: 5, right: 6 });
+    assert_eq!(captured.len(), 1);
+}
+
+#[test]
+fn file_sinks_write_fixed_and_fresh_paths() {
+    let dir = scratch_dir("files");
+    let fixed = dir.join("latest.html");
+    let options = DiagramOptions::new().sink(FileSink::at(&fixed));
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+    diagram_with(&Pair { left: 7, right: 8 }, &options);
+    let saved = from_html(&fs::read_to_string(&fixed).unwrap()).unwrap();
+    assert!(saved.instance.atoms.iter().any(|a| a.label == "8"));
+
+    let fresh = dir.join("fresh");
+    fs::create_dir_all(&fresh).unwrap();
+    let options = DiagramOptions::new().sink(FileSink::in_dir(&fresh));
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+    diagram_with(&Pair { left: 1, right: 2 }, &options);
+    assert_eq!(fs::read_dir(&fresh).unwrap().count(), 2);
+}
+
+/// `viz_server` on a free port, killed on drop.
+struct VizServer {
+    child: Child,
+    port: u16,
+}
+
+impl Drop for VizServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn start_viz_server(file: &PathBuf) -> VizServer {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let child = Command::new(env!("CARGO_BIN_EXE_viz_server"))
+        .arg(file)
+        .env("SPYTIAL_PORT", port.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let server = VizServer { child, port };
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while get(port, "/health").is_err() {
+        assert!(Instant::now() < deadline, "viz_server did not start");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    server
+}
+
+/// Status line and body of `GET path`.
+fn get(port: u16, path: &str) -> io::Result<(String, String)> {
+    let (status, _, body) = get_with(port, path, "")?;
+    Ok((status, body))
+}
+
+/// Status line, headers and body of `GET path`, sent with `extra_headers`.
+fn get_with(port: u16, path: &str, extra_headers: &str) -> io::Result<(String, String, String)> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\n{extra_headers}Connection: close\r\n\r\n"
+    )?;
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status)?;
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest)?;
+    let (headers, body) = rest.split_once("\r\n\r\n").unwrap_or((&rest, ""));
+    Ok((
+        status.trim_end().to_string(),
+        headers.to_string(),
+        body.to_string(),
+    ))
+}
+
+#[test]
+fn http_sink_posts_to_viz_server() {
+    let file = scratch_dir("http").join("served.html");
+    let server = start_viz_server(&file);
+    let (status, _) = get(server.port, "/rust_viz_data.html").unwrap();
+    assert!(status.contains("404"), "{status}");
+
+    let options =
+        DiagramOptions::new().sink(HttpSink::new(format!("http://127.0.0.1:{}/", server.port)));
+    diagram_with(&Pair { left: 9, right: 10 }, &options);
+
+    let (status, page) = get(server.port, "/rust_viz_data.html").unwrap();
+    assert!(status.contains("200"), "{status}");
+    let saved = from_html(&page).unwrap();
+    assert!(saved.instance.atoms.iter().any(|a| a.label == "10"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), page);
+}
+
+#[test]
+fn viz_server_serves_a_posted_image_as_svg() {
+    let file = scratch_dir("http-svg").join("served.html");
+    let server = start_viz_server(&file);
+    let options = DiagramOptions::new()
+        .format(OutputFormat::Svg)
+        .sink(HttpSink::new(format!("127.0.0.1:{}", server.port)));
+    diagram_with(&Pair { left: 9, right: 10 }, &options);
+
+    let (status, headers, body) = get_with(server.port, "/rust_viz_data.html", "").unwrap();
+    assert!(status.contains("200"), "{status}");
+    assert!(headers.contains("Content-Type: image/svg+xml"), "{headers}");
+    assert!(body.starts_with("<svg"), "{body}");
+}
+
+#[test]
+fn viz_server_rejects_oversized_headers() {
+    let file = scratch_dir("http-head").join("served.html");
+    let server = start_viz_server(&file);
+    let cookie = format!("Cookie: {}\r\n", "x".repeat(32 * 1024));
+    let (status, _, _) = get_with(server.port, "/health", &cookie).unwrap();
+    assert!(status.contains("431"), "{status}");
+    let (status, _) = get(server.port, "/health").unwrap();
+    assert!(status.contains("200"), "{status}");
+}
+
+/// Status line of a `POST /diagram` with `headers` and a small page.
+fn post(port: u16, headers: &str) -> String {
+    let body = "<script>alert(1)</script>";
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(
+        stream,
+        "POST /diagram HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut status = String::new();
+    BufReader::new(stream).read_line(&mut status).unwrap();
+    status.trim_end().to_string()
+}
+
+#[test]
+fn viz_server_accepts_diagrams_only_from_sinks() {
+    let file = scratch_dir("http-auth").join("served.html");
+    let server = start_viz_server(&file);
+    let html = "Content-Type: text/html\r\n";
+    let sink = "X-Caraspace-Sink: 1\r\n";
+
+    // What a cross-origin `fetch` with `mode: "no-cors"` can send.
+    let simple = post(
+        server.port,
+        "Content-Type: text/plain\r\nOrigin: http://evil.test\r\n",
+    );
+    assert!(simple.contains("403"), "{simple}");
+    let foreign = post(
+        server.port,
+        &format!("{html}{sink}Origin: http://evil.test\r\n"),
+    );
+    assert!(foreign.contains("403"), "{foreign}");
+    let script = post(
+        server.port,
+        &format!("Content-Type: text/javascript\r\n{sink}"),
+    );
+    assert!(script.contains("415"), "{script}");
+    assert!(!file.exists(), "nothing was written");
+
+    let own = format!("{html}{sink}Origin: http://127.0.0.1:{}\r\n", server.port);
+    assert!(post(server.port, &own).contains("204"));
+    let (_, headers, _) = get_with(server.port, "/rust_viz_data.html", "").unwrap();
+    assert!(headers.contains("Content-Type: text/html"), "{headers}");
+}
+
+#[test]
+fn viz_server_drops_stalled_clients() {
+    let file = scratch_dir("http-stall").join("served.html");
+    let server = start_viz_server(&file);
+    let mut stalled = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    write!(
+        stalled,
+        "POST /diagram HTTP/1.1\r\nContent-Type: text/html\r\nX-Caraspace-Sink: 1\r\nContent-Length: 100\r\n\r\nshort"
+    )
+    .unwrap();
+
+    let started = Instant::now();
+    let (status, _) = get(server.port, "/health").unwrap();
+    assert!(status.contains("200"), "{status}");
+    assert!(started.elapsed() < Duration::from_secs(30));
+    assert!(!file.exists(), "a short body is not written");
+}
+
+#[test]
+fn http_sink_reports_a_missing_server() {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let artifact = Artifact {
+        format: OutputFormat::Html,
+        content: "<html></html>",
+        title: None,
+    };
+    assert!(HttpSink::new(format!("127.0.0.1:{port}"))
+        .deliver(&artifact)
+        .is_err());
+}
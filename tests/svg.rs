@@ -0,0 +1,222 @@
+//! Tests for the native SVG renderer (`caraspace::svg` / `render_svg`).
+//!
+//! Node positions are read back from the `data-id` groups the renderer
+//! emits, so layout assertions stay independent of exact pixel values.
+
+use caraspace::spytial_annotations::SpytialDecoratorsBuilder;
+use caraspace::{export_json_instance, render_svg, svg, SpytialDecorators};
+use serde::Serialize;
+
+/// `(x, y)` of the box for the atom with `id`.
+fn position(svg: &str, id: &str) -> (f64, f64) {
+    let at = svg
+        .find(&format!(r#"data-id="{id}""#))
+        .unwrap_or_else(|| panic!("no atom {id} in:\n{svg}"));
+    let attr = |name: &str| -> f64 {
+        let key = format!(r#" {name}=""#);
+        let start = at + svg[at..].find(&key).unwrap() + key.len();
+        let end = start + svg[start..].find('"').unwrap();
+        svg[start..end].parse().unwrap()
+    };
+    (attr("x"), attr("y"))
+}
+
+/// Atom id of the first node whose bold label line reads `label`.
+fn id_labelled(svg: &str, label: &str) -> String {
+    let text = format!(r#"font-weight="bold">{label}</text>"#);
+    let at = svg.find(&text).unwrap_or_else(|| panic!("no {label} node"));
+    let open = svg[..at].rfind(r#"data-id=""#).unwrap() + r#"data-id=""#.len();
+    svg[open..open + svg[open..].find('"').unwrap()].to_string()
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "key")]
+#[orientation(selector = "{x, y : Node | x->y in left}", directions = ["left", "below"])]
+#[orientation(selector = "{x, y : Node | x->y in right}", directions = ["right", "below"])]
+#[hide_atom(selector = "Color + u32 + None")]
+#[atom_color(selector = "{x : Node | @:(x.color) = Red}", value = "red")]
+struct Node {
+    key: u32,
+    color: Color,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+#[derive(Serialize)]
+enum Color {
+    Red,
+    Black,
+}
+
+fn leaf(key: u32, color: Color) -> Option<Box<Node>> {
+    Some(Box::new(Node {
+        key,
+        color,
+        left: None,
+        right: None,
+    }))
+}
+
+#[test]
+fn binary_tree_honors_orientation_attributes_and_colors() {
+    let tree = Node {
+        key: 5,
+        color: Color::Black,
+        left: leaf(3, Color::Red),
+        right: Some(Box::new(Node {
+            key: 8,
+            color: Color::Black,
+            left: None,
+            right: leaf(9, Color::Red),
+        })),
+    };
+    let image = render_svg(&tree);
+
+    assert!(image.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(image.trim_end().ends_with("</svg>"));
+    for key in ["key: 5", "key: 3", "key: 8", "key: 9"] {
+        assert!(image.contains(key), "missing {key}");
+    }
+    // Hidden atoms and the attribute edges are gone.
+    assert!(!image.contains(r#"data-type="u32""#));
+    assert!(!image.contains(r#"data-type="None""#));
+    assert!(!image.contains(r#"data-type="Color""#));
+    assert!(!image.contains(r#"data-relation="key""#));
+    // Exactly the two red leaves are filled red.
+    assert_eq!(image.matches(r#"fill="red""#).count(), 2);
+
+    let (root, l, r) = ("atom0", id_of_key(&image, 3), id_of_key(&image, 8));
+    let (rx, ry) = position(&image, root);
+    let (lx, ly) = position(&image, &l);
+    let (gx, gy) = position(&image, &r);
+    assert!(lx < rx && ly > ry, "left child should sit below-left");
+    assert!(gx > rx && gy > ry, "right child should sit below-right");
+
+    // A lone right child still leans right.
+    let (gcx, gcy) = position(&image, &id_of_key(&image, 9));
+    assert!(gcx > gx && gcy > gy);
+}
+
+/// Node id whose attribute line is `key: {key}`.
+fn id_of_key(svg: &str, key: u32) -> String {
+    let at = svg.find(&format!(">key: {key}<")).unwrap();
+    let open = svg[..at].rfind(r#"data-id=""#).unwrap() + r#"data-id=""#.len();
+    svg[open..open + svg[open..].find('"').unwrap()].to_string()
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_field(field = "cache")]
+#[edge_style(field = "items", value = "#ff0000", style = "dashed")]
+#[group(selector = "Item", name = "inventory")]
+struct Store {
+    items: Vec<Item>,
+    cache: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct Item {
+    name: String,
+}
+
+#[test]
+fn hide_field_edge_style_and_groups() {
+    let store = Store {
+        items: vec![Item { name: "a".into() }, Item { name: "b".into() }],
+        cache: vec![1],
+    };
+    let image = render_svg(&store);
+
+    assert!(!image.contains(r#"data-relation="cache""#));
+    assert!(image.contains(r##"data-relation="items"><path"##));
+    assert!(image.contains(r##"stroke="#ff0000""##));
+    assert!(image.contains("stroke-dasharray=\"6 4\""));
+    assert!(image.contains(r#"<g class="group">"#));
+    assert!(image.contains(">inventory</text>"));
+    // Sequence edges are labelled with their position.
+    assert!(image.contains(">idx[0]</text>"));
+    assert!(image.contains(">idx[1]</text>"));
+}
+
+#[derive(Serialize)]
+struct Pair {
+    near: Wrapper,
+    far: Box<Wrapper>,
+}
+
+#[derive(Serialize)]
+struct Wrapper {
+    inner: Leaf,
+}
+
+#[derive(Serialize)]
+struct Leaf {
+    v: u8,
+}
+
+#[test]
+fn horizontal_align_shares_a_row() {
+    let pair = Pair {
+        near: Wrapper {
+            inner: Leaf { v: 1 },
+        },
+        far: Box::new(Wrapper {
+            inner: Leaf { v: 2 },
+        }),
+    };
+    let inst = export_json_instance(&pair);
+    let plain = svg::to_svg(&inst, &Default::default());
+    let aligned = svg::to_svg(
+        &inst,
+        &SpytialDecoratorsBuilder::new()
+            .align("near", "horizontal", false)
+            .build(),
+    );
+
+    let root = id_labelled(&plain, "Pair");
+    let near = id_labelled(&plain, "Wrapper");
+    assert!(position(&plain, &near).1 > position(&plain, &root).1);
+    assert_eq!(position(&aligned, &near).1, position(&aligned, &root).1);
+}
+
+#[test]
+fn labels_are_escaped() {
+    #[derive(Serialize)]
+    struct Evil {
+        text: String,
+    }
+    let image = svg::to_svg(
+        &export_json_instance(&Evil {
+            text: "</text><script>alert(\"x\" & 'y')</script>".into(),
+        }),
+        &Default::default(),
+    );
+    assert!(!image.contains("<script>"));
+    assert!(image.contains("&lt;script&gt;alert(&quot;x&quot; &amp; &#39;y&#39;)"));
+}
+
+#[test]
+fn broken_selectors_are_ignored_and_cycles_terminate() {
+    use caraspace::SharedRef;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Serialize)]
+    struct Ring {
+        next: Option<SharedRef<Rc<RefCell<Ring>>>>,
+    }
+    let a = Rc::new(RefCell::new(Ring { next: None }));
+    let b = Rc::new(RefCell::new(Ring {
+        next: Some(SharedRef(a.clone())),
+    }));
+    a.borrow_mut().next = Some(SharedRef(b.clone()));
+
+    let decorators = SpytialDecoratorsBuilder::new()
+        .atom_color("{x : Ring | x->", "blue")
+        .orientation("next", vec!["below"], false)
+        .build();
+    let image = svg::to_svg(&export_json_instance(&SharedRef(a.clone())), &decorators);
+    assert_eq!(image.matches(r#"class="atom""#).count(), 2);
+    assert!(!image.contains(r#"fill="blue""#));
+
+    a.borrow_mut().next = None;
+}
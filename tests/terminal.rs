@@ -0,0 +1,150 @@
+//! Tests for the text renderer (`caraspace::terminal`).
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use caraspace::export::export_json_instance;
+use caraspace::spytial_annotations::HasSpytialDecorators;
+use caraspace::terminal::{self, Charset};
+use caraspace::{SharedRef, SpytialDecorators};
+use serde::Serialize;
+
+/// Field names sort the "wrong" way round, so child order must come from
+/// the orientation hints rather than from the relation names.
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "key")]
+#[orientation(selector = "{x, y : Node | x->y in lo}", directions = ["left", "below"])]
+#[orientation(selector = "{x, y : Node | x->y in hi}", directions = ["right", "below"])]
+#[hide_atom(selector = "None + u32")]
+#[atom_color(selector = "{x : Node | @:(x.key) = 9}", value = "red")]
+struct Node {
+    key: u32,
+    lo: Option<Box<Node>>,
+    hi: Option<Box<Node>>,
+}
+
+fn node(key: u32, lo: Option<Box<Node>>, hi: Option<Box<Node>>) -> Option<Box<Node>> {
+    Some(Box::new(Node { key, lo, hi }))
+}
+
+#[test]
+fn trees_print_left_before_right() {
+    let tree = Node {
+        key: 5,
+        lo: node(3, None, None),
+        hi: node(8, None, node(9, None, None)),
+    };
+    let text = terminal::to_text(&export_json_instance(&tree), &Node::decorators());
+    assert_eq!(
+        text,
+        "Node (key: 5)\n\
+         ├─ lo ─▶ Node (key: 3)\n\
+         └─ hi ─▶ Node (key: 8)\n   \
+            └─ hi ─▶ Node (key: 9) [red]\n"
+    );
+}
+
+/// The README's tree, with no `hide_atom` to tidy it up.
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "key")]
+#[orientation(selector = "{x, y : ReadmeNode | x->y in left}", directions = ["left", "below"])]
+#[orientation(selector = "{x, y : ReadmeNode | x->y in right}", directions = ["right", "below"])]
+struct ReadmeNode {
+    key: u32,
+    left: Option<Box<ReadmeNode>>,
+    right: Option<Box<ReadmeNode>>,
+}
+
+#[test]
+fn shared_none_leaves_and_attribute_values_keep_a_tree() {
+    let leaf = |key| {
+        Some(Box::new(ReadmeNode {
+            key,
+            left: None,
+            right: None,
+        }))
+    };
+    let tree = ReadmeNode {
+        key: 2,
+        left: leaf(1),
+        right: leaf(3),
+    };
+    let text = terminal::to_text(&export_json_instance(&tree), &ReadmeNode::decorators());
+    assert_eq!(
+        text,
+        "ReadmeNode (key: 2)\n\
+         ├─ left ─▶ ReadmeNode (key: 1)\n\
+         │  ├─ left ─▶ None\n\
+         │  └─ right ─▶ None\n\
+         └─ right ─▶ ReadmeNode (key: 3)\n   \
+            ├─ left ─▶ None\n   \
+            └─ right ─▶ None\n"
+    );
+}
+
+#[test]
+fn ascii_charset() {
+    let tree = Node {
+        key: 1,
+        lo: node(0, None, None),
+        hi: node(2, None, None),
+    };
+    let text = terminal::to_text_with(
+        &export_json_instance(&tree),
+        &Node::decorators(),
+        Charset::Ascii,
+    );
+    assert!(text.is_ascii());
+    assert!(text.contains("|- lo -> Node (key: 0)\n"));
+    assert!(text.contains("`- hi -> Node (key: 2)\n"));
+}
+
+#[derive(Serialize, SpytialDecorators)]
+#[attribute(field = "val")]
+#[hide_atom(selector = "None + i32")]
+struct ListNode {
+    val: i32,
+    next: Option<SharedRef<Rc<RefCell<ListNode>>>>,
+    #[serde(with = "caraspace::shared::weak")]
+    prev: Weak<RefCell<ListNode>>,
+}
+
+#[test]
+fn cycles_fall_back_to_an_adjacency_listing() {
+    let a = Rc::new(RefCell::new(ListNode {
+        val: 1,
+        next: None,
+        prev: Weak::new(),
+    }));
+    let b = Rc::new(RefCell::new(ListNode {
+        val: 2,
+        next: None,
+        prev: Rc::downgrade(&a),
+    }));
+    a.borrow_mut().next = Some(SharedRef(b));
+
+    let text = terminal::to_text(
+        &export_json_instance(&SharedRef(a.clone())),
+        &ListNode::decorators(),
+    );
+    assert_eq!(
+        text,
+        "[0] ListNode (val: 1)\n    └─ next ─▶ [1]\n[1] ListNode (val: 2)\n    └─ prev ─▶ [0]\n"
+    );
+    a.borrow_mut().next = None;
+}
+
+#[test]
+fn groups_are_listed_and_labels_stay_on_one_line() {
+    #[derive(Serialize, SpytialDecorators)]
+    #[group(selector = "string", name = "notes")]
+    struct Notes {
+        items: Vec<String>,
+    }
+    let notes = Notes {
+        items: vec!["two\nlines".into()],
+    };
+    let text = terminal::to_text(&export_json_instance(&notes), &Notes::decorators());
+    assert!(text.contains(r"idx[0] ─▶ two\nlines"));
+    assert!(text.ends_with("\ngroup notes: two\\nlines\n"));
+}
@@ -0,0 +1,101 @@
+//! Tests for `caraspace::Timeline`: steps, call-site labels and the
+//! embedded step data in the generated page.
+
+use caraspace::{export_json_instance, DiagramOptions, SpytialDecorators, Theme, Timeline};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, SpytialDecorators)]
+#[atom_color(selector = "Stack", value = "blue")]
+struct Stack {
+    items: Vec<u32>,
+}
+
+/// The parsed `spytial-timeline` block of a page.
+fn steps(html: &str) -> Value {
+    let open = r#"<script type="application/json" id="spytial-timeline">"#;
+    let start = html.find(open).unwrap() + open.len();
+    let end = start + html[start..].find("</script>").unwrap();
+    serde_json::from_str(&html[start..end]).unwrap()
+}
+
+#[test]
+fn records_each_snapshot_with_its_call_site() {
+    let mut stack = Stack { items: vec![] };
+    let mut timeline = Timeline::new();
+    assert!(timeline.is_empty());
+    let mut site = String::new();
+    for i in 0..3 {
+        stack.items.push(i);
+        timeline.record(&stack);
+        site = format!("{}:{}", file!(), line!() - 1);
+    }
+    timeline.record_labeled("done", &stack);
+
+    assert_eq!(timeline.len(), 4);
+    let labels: Vec<&str> = timeline.labels().collect();
+    assert_eq!(labels, [site.as_str(), &site, &site, "done"]);
+
+    let page = steps(&timeline.to_html());
+    let steps = page["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 4);
+    for (i, step) in steps.iter().enumerate().take(3) {
+        let ints = step["data"]["atoms"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|a| a["type"] == "u32")
+            .count();
+        assert_eq!(ints, i + 1, "step {i} holds its own snapshot");
+        assert!(step["spec"].as_str().unwrap().contains("atomColor"));
+    }
+}
+
+#[test]
+fn pushed_instances_keep_their_spec_and_first_step_is_shown() {
+    let mut timeline = Timeline::new();
+    timeline.push("a", export_json_instance(&vec!["</script>"]), "");
+    timeline.push("b", export_json_instance(&vec![1, 2]), "constraints: []\n");
+
+    let html = timeline.to_html();
+    let step = steps(&html);
+    assert_eq!(step["steps"][1]["spec"], "constraints: []\n");
+    assert_eq!(step["steps"][0]["label"], "a");
+    // The page opens on step one, and `</script>` stays escaped in both
+    // the shown data and the step list.
+    let open = r#"<script type="application/json" id="spytial-data">"#;
+    let data = &html[html.find(open).unwrap()..];
+    assert!(data.starts_with(&format!(r#"{open}{{"atoms":"#)));
+    assert!(data.contains(r#""label":"\u003c/script\u003e""#));
+    assert!(!html.contains(r#""</script>""#));
+}
+
+#[test]
+fn empty_timelines_render_a_plain_page() {
+    let html = Timeline::new().to_html();
+    assert!(html.contains(r#"<script type="application/json" id="spytial-timeline">null</script>"#));
+}
+
+#[test]
+fn write_saves_the_page() {
+    let mut timeline = Timeline::new();
+    timeline.record(&Stack { items: vec![7] });
+    let path = std::env::temp_dir().join(format!("caraspace-timeline-{}.html", std::process::id()));
+    timeline.write(&path).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(written, timeline.to_html());
+}
+
+#[test]
+fn options_set_the_page_title_and_theme() {
+    let mut timeline = Timeline::new();
+    timeline.record(&Stack { items: vec![1] });
+    let html = timeline.to_html_with(
+        &DiagramOptions::new()
+            .title("Stack <pushes>")
+            .theme(Theme::Dark),
+    );
+    assert!(html.contains("<title>Stack &lt;pushes&gt;</title>"));
+    assert!(html.contains(r#"data-theme="dark""#));
+}
@@ -0,0 +1,158 @@
+use caraspace::export::{export_json_instance, export_json_instance_with_decorators};
+use caraspace::spytial_annotations::{Directive, HasSpytialDecorators, SpytialDecoratorsBuilder};
+use caraspace::{selector, SpytialDecorators};
+use serde::Serialize;
+
+mod ast {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[spytial(type_name = "ast::Node")]
+    #[atom_color(selector = "Node", value = "red")]
+    pub struct Node {
+        pub op: Op,
+        pub args: Vec<Node>,
+    }
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[spytial(type_name = "ast::Op")]
+    pub enum Op {
+        Add,
+        Lit(i64),
+    }
+}
+
+mod ir {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[spytial(qualified)]
+    #[atom_color(selector = "Node", value = "blue")]
+    #[hide_atom(selector = "{x : Node | @:(x.code) = 2}")]
+    pub struct Node {
+        pub code: u8,
+        pub next: Option<Box<Node>>,
+    }
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Program {
+    source: ast::Node,
+    lowered: ir::Node,
+}
+
+fn program() -> Program {
+    Program {
+        source: ast::Node {
+            op: ast::Op::Add,
+            args: vec![ast::Node {
+                op: ast::Op::Lit(1),
+                args: Vec::new(),
+            }],
+        },
+        lowered: ir::Node {
+            code: 1,
+            next: Some(Box::new(ir::Node {
+                code: 2,
+                next: None,
+            })),
+        },
+    }
+}
+
+fn atom_color(decorators: &[Directive], value: &str) -> String {
+    decorators
+        .iter()
+        .find_map(|d| match d {
+            Directive::AtomColor(c) if c.atom_color.value == value => {
+                Some(c.atom_color.selector.clone())
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn qualified_types_name_their_atoms_and_selectors_alike() {
+    let decorators = Program::decorators();
+    let instance = export_json_instance(&program());
+
+    let types = |ty: &str| instance.atoms.iter().filter(|a| a.r#type == ty).count();
+    assert_eq!(types("ast::Node"), 2);
+    assert_eq!(types("type_names::ir::Node"), 2);
+    assert_eq!(types("Node"), 0);
+    // enums are named the same way, unit variants included
+    assert_eq!(types("ast::Op"), 2);
+
+    let red = atom_color(&decorators.directives, "red");
+    let blue = atom_color(&decorators.directives, "blue");
+    assert_eq!(red, "ast::Node");
+    assert_eq!(blue, "type_names::ir::Node");
+    assert_eq!(selector::evaluate(&red, &instance).unwrap().len(), 2);
+    assert_eq!(selector::evaluate(&blue, &instance).unwrap().len(), 2);
+    let issues = selector::check(&decorators, &instance);
+    assert!(issues.is_empty(), "{issues:?}");
+}
+
+#[test]
+fn relations_carry_the_qualified_type() {
+    Program::decorators();
+    let instance = export_json_instance(&program());
+    let next = instance
+        .relations
+        .iter()
+        .find(|r| r.name == "next")
+        .unwrap();
+    assert_eq!(next.types[0], "type_names::ir::Node");
+    let args = instance
+        .relations
+        .iter()
+        .find(|r| r.name == "args")
+        .unwrap();
+    assert_eq!(args.types[0], "ast::Node");
+}
+
+#[test]
+fn serialization_collects_decorators_of_the_right_same_named_type() {
+    Program::decorators();
+    let (_, decorators) = export_json_instance_with_decorators(&program(), "Program");
+    let colors: Vec<&str> = decorators
+        .directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::AtomColor(c) => Some(c.atom_color.value.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(colors, ["red", "blue"]);
+}
+
+#[test]
+fn renaming_touches_only_the_type_name() {
+    let decorators = SpytialDecoratorsBuilder::new()
+        .atom_color("{x : Node | @:(x.kind) = 'Node'}", "red")
+        .hide_atom("NodeList + ast::Node + Node.next")
+        .projection("Node")
+        .rename_type("Node", "ast::Node")
+        .build();
+    let selectors: Vec<String> = decorators
+        .directives
+        .iter()
+        .map(|d| match d {
+            Directive::AtomColor(c) => c.atom_color.selector.clone(),
+            Directive::HideAtom(h) => h.hide_atom.selector.clone(),
+            Directive::Projection(p) => p.projection.sig.clone(),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        selectors,
+        [
+            "{x : ast::Node | @:(x.kind) = 'Node'}",
+            "NodeList + ast::Node + ast::Node.next",
+            "ast::Node",
+        ]
+    );
+}
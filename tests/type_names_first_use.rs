@@ -0,0 +1,87 @@
+//! Qualified type names are registered when a type's `decorators()` first
+//! runs. This file is its own test binary, so nothing has called them yet:
+//! each test reaches its type through an entry point that exports.
+
+use caraspace::sink::MemorySink;
+use caraspace::{diff, render_svg, render_with, DiagramOptions, SpytialDecorators};
+use serde::Serialize;
+
+mod before_diff {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators, Clone)]
+    #[spytial(qualified)]
+    pub struct Node {
+        pub key: u32,
+        pub next: Option<Box<Node>>,
+    }
+}
+
+mod before_svg {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[spytial(type_name = "svg::Node")]
+    pub struct Node {
+        pub key: u32,
+    }
+}
+
+mod before_render {
+    use caraspace::SpytialDecorators;
+    use serde::Serialize;
+
+    #[derive(Serialize, SpytialDecorators)]
+    #[spytial(type_name = "render::Node")]
+    pub struct Node {
+        pub key: u32,
+    }
+}
+
+/// Holds a qualified type without being qualified itself.
+#[derive(Serialize, SpytialDecorators)]
+struct Holder {
+    inner: before_render::Node,
+}
+
+#[test]
+fn diff_registers_names_before_exporting() {
+    let before = before_diff::Node { key: 1, next: None };
+    let mut after = before.clone();
+    after.next = Some(Box::new(before.clone()));
+    let d = diff(&before, &after);
+    let types: Vec<&str> = d.instance.atoms.iter().map(|a| a.r#type.as_str()).collect();
+    assert!(
+        types.contains(&"type_names_first_use::before_diff::Node"),
+        "{types:?}"
+    );
+    assert!(!types.contains(&"Node"), "{types:?}");
+}
+
+#[test]
+fn render_svg_registers_names_before_exporting() {
+    let image = render_svg(&before_svg::Node { key: 1 });
+    assert!(image.contains("svg::Node"), "{image}");
+}
+
+#[test]
+fn render_registers_names_of_field_types_before_exporting() {
+    let sink = MemorySink::new();
+    let options = DiagramOptions::new().sink(sink);
+    let rendered = render_with(
+        &Holder {
+            inner: before_render::Node { key: 1 },
+        },
+        &options,
+    )
+    .unwrap();
+    let types: Vec<&str> = rendered
+        .instance
+        .atoms
+        .iter()
+        .map(|a| a.r#type.as_str())
+        .collect();
+    assert!(types.contains(&"render::Node"), "{types:?}");
+}
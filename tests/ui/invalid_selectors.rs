@@ -0,0 +1,51 @@
+//! Selectors `caraspace::selector` rejects. `tests/derive_checks.rs` parses
+//! each one at runtime and expects the same message in the `.stderr`.
+
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "Node +")]
+struct TrailingOperator;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "Node key")]
+struct TwoNames;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "(Node")]
+struct UnclosedParen;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "Node # Node")]
+struct UnknownCharacter;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "{x : Node | x.key = \"one}")]
+struct UnterminatedString;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "{x, y : Node | x->y in }")]
+struct MissingRhs;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "{x : Node x.key}")]
+struct MissingBar;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "{ : Node | some Node}")]
+struct NoVariables;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "{x : Node | x.key}")]
+struct ExpressionAsFormula;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "@Node")]
+struct LabelWithoutColon;
+
+#[derive(Serialize, SpytialDecorators)]
+#[hide_atom(selector = "")]
+struct Empty;
+
+fn main() {}
@@ -0,0 +1,21 @@
+//! Names one typo away from a field or type of a struct whose vocabulary
+//! the derive knows in full.
+
+use caraspace::SpytialDecorators;
+use serde::Serialize;
+
+#[derive(Serialize, SpytialDecorators)]
+#[orientation(selector = "{x, y : Nod | x->y in lft}", directions = ["below"])]
+#[attribute(field = "kye")]
+struct Node {
+    key: u32,
+    left: Option<Box<Node>>,
+}
+
+#[derive(Serialize, SpytialDecorators)]
+struct Leaf {
+    #[spytial(atribute)]
+    key: u32,
+}
+
+fn main() {}